@@ -0,0 +1,439 @@
+//! 归档导出/导入（带图片的 zip 备份，可在其他设备上恢复）
+//!
+//! 归档结构：
+//! - `manifest.json`: 格式标识、版本、导出时间、导出条件、记录数和用到的标签颜色
+//! - `items.jsonl`: 每行一条记录；图片记录的 content 为归档内路径 `images/{文件名}`，不含缩略图
+//! - `images/{sha256}.{ext}`: 原图（启用静态加密时以解密后的内容写入）
+//!
+//! 导出分两遍：先按批读取记录写入 items.jsonl 并收集图片，再逐个写入图片，
+//! 不会把全部记录或图片一次读入内存。导入时先扫描一遍记录找出用到的图片，只恢复这些图片
+//! （不会在图片存储中留下没有记录引用的文件），再按行读取记录并改写图片路径。
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::clipboard::ClipboardManager;
+use crate::image_handler::ImageStore;
+use crate::models::{ClipboardContentType, ClipboardItem, ExportReport, ExportRequest, ImportReport};
+use crate::storage::Database;
+
+/// 归档格式标识
+pub const ARCHIVE_FORMAT: &str = "paste-library-archive";
+/// 当前归档版本（只能导入不高于该版本的归档）
+pub const ARCHIVE_VERSION: u32 = 1;
+
+const MANIFEST_FILE: &str = "manifest.json";
+const ITEMS_FILE: &str = "items.jsonl";
+const IMAGES_DIR: &str = "images/";
+
+/// 导出时每批从数据库读取、导入时每个事务写入的记录数
+const BATCH_SIZE: usize = 500;
+
+/// 导入时单张图片的最大字节数（超过时跳过，避免损坏或恶意构造的归档占满内存）
+const MAX_IMAGE_BYTES: u64 = 128 * 1024 * 1024;
+
+/// 归档清单
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Manifest {
+    pub format: String,
+    pub version: u32,
+    /// 导出时的应用版本
+    pub app_version: String,
+    pub exported_at: DateTime<Utc>,
+    /// 导出条件
+    #[serde(default)]
+    pub filter: ExportRequest,
+    pub item_count: usize,
+    pub image_count: usize,
+    /// 导出记录用到的标签及颜色（名称 -> #RRGGBB）
+    #[serde(default)]
+    pub tags: BTreeMap<String, String>,
+}
+
+/// 判断文件是否为 zip 归档（用于兼容旧版 JSON 备份）
+pub fn is_archive(path: &Path) -> Result<bool, String> {
+    let mut magic = [0u8; 4];
+    let mut file = File::open(path).map_err(|e| format!("打开 {} 失败: {}", path.display(), e))?;
+    let read = file.read(&mut magic).map_err(|e| format!("读取 {} 失败: {}", path.display(), e))?;
+    Ok(read == magic.len() && magic == *b"PK\x03\x04")
+}
+
+/// 导出归档到 path（先写入临时文件，完成后再替换，失败时不会留下不完整的归档）
+pub fn export_archive(
+    database: &Database,
+    image_store: &ImageStore,
+    request: &ExportRequest,
+    path: &Path,
+) -> Result<ExportReport, String> {
+    let mut temp_name = path.as_os_str().to_owned();
+    temp_name.push(".part");
+    let temp_path = Path::new(&temp_name);
+
+    let result = write_archive(database, image_store, request, temp_path)
+        .and_then(|report| {
+            std::fs::rename(temp_path, path)
+                .map_err(|e| format!("写入 {} 失败: {}", path.display(), e))
+                .map(|()| report)
+        });
+    if result.is_err() {
+        let _ = std::fs::remove_file(temp_path);
+    }
+    result
+}
+
+fn write_archive(
+    database: &Database,
+    image_store: &ImageStore,
+    request: &ExportRequest,
+    path: &Path,
+) -> Result<ExportReport, String> {
+    let file = File::create(path).map_err(|e| format!("创建 {} 失败: {}", path.display(), e))?;
+    let mut zip = ZipWriter::new(BufWriter::new(file));
+    let deflated = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    // 图片本身已压缩，直接存储
+    let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+    let zip_error = |e: zip::result::ZipError| format!("写入归档失败: {}", e);
+    let io_error = |e: std::io::Error| format!("写入归档失败: {}", e);
+
+    let mut report = ExportReport::default();
+    // 原图路径 -> 归档内路径（相同图片只写一份）
+    let mut images: HashMap<String, String> = HashMap::new();
+    let mut image_names: HashSet<String> = HashSet::new();
+    let mut tag_names: HashSet<String> = HashSet::new();
+
+    // 第一遍：记录
+    zip.start_file(ITEMS_FILE, deflated).map_err(zip_error)?;
+    let mut after_id = 0;
+    loop {
+        let batch = database
+            .get_export_batch(request, after_id, BATCH_SIZE as i64)
+            .map_err(|e| e.to_string())?;
+        let Some(last) = batch.last() else {
+            break;
+        };
+        after_id = last.id;
+
+        for mut item in batch {
+            if item.content_type == ClipboardContentType::Image {
+                if !Path::new(&item.content).is_file() {
+                    report.missing_images += 1;
+                    continue;
+                }
+                let archive_path = match images.get(&item.content) {
+                    Some(archive_path) => archive_path.clone(),
+                    None => {
+                        let archive_path = image_archive_path(&item.content, &mut image_names);
+                        images.insert(item.content.clone(), archive_path.clone());
+                        archive_path
+                    }
+                };
+                item.content = archive_path;
+                item.thumbnail_path = None;
+            }
+            if let Some(tags) = &item.tags {
+                tag_names.extend(tags.iter().cloned());
+            }
+
+            let line = serde_json::to_string(&item).map_err(|e| format!("序列化记录失败: {}", e))?;
+            zip.write_all(line.as_bytes()).map_err(io_error)?;
+            zip.write_all(b"\n").map_err(io_error)?;
+            report.items += 1;
+        }
+    }
+
+    // 第二遍：图片
+    for (original_path, archive_path) in &images {
+        let bytes = image_store.read_bytes(original_path)?;
+        zip.start_file(archive_path.as_str(), stored).map_err(zip_error)?;
+        zip.write_all(&bytes).map_err(io_error)?;
+        report.images += 1;
+    }
+
+    let tags = database
+        .get_tags()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|tag| tag_names.contains(&tag.name))
+        .map(|tag| (tag.name, tag.color))
+        .collect();
+    let manifest = Manifest {
+        format: ARCHIVE_FORMAT.to_string(),
+        version: ARCHIVE_VERSION,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        exported_at: Utc::now(),
+        filter: request.clone(),
+        item_count: report.items,
+        image_count: report.images,
+        tags,
+    };
+    zip.start_file(MANIFEST_FILE, deflated).map_err(zip_error)?;
+    serde_json::to_writer_pretty(&mut zip, &manifest).map_err(|e| format!("写入清单失败: {}", e))?;
+
+    zip.finish()
+        .map_err(zip_error)?
+        .flush()
+        .map_err(io_error)?;
+    Ok(report)
+}
+
+// 图片在归档内的路径：沿用存储中的文件名（内容哈希），重名时加序号
+fn image_archive_path(original_path: &str, used: &mut HashSet<String>) -> String {
+    let file_name = Path::new(original_path)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| "image".to_string());
+    let mut name = file_name.clone();
+    let mut n = 1;
+    while !used.insert(name.clone()) {
+        name = format!("{}-{}", n, file_name);
+        n += 1;
+    }
+    format!("{}{}", IMAGES_DIR, name)
+}
+
+/// 从归档导入记录，图片恢复到图片存储（按当前的加密设置写入）并改写记录中的路径
+pub fn import_archive(database: &Database, image_store: &ImageStore, path: &Path) -> Result<ImportReport, String> {
+    let file = File::open(path).map_err(|e| format!("打开 {} 失败: {}", path.display(), e))?;
+    let mut zip = ZipArchive::new(BufReader::new(file)).map_err(|e| format!("无法读取归档: {}", e))?;
+
+    let manifest: Manifest = {
+        let entry = zip
+            .by_name(MANIFEST_FILE)
+            .map_err(|_| "不是有效的 Paste Library 归档：缺少 manifest.json".to_string())?;
+        serde_json::from_reader(entry).map_err(|e| format!("清单格式错误: {}", e))?
+    };
+    if manifest.format != ARCHIVE_FORMAT {
+        return Err(format!("不是有效的 Paste Library 归档：未知格式 {}", manifest.format));
+    }
+    if manifest.version > ARCHIVE_VERSION {
+        return Err(format!(
+            "归档版本 {} 高于当前支持的版本 {}，请升级应用后再导入",
+            manifest.version, ARCHIVE_VERSION
+        ));
+    }
+
+    let mut report = ImportReport::default();
+
+    for (name, color) in &manifest.tags {
+        database.import_tag(name, color).map_err(|e| e.to_string())?;
+    }
+
+    // 先恢复记录用到的图片：归档内路径 -> (原图路径, 缩略图路径)
+    let referenced = referenced_images(&mut zip)?;
+    let mut images: HashMap<String, (String, String)> = HashMap::new();
+    for index in 0..zip.len() {
+        let entry = zip.by_index(index).map_err(|e| format!("读取归档失败: {}", e))?;
+        let name = entry.name().to_string();
+        if entry.is_dir() || !referenced.contains(&name) {
+            continue;
+        }
+        if entry.size() > MAX_IMAGE_BYTES {
+            eprintln!("跳过图片 {}：超过 {} 字节", name, MAX_IMAGE_BYTES);
+            continue;
+        }
+        // 声明的大小可能与实际不符，读取时同样限制
+        let mut bytes = Vec::new();
+        entry
+            .take(MAX_IMAGE_BYTES + 1)
+            .read_to_end(&mut bytes)
+            .map_err(|e| format!("读取归档中的 {} 失败: {}", name, e))?;
+        if bytes.len() as u64 > MAX_IMAGE_BYTES {
+            eprintln!("跳过图片 {}：超过 {} 字节", name, MAX_IMAGE_BYTES);
+            continue;
+        }
+        match image_store.store_bytes(&bytes) {
+            Ok(stored) => {
+                images.insert(name, (stored.original_path, stored.thumbnail_path));
+                report.images += 1;
+            }
+            Err(e) => eprintln!("恢复图片 {} 失败: {}", name, e),
+        }
+    }
+
+    let entry = zip
+        .by_name(ITEMS_FILE)
+        .map_err(|_| "不是有效的 Paste Library 归档：缺少 items.jsonl".to_string())?;
+    let mut batch = Vec::with_capacity(BATCH_SIZE);
+    for (line_number, line) in BufReader::new(entry).lines().enumerate() {
+        let line = line.map_err(|e| format!("读取归档失败: {}", e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let mut item: ClipboardItem = match serde_json::from_str(&line) {
+            Ok(item) => item,
+            Err(e) => {
+                eprintln!("跳过第 {} 行无法解析的记录: {}", line_number + 1, e);
+                report.skipped += 1;
+                continue;
+            }
+        };
+
+        if item.content_type == ClipboardContentType::Image {
+            let Some((original_path, thumbnail_path)) = images.get(&item.content) else {
+                eprintln!("跳过图片记录：归档中缺少 {}", item.content);
+                report.skipped += 1;
+                continue;
+            };
+            item.content = original_path.clone();
+            item.thumbnail_path = Some(thumbnail_path.clone());
+            // 归档中的哈希基于源机器上的路径，按新路径重新计算，之后再次复制同一图片时才能合并
+            item.content_hash = ClipboardManager::content_hash(&item.content, item.file_paths.as_deref());
+        }
+
+        batch.push(item);
+        if batch.len() >= BATCH_SIZE {
+            import_batch(database, &mut batch, &mut report)?;
+        }
+    }
+    import_batch(database, &mut batch, &mut report)?;
+
+    Ok(report)
+}
+
+// 图片记录中引用的归档内路径（只解析类型和内容两个字段）
+fn referenced_images(zip: &mut ZipArchive<BufReader<File>>) -> Result<HashSet<String>, String> {
+    #[derive(Deserialize)]
+    struct ImageReference {
+        content_type: ClipboardContentType,
+        content: String,
+    }
+
+    let entry = zip
+        .by_name(ITEMS_FILE)
+        .map_err(|_| "不是有效的 Paste Library 归档：缺少 items.jsonl".to_string())?;
+    let mut referenced = HashSet::new();
+    for line in BufReader::new(entry).lines() {
+        let line = line.map_err(|e| format!("读取归档失败: {}", e))?;
+        if let Ok(reference) = serde_json::from_str::<ImageReference>(&line) {
+            if reference.content_type == ClipboardContentType::Image && reference.content.starts_with(IMAGES_DIR) {
+                referenced.insert(reference.content);
+            }
+        }
+    }
+    Ok(referenced)
+}
+
+// 写入一批记录（与旧版导入相同：重复内容合并到已有记录，时间取归档中的时间）
+fn import_batch(database: &Database, batch: &mut Vec<ClipboardItem>, report: &mut ImportReport) -> Result<(), String> {
+    if batch.is_empty() {
+        return Ok(());
+    }
+    for result in database.import_items(batch).map_err(|e| e.to_string())? {
+        match result {
+            Ok(_) => report.imported += 1,
+            Err(e) => {
+                eprintln!("导入记录失败: {}", e);
+                report.skipped += 1;
+            }
+        }
+    }
+    batch.clear();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ExportRequest;
+
+    fn item(content_type: ClipboardContentType, content: &str, thumbnail_path: Option<String>) -> ClipboardItem {
+        ClipboardItem {
+            id: 0,
+            content_type,
+            content: content.to_string(),
+            created_at: Utc::now(),
+            content_hash: format!("hash-{}", content),
+            text_content: None,
+            metadata: None,
+            file_paths: None,
+            thumbnail_path,
+            tags: Some(vec!["备份".to_string()]),
+            is_sensitive: false,
+            expires_at: None,
+            source_app: None,
+        }
+    }
+
+    fn tiny_png() -> Vec<u8> {
+        let image = image::RgbaImage::from_pixel(3, 2, image::Rgba([0, 128, 255, 255]));
+        let mut out = std::io::Cursor::new(Vec::new());
+        image.write_to(&mut out, image::ImageFormat::Png).unwrap();
+        out.into_inner()
+    }
+
+    #[test]
+    fn export_then_import_round_trip() {
+        let source_dir = tempfile::tempdir().unwrap();
+        let database = Database::new(source_dir.path().to_path_buf()).unwrap();
+        let image_store = ImageStore::new(source_dir.path());
+        let png = tiny_png();
+        let stored = image_store.store_bytes(&png).unwrap();
+        database
+            .import_items(&[
+                item(ClipboardContentType::Text, "hello", None),
+                item(ClipboardContentType::Image, &stored.original_path, Some(stored.thumbnail_path)),
+            ])
+            .unwrap();
+
+        let archive_path = source_dir.path().join("backup.zip");
+        let exported = export_archive(&database, &image_store, &ExportRequest::default(), &archive_path).unwrap();
+        assert_eq!((exported.items, exported.images, exported.missing_images), (2, 1, 0));
+        assert!(is_archive(&archive_path).unwrap());
+
+        let target_dir = tempfile::tempdir().unwrap();
+        let target = Database::new(target_dir.path().to_path_buf()).unwrap();
+        let target_store = ImageStore::new(target_dir.path());
+        let report = import_archive(&target, &target_store, &archive_path).unwrap();
+        assert_eq!((report.imported, report.images, report.skipped), (2, 1, 0));
+
+        let items = target.get_history(10, 0).unwrap();
+        assert_eq!(items.len(), 2);
+        let image = items
+            .iter()
+            .find(|item| item.content_type == ClipboardContentType::Image)
+            .unwrap();
+        assert!(target_store.contains(&image.content));
+        assert_eq!(target_store.read_bytes(&image.content).unwrap(), png);
+        // 与在目标机器上复制同一图片时的哈希一致
+        let copied = target_store.store_bytes(&png).unwrap();
+        assert_eq!(image.content_hash, ClipboardManager::content_hash(&copied.original_path, None));
+        assert!(items.iter().all(|item| item.tags.as_deref() == Some(&["备份".to_string()][..])));
+    }
+
+    #[test]
+    fn unreferenced_images_are_not_restored() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive_path = dir.path().join("orphan.zip");
+        let manifest = Manifest {
+            format: ARCHIVE_FORMAT.to_string(),
+            version: ARCHIVE_VERSION,
+            app_version: "test".to_string(),
+            exported_at: Utc::now(),
+            filter: ExportRequest::default(),
+            item_count: 1,
+            image_count: 1,
+            tags: BTreeMap::new(),
+        };
+        let mut zip = ZipWriter::new(File::create(&archive_path).unwrap());
+        zip.start_file(MANIFEST_FILE, SimpleFileOptions::default()).unwrap();
+        zip.write_all(&serde_json::to_vec(&manifest).unwrap()).unwrap();
+        zip.start_file(ITEMS_FILE, SimpleFileOptions::default()).unwrap();
+        let line = serde_json::to_string(&item(ClipboardContentType::Text, "only text", None)).unwrap();
+        zip.write_all(line.as_bytes()).unwrap();
+        zip.start_file("images/orphan.png", SimpleFileOptions::default()).unwrap();
+        zip.write_all(&tiny_png()).unwrap();
+        zip.finish().unwrap();
+
+        let database = Database::new(dir.path().to_path_buf()).unwrap();
+        let image_store = ImageStore::new(dir.path());
+        let report = import_archive(&database, &image_store, &archive_path).unwrap();
+        assert_eq!((report.imported, report.images, report.skipped), (1, 0, 0));
+        assert!(image_store.list_files().is_empty());
+    }
+}
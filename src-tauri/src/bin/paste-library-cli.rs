@@ -0,0 +1,5 @@
+// 命令行工具入口，用法见 `paste-library-cli --help`
+fn main() {
+    let args = std::env::args().skip(1).collect();
+    std::process::exit(paste_library_lib::cli::run(args))
+}
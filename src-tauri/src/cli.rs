@@ -0,0 +1,728 @@
+//! 命令行工具（paste-library-cli）
+//! 直接打开 GUI 使用的 clipboard.db，供脚本和编辑器查询、添加、复制历史记录。
+//! 可以与正在运行的 GUI 同时使用：两边都通过 SQLite 的文件锁串行写入（见 storage 的 busy_timeout）。
+
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::clipboard::ClipboardManager;
+use crate::clipboard_watcher::ClipboardWatcher;
+use crate::encryption::KeyMaterial;
+use crate::image_handler::ImageStore;
+use crate::models::{
+    AdvancedSearchRequest, AppSettings, ClipboardContentType, ClipboardItem, ExportReport, ExportRequest,
+    ExternalImportReport, ImportReport, ImportSource, Tag,
+};
+use crate::platform::{self, NoSourceApp};
+use crate::storage::DB_FILE;
+use crate::template::{self, TemplateContext};
+
+/// 与 tauri.conf.json 的 identifier 一致（GUI 数据目录名）
+const APP_IDENTIFIER: &str = "com.lanxiuyun.paste-library";
+/// 指定数据目录的环境变量（--data-dir 优先）
+const DATA_DIR_ENV: &str = "PASTE_LIBRARY_DATA_DIR";
+/// 口令加密数据库的口令
+const PASSPHRASE_ENV: &str = "PASTE_LIBRARY_PASSPHRASE";
+/// list/search 默认返回的条数
+const DEFAULT_LIMIT: i64 = 50;
+/// 纯文本输出时预览的最大字符数
+const PREVIEW_CHARS: usize = 80;
+
+const USAGE: &str = "\
+用法: paste-library-cli [--json] [--data-dir <目录>] <命令> [参数]
+
+命令:
+  list [--tag <标签>]... [--type <类型>]... [--limit <N>] [--offset <N>]
+                        列出历史记录（最新的在前）
+  search <关键词>... [--tag <标签>]... [--type <类型>]... [--limit <N>]
+                        搜索历史记录
+  get <ID>              输出记录内容（--json 时输出完整记录）
+  copy <ID>             复制记录到剪贴板（片段会展开占位符）
+  add [<文本> | -] [--tag <标签>]...
+                        添加文本记录，不提供文本或为 - 时读取标准输入
+  delete <ID>           删除记录
+  tags                  列出标签及记录数
+  export [--output <文件>]
+                        导出历史记录（JSON），不指定文件时输出到标准输出
+  export --output <文件.zip> [--tag <标签>]... [--type <类型>]... [--since <日期>] [--until <日期>]
+         [--include-sensitive]
+                        导出 zip 归档（含图片）；--tag 为带有任一标签，--since 含当天、--until 不含当天，
+                        日期为 YYYY-MM-DD（本地时间）或 RFC 3339；默认不导出敏感记录
+  import <文件>         导入 zip 归档或 JSON 备份
+  import --from <来源> <路径>
+                        从其他剪贴板管理器导入，来源为 ditto（Ditto.db）、copyq（导出的 .cpq）、
+                        maccy（Storage.sqlite）或 snippets（片段目录，或以 --- 行分隔的文本文件）
+
+选项:
+  --json                以 JSON 输出结果，错误以 {\"error\": ...} 输出到标准错误
+  --data-dir <目录>     数据目录（默认与 GUI 相同，也可通过 PASTE_LIBRARY_DATA_DIR 指定）
+  -h, --help            显示帮助
+
+加密数据库（口令模式）需要通过环境变量 PASTE_LIBRARY_PASSPHRASE 提供口令。
+Linux X11 下 copy 会等到其他程序接管剪贴板后才退出（与 xclip 相同），可在命令末尾加 & 放到后台。
+
+退出码: 0 成功，1 执行失败，2 参数错误，3 记录不存在，4 数据库已加密且无法解锁";
+
+/// 命令执行失败（不同的失败对应不同的退出码，便于脚本判断）
+#[derive(Debug)]
+pub enum CliError {
+    /// 参数错误
+    Usage(String),
+    /// 记录不存在
+    NotFound(String),
+    /// 数据库已加密且无法解锁
+    Locked(String),
+    /// 其他错误
+    Failed(String),
+}
+
+impl CliError {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::Failed(_) => 1,
+            Self::Usage(_) => 2,
+            Self::NotFound(_) => 3,
+            Self::Locked(_) => 4,
+        }
+    }
+
+    fn message(&self) -> &str {
+        match self {
+            Self::Usage(message) | Self::NotFound(message) | Self::Locked(message) | Self::Failed(message) => message,
+        }
+    }
+}
+
+impl From<String> for CliError {
+    fn from(message: String) -> Self {
+        Self::Failed(message)
+    }
+}
+
+/// 子命令
+#[derive(Debug, PartialEq, Eq)]
+enum Command {
+    List,
+    Search(String),
+    Get(i64),
+    Copy(i64),
+    /// None 表示从标准输入读取
+    Add(Option<String>),
+    Delete(i64),
+    Tags,
+    Export,
+    Import(PathBuf),
+}
+
+/// 命令行解析结果（显示帮助不需要打开数据库）
+#[derive(Debug)]
+enum Invocation {
+    Help,
+    Run(Command, Options),
+}
+
+/// 全局选项和各命令共用的过滤选项
+#[derive(Debug, Default)]
+struct Options {
+    json: bool,
+    data_dir: Option<PathBuf>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+    tags: Vec<String>,
+    types: Vec<ClipboardContentType>,
+    output: Option<PathBuf>,
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+    include_sensitive: bool,
+    from: Option<ImportSource>,
+}
+
+/// 命令的执行结果
+enum Output {
+    Items(Vec<ClipboardItem>),
+    /// 新添加的记录（纯文本输出 ID）
+    Added(ClipboardItem),
+    /// 记录内容（纯文本输出内容本身）
+    Content(ClipboardItem),
+    Tags(Vec<Tag>),
+    /// 操作成功，无内容输出（--json 时输出 {"id": ...}）
+    Done(i64),
+    /// 原样输出
+    Raw(String),
+    Exported(ExportReport),
+    Imported(ImportReport),
+    ImportedExternal(ExternalImportReport),
+}
+
+/// 执行命令行，返回退出码
+pub fn run(args: Vec<String>) -> i32 {
+    let json = args.iter().any(|arg| arg == "--json");
+    let result = parse_args(args).and_then(|invocation| {
+        let (command, options) = match invocation {
+            Invocation::Help => return print_output(Output::Raw(format!("{}\n", USAGE)), false),
+            Invocation::Run(command, options) => (command, options),
+        };
+        let hold_clipboard = matches!(command, Command::Copy(_));
+        let output = execute(command, &options)?;
+        print_output(output, options.json)?;
+        if hold_clipboard {
+            if let Err(e) = platform::hold_clipboard_until_replaced() {
+                eprintln!("等待剪贴板被接管失败，退出后剪贴板内容可能失效: {}", e);
+            }
+        }
+        Ok(())
+    });
+
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            if json {
+                eprintln!("{}", serde_json::json!({ "error": e.message() }));
+            } else {
+                eprintln!("错误: {}", e.message());
+                if matches!(e, CliError::Usage(_)) {
+                    eprintln!("运行 paste-library-cli --help 查看用法");
+                }
+            }
+            e.exit_code()
+        }
+    }
+}
+
+fn parse_args(args: Vec<String>) -> Result<Invocation, CliError> {
+    let mut options = Options::default();
+    let mut positionals = Vec::new();
+    let mut iter = args.into_iter();
+
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--json" => options.json = true,
+            "-h" | "--help" => return Ok(Invocation::Help),
+            "--data-dir" => options.data_dir = Some(PathBuf::from(option_value(&mut iter, &arg)?)),
+            "--limit" | "-n" => options.limit = Some(parse_number(&arg, &option_value(&mut iter, &arg)?)?),
+            "--offset" => options.offset = Some(parse_number(&arg, &option_value(&mut iter, &arg)?)?),
+            "--tag" | "-t" => options.tags.push(option_value(&mut iter, &arg)?),
+            "--type" => options.types.push(parse_content_type(&option_value(&mut iter, &arg)?)?),
+            "--output" | "-o" => options.output = Some(PathBuf::from(option_value(&mut iter, &arg)?)),
+            "--since" => options.since = Some(parse_date(&arg, &option_value(&mut iter, &arg)?)?),
+            "--until" => options.until = Some(parse_date(&arg, &option_value(&mut iter, &arg)?)?),
+            "--include-sensitive" => options.include_sensitive = true,
+            "--from" => options.from = Some(parse_import_source(&option_value(&mut iter, &arg)?)?),
+            // -- 之后的参数都按位置参数处理（如以 - 开头的文本）
+            "--" => positionals.extend(iter.by_ref()),
+            "-" => positionals.push(arg),
+            _ if arg.starts_with('-') => return Err(CliError::Usage(format!("未知选项: {}", arg))),
+            _ => positionals.push(arg),
+        }
+    }
+
+    let mut positionals = positionals.into_iter();
+    let Some(name) = positionals.next() else {
+        return Ok(Invocation::Help);
+    };
+    let rest: Vec<String> = positionals.collect();
+
+    let command = match name.as_str() {
+        "list" => no_arguments(&name, &rest, Command::List)?,
+        "search" => {
+            let query = rest.join(" ");
+            if query.trim().is_empty() {
+                return Err(CliError::Usage("search 需要关键词".to_string()));
+            }
+            Command::Search(query)
+        }
+        "get" => Command::Get(single_id(&name, &rest)?),
+        "copy" => Command::Copy(single_id(&name, &rest)?),
+        "delete" => Command::Delete(single_id(&name, &rest)?),
+        "add" => match rest.as_slice() {
+            [] => Command::Add(None),
+            [text] if text == "-" => Command::Add(None),
+            [text] => Command::Add(Some(text.clone())),
+            _ => return Err(CliError::Usage("add 只接受一个文本参数，包含空格时请加引号".to_string())),
+        },
+        "tags" => no_arguments(&name, &rest, Command::Tags)?,
+        "export" => no_arguments(&name, &rest, Command::Export)?,
+        "import" => match rest.as_slice() {
+            [path] => Command::Import(PathBuf::from(path)),
+            _ => return Err(CliError::Usage("import 需要一个文件路径".to_string())),
+        },
+        "help" => return Ok(Invocation::Help),
+        _ => return Err(CliError::Usage(format!("未知命令: {}", name))),
+    };
+    Ok(Invocation::Run(command, options))
+}
+
+fn option_value(iter: &mut impl Iterator<Item = String>, option: &str) -> Result<String, CliError> {
+    iter.next()
+        .ok_or_else(|| CliError::Usage(format!("选项 {} 需要一个值", option)))
+}
+
+fn parse_number(option: &str, value: &str) -> Result<i64, CliError> {
+    value
+        .parse::<i64>()
+        .ok()
+        .filter(|n| *n >= 0)
+        .ok_or_else(|| CliError::Usage(format!("选项 {} 需要非负整数: {}", option, value)))
+}
+
+fn parse_content_type(value: &str) -> Result<ClipboardContentType, CliError> {
+    serde_json::from_value(serde_json::Value::String(value.to_lowercase()))
+        .map_err(|_| CliError::Usage(format!("未知类型: {}（可用: text/html/rtf/image/file/folder/files/snippet）", value)))
+}
+
+fn parse_import_source(value: &str) -> Result<ImportSource, CliError> {
+    serde_json::from_value(serde_json::Value::String(value.to_lowercase()))
+        .map_err(|_| CliError::Usage(format!("未知来源: {}（可用: ditto/copyq/maccy/snippets）", value)))
+}
+
+// 日期参数：YYYY-MM-DD 按本地时间当天 0 点，其余按 RFC 3339 解析
+fn parse_date(option: &str, value: &str) -> Result<DateTime<Utc>, CliError> {
+    if let Ok(date) = chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        let local = date
+            .and_hms_opt(0, 0, 0)
+            .and_then(|time| time.and_local_timezone(chrono::Local).earliest());
+        if let Some(local) = local {
+            return Ok(local.with_timezone(&Utc));
+        }
+    }
+    DateTime::parse_from_rfc3339(value)
+        .map(|time| time.with_timezone(&Utc))
+        .map_err(|_| CliError::Usage(format!("选项 {} 需要日期（YYYY-MM-DD 或 RFC 3339）: {}", option, value)))
+}
+
+fn no_arguments(name: &str, rest: &[String], command: Command) -> Result<Command, CliError> {
+    if rest.is_empty() {
+        Ok(command)
+    } else {
+        Err(CliError::Usage(format!("{} 不接受参数: {}", name, rest.join(" "))))
+    }
+}
+
+fn single_id(name: &str, rest: &[String]) -> Result<i64, CliError> {
+    match rest {
+        [id] => id
+            .parse()
+            .map_err(|_| CliError::Usage(format!("无效的记录 ID: {}", id))),
+        _ => Err(CliError::Usage(format!("{} 需要一个记录 ID", name))),
+    }
+}
+
+/// GUI 的数据目录（与 Tauri 的 app_local_data_dir 相同）
+fn default_data_dir() -> Option<PathBuf> {
+    #[cfg(target_os = "windows")]
+    let base = std::env::var_os("LOCALAPPDATA").map(PathBuf::from);
+
+    #[cfg(target_os = "macos")]
+    let base = std::env::var_os("HOME").map(|home| PathBuf::from(home).join("Library/Application Support"));
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let base = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")));
+
+    base.map(|base| base.join(APP_IDENTIFIER))
+}
+
+/// 打开数据库（不会创建新数据库），口令加密时使用环境变量中的口令解锁
+fn open_manager(options: &Options) -> Result<ClipboardManager, CliError> {
+    let app_dir = options
+        .data_dir
+        .clone()
+        .or_else(|| std::env::var_os(DATA_DIR_ENV).map(PathBuf::from))
+        .or_else(default_data_dir)
+        .ok_or_else(|| CliError::Failed("无法确定数据目录，请使用 --data-dir 指定".to_string()))?;
+    if !app_dir.join(DB_FILE).is_file() {
+        return Err(CliError::Failed(format!(
+            "找不到数据库 {}（请先运行一次 Paste Library，或使用 --data-dir 指定数据目录）",
+            app_dir.join(DB_FILE).display()
+        )));
+    }
+
+    let (database, image_key) = crate::open_database(&app_dir)?;
+    let database = Arc::new(database);
+    let image_store = Arc::new(ImageStore::new(&app_dir));
+    image_store.set_key(image_key);
+
+    let settings = if database.is_locked() {
+        AppSettings::default()
+    } else {
+        database.get_settings().map_err(|e| e.to_string())?
+    };
+    let manager = ClipboardManager::new(database.clone(), Arc::new(Mutex::new(settings)), image_store)
+        .with_source_app_provider(Arc::new(NoSourceApp));
+
+    if database.is_locked() {
+        let passphrase = std::env::var(PASSPHRASE_ENV).map_err(|_| {
+            CliError::Locked(format!("数据库已加密，请通过环境变量 {} 提供口令", PASSPHRASE_ENV))
+        })?;
+        tauri::async_runtime::block_on(manager.unlock(&KeyMaterial::Passphrase { passphrase }))
+            .map_err(CliError::Locked)?;
+    }
+    Ok(manager)
+}
+
+fn execute(command: Command, options: &Options) -> Result<Output, CliError> {
+    let manager = open_manager(options)?;
+
+    let output = match command {
+        Command::List => Output::Items(search(&manager, Vec::new(), options)?),
+        Command::Search(query) => {
+            let keywords = query.split_whitespace().map(String::from).collect();
+            Output::Items(search(&manager, keywords, options)?)
+        }
+        Command::Get(id) => Output::Content(require_item(&manager, id)?),
+        Command::Copy(id) => {
+            copy_item(&manager, require_item(&manager, id)?)?;
+            Output::Done(id)
+        }
+        Command::Add(text) => Output::Added(add_text(&manager, text, &options.tags)?),
+        Command::Delete(id) => {
+            require_item(&manager, id)?;
+            manager.delete_item(id)?;
+            Output::Done(id)
+        }
+        Command::Tags => Output::Tags(manager.get_tags()?),
+        Command::Export if options.output.as_deref().is_some_and(is_zip_path) => {
+            let request = ExportRequest {
+                tags: options.tags.clone(),
+                types: options.types.clone(),
+                from: options.since,
+                to: options.until,
+                include_sensitive: options.include_sensitive,
+            };
+            let path = options.output.as_deref().unwrap_or(Path::new(""));
+            Output::Exported(manager.export_archive(&request, path)?)
+        }
+        Command::Export => {
+            let has_filter = !options.tags.is_empty()
+                || !options.types.is_empty()
+                || options.since.is_some()
+                || options.until.is_some()
+                || options.include_sensitive;
+            if has_filter {
+                return Err(CliError::Usage("筛选条件和 --include-sensitive 只支持 zip 归档导出（--output 以 .zip 结尾）".to_string()));
+            }
+            let data = manager.export_data()?;
+            match &options.output {
+                Some(path) => {
+                    std::fs::write(path, data).map_err(|e| format!("写入 {} 失败: {}", path.display(), e))?;
+                    Output::Raw(String::new())
+                }
+                None => Output::Raw(format!("{}\n", data)),
+            }
+        }
+        Command::Import(path) => match options.from {
+            Some(source) => Output::ImportedExternal(manager.import_from(source, &path)?),
+            None => Output::Imported(manager.import_file(&path)?),
+        },
+    };
+    Ok(output)
+}
+
+fn search(manager: &ClipboardManager, keywords: Vec<String>, options: &Options) -> Result<Vec<ClipboardItem>, CliError> {
+    let request = AdvancedSearchRequest {
+        keywords,
+        tags: options.tags.clone(),
+        types: options.types.clone(),
+        limit: Some(options.limit.unwrap_or(DEFAULT_LIMIT)),
+        offset: options.offset,
+        source_apps: Vec::new(),
+    };
+    Ok(manager
+        .search_history_advanced(request)?
+        .into_iter()
+        .map(|result| result.item)
+        .collect())
+}
+
+fn require_item(manager: &ClipboardManager, id: i64) -> Result<ClipboardItem, CliError> {
+    manager
+        .get_item(id)?
+        .ok_or_else(|| CliError::NotFound(format!("记录不存在: {}", id)))
+}
+
+// 写入系统剪贴板（片段先展开占位符，需要填写输入值的片段无法复制）
+fn copy_item(manager: &ClipboardManager, item: ClipboardItem) -> Result<(), CliError> {
+    let watcher = ClipboardWatcher::new(manager.clone());
+    if item.content_type != ClipboardContentType::Snippet {
+        return tauri::async_runtime::block_on(watcher.write_item(&item)).map_err(CliError::from);
+    }
+
+    let clipboard = if template::uses_clipboard(&item.content).map_err(|e| e.to_string())? {
+        watcher.read_text()
+    } else {
+        None
+    };
+    let context = TemplateContext {
+        now: chrono::Local::now(),
+        clipboard,
+        inputs: HashMap::new(),
+    };
+    let expanded = template::expand(&item.content, &context).map_err(|e| format!("片段展开失败: {}", e))?;
+    tauri::async_runtime::block_on(watcher.write_text_unrecorded(&expanded.text)).map_err(CliError::from)
+}
+
+// 添加文本记录（与外部复制相同：重复内容移到最前，经过敏感内容检测），并追加标签
+fn add_text(manager: &ClipboardManager, text: Option<String>, tags: &[String]) -> Result<ClipboardItem, CliError> {
+    let text = match text {
+        Some(text) => text,
+        None => read_stdin()?,
+    };
+    if text.is_empty() {
+        return Err(CliError::Usage("没有要添加的内容".to_string()));
+    }
+
+    let outcome = tauri::async_runtime::block_on(manager.handle_clipboard_change(text, None, false))?;
+    let Some(item) = outcome.item else {
+        return Err(CliError::Failed("内容命中敏感内容规则，已按设置丢弃".to_string()));
+    };
+    if tags.is_empty() {
+        return Ok(item);
+    }
+    Ok(manager.append_tags(item.id, tags)?)
+}
+
+// 读取标准输入（去掉 echo 等命令追加的一个行尾换行）
+fn read_stdin() -> Result<String, CliError> {
+    let mut text = String::new();
+    std::io::stdin()
+        .read_to_string(&mut text)
+        .map_err(|e| format!("读取标准输入失败: {}", e))?;
+    if text.ends_with('\n') {
+        text.pop();
+        if text.ends_with('\r') {
+            text.pop();
+        }
+    }
+    Ok(text)
+}
+
+fn print_output(output: Output, json: bool) -> Result<(), CliError> {
+    let text = match output {
+        Output::Raw(text) => text,
+        Output::Items(items) if json => to_json(&items)?,
+        Output::Items(items) => items.iter().map(|item| format!("{}\n", summary_line(item))).collect(),
+        Output::Added(item) if json => to_json(&item)?,
+        Output::Added(item) => format!("{}\n", item.id),
+        Output::Content(item) if json => to_json(&item)?,
+        Output::Content(item) => item_text(&item),
+        Output::Tags(tags) if json => to_json(&tags)?,
+        Output::Tags(tags) => tags
+            .iter()
+            .map(|tag| format!("{}\t{}\n", tag.name, tag.item_count))
+            .collect(),
+        Output::Done(id) if json => to_json(&serde_json::json!({ "id": id }))?,
+        Output::Done(_) => String::new(),
+        Output::Exported(report) if json => to_json(&report)?,
+        Output::Exported(report) => format!("已导出 {} 条记录、{} 张图片\n", report.items, report.images),
+        Output::Imported(report) if json => to_json(&report)?,
+        Output::Imported(report) => format!(
+            "已导入 {} 条记录、{} 张图片，跳过 {} 条\n",
+            report.imported, report.images, report.skipped
+        ),
+        Output::ImportedExternal(report) if json => to_json(&report)?,
+        Output::ImportedExternal(report) => {
+            let mut text = format!(
+                "已从 {} 导入 {} 条记录（其中图片 {} 张），跳过 {} 条，不支持的格式 {} 条，失败 {} 条\n",
+                report.source.label(),
+                report.imported,
+                report.images,
+                report.skipped,
+                report.unsupported,
+                report.failed
+            );
+            for reason in &report.unsupported_reasons {
+                text.push_str(&format!("  {}\n", reason));
+            }
+            text
+        }
+    };
+
+    let mut stdout = std::io::stdout().lock();
+    stdout
+        .write_all(text.as_bytes())
+        .and_then(|()| stdout.flush())
+        .map_err(|e| CliError::Failed(format!("输出失败: {}", e)))
+}
+
+fn is_zip_path(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("zip"))
+}
+
+fn to_json<T: serde::Serialize + ?Sized>(value: &T) -> Result<String, CliError> {
+    serde_json::to_string(value)
+        .map(|json| format!("{}\n", json))
+        .map_err(|e| CliError::Failed(format!("序列化失败: {}", e)))
+}
+
+// 记录的文本内容（HTML/RTF 取纯文本，文件取路径列表）
+fn item_text(item: &ClipboardItem) -> String {
+    match item.content_type {
+        ClipboardContentType::Html | ClipboardContentType::Rtf => {
+            item.text_content.clone().unwrap_or_else(|| item.content.clone())
+        }
+        ClipboardContentType::File | ClipboardContentType::Folder | ClipboardContentType::Files => item
+            .file_paths
+            .as_ref()
+            .map(|paths| paths.join("\n"))
+            .unwrap_or_else(|| item.content.clone()),
+        _ => item.content.clone(),
+    }
+}
+
+// 纯文本列表的一行：ID、类型、本地时间、单行预览（以 Tab 分隔）
+fn summary_line(item: &ClipboardItem) -> String {
+    let content_type = serde_json::to_value(item.content_type)
+        .ok()
+        .and_then(|value| value.as_str().map(String::from))
+        .unwrap_or_default();
+    let created_at = item.created_at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S");
+
+    let flattened: String = item_text(item)
+        .chars()
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .collect();
+    let mut preview: String = flattened.trim().chars().take(PREVIEW_CHARS).collect();
+    if flattened.trim().chars().count() > PREVIEW_CHARS {
+        preview.push('…');
+    }
+    format!("{}\t{}\t{}\t{}", item.id, content_type, created_at, preview)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::Database;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    fn parse(list: &[&str]) -> (Command, Options) {
+        match parse_args(args(list)) {
+            Ok(Invocation::Run(command, options)) => (command, options),
+            other => panic!("unexpected parse result for {:?}: {:?}", list, other),
+        }
+    }
+
+    fn usage_error(list: &[&str]) -> String {
+        match parse_args(args(list)) {
+            Err(CliError::Usage(message)) => message,
+            other => panic!("expected usage error for {:?}: {:?}", list, other),
+        }
+    }
+
+    #[test]
+    fn parses_commands() {
+        assert_eq!(parse(&["list"]).0, Command::List);
+        assert_eq!(parse(&["search", "foo", "bar"]).0, Command::Search("foo bar".to_string()));
+        assert_eq!(parse(&["get", "7"]).0, Command::Get(7));
+        assert_eq!(parse(&["copy", "8"]).0, Command::Copy(8));
+        assert_eq!(parse(&["delete", "9"]).0, Command::Delete(9));
+        assert_eq!(parse(&["add"]).0, Command::Add(None));
+        assert_eq!(parse(&["add", "-"]).0, Command::Add(None));
+        assert_eq!(parse(&["add", "--", "-n"]).0, Command::Add(Some("-n".to_string())));
+        assert_eq!(parse(&["tags"]).0, Command::Tags);
+        assert_eq!(parse(&["export"]).0, Command::Export);
+        assert_eq!(parse(&["import", "a.zip"]).0, Command::Import(PathBuf::from("a.zip")));
+
+        let helps: [&[&str]; 4] = [&[], &["--help"], &["list", "-h"], &["help"]];
+        for help in helps {
+            assert!(matches!(parse_args(args(help)), Ok(Invocation::Help)), "{:?}", help);
+        }
+    }
+
+    #[test]
+    fn parses_options() {
+        let (command, options) = parse(&[
+            "--json", "list", "-t", "工作", "--tag", "b", "--type", "TEXT", "-n", "5", "--offset", "10",
+            "--data-dir", "/tmp/data",
+        ]);
+        assert_eq!(command, Command::List);
+        assert!(options.json);
+        assert_eq!(options.tags, vec!["工作", "b"]);
+        assert_eq!(options.types, vec![ClipboardContentType::Text]);
+        assert_eq!((options.limit, options.offset), (Some(5), Some(10)));
+        assert_eq!(options.data_dir, Some(PathBuf::from("/tmp/data")));
+
+        let (_, options) = parse(&["export", "-o", "out.zip", "--include-sensitive", "--since", "2024-01-01"]);
+        assert_eq!(options.output, Some(PathBuf::from("out.zip")));
+        assert!(options.include_sensitive && options.since.is_some() && options.until.is_none());
+
+        let (command, options) = parse(&["import", "--from", "Maccy", "Storage.sqlite"]);
+        assert_eq!(command, Command::Import(PathBuf::from("Storage.sqlite")));
+        assert_eq!(options.from, Some(ImportSource::Maccy));
+    }
+
+    #[test]
+    fn rejects_bad_arguments() {
+        assert_eq!(usage_error(&["--verbose", "list"]), "未知选项: --verbose");
+        assert_eq!(usage_error(&["frobnicate"]), "未知命令: frobnicate");
+        assert_eq!(usage_error(&["list", "--limit"]), "选项 --limit 需要一个值");
+        assert_eq!(usage_error(&["list", "--limit", "-1"]), "选项 --limit 需要非负整数: -1");
+        assert_eq!(usage_error(&["get", "abc"]), "无效的记录 ID: abc");
+        assert_eq!(usage_error(&["get"]), "get 需要一个记录 ID");
+        assert_eq!(usage_error(&["list", "extra"]), "list 不接受参数: extra");
+        assert_eq!(usage_error(&["search", " "]), "search 需要关键词");
+        assert!(usage_error(&["add", "a", "b"]).starts_with("add 只接受一个文本参数"));
+        assert!(usage_error(&["list", "--type", "video"]).starts_with("未知类型: video"));
+        assert!(usage_error(&["import", "--from", "clipy", "x"]).starts_with("未知来源: clipy"));
+    }
+
+    #[test]
+    fn parses_dates() {
+        let local = chrono::NaiveDate::from_ymd_opt(2024, 3, 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+            .and_local_timezone(chrono::Local)
+            .earliest()
+            .unwrap();
+        assert_eq!(parse_date("--since", "2024-03-01").unwrap(), local.with_timezone(&Utc));
+
+        let rfc3339 = parse_date("--until", "2024-03-01T08:30:00+08:00").unwrap();
+        assert_eq!(rfc3339, "2024-03-01T00:30:00Z".parse::<DateTime<Utc>>().unwrap());
+
+        for invalid in ["2024-13-01", "03/01/2024", "yesterday"] {
+            assert!(matches!(parse_date("--since", invalid), Err(CliError::Usage(_))), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn errors_map_to_exit_codes() {
+        assert_eq!(CliError::Failed(String::new()).exit_code(), 1);
+        assert_eq!(CliError::Usage(String::new()).exit_code(), 2);
+        assert_eq!(CliError::NotFound(String::new()).exit_code(), 3);
+        assert_eq!(CliError::Locked(String::new()).exit_code(), 4);
+        assert!(matches!(CliError::from("x".to_string()), CliError::Failed(_)));
+
+        let dir = tempfile::tempdir().unwrap();
+        let data_dir = dir.path().to_str().unwrap();
+        assert_eq!(run(args(&["--help"])), 0);
+        assert_eq!(run(args(&["--bogus"])), 2);
+        // 数据目录中没有数据库
+        assert_eq!(run(args(&["--data-dir", data_dir, "list"])), 1);
+
+        let database = Arc::new(Database::new(dir.path().to_path_buf()).unwrap());
+        assert_eq!(run(args(&["--data-dir", data_dir, "--json", "list"])), 0);
+        assert_eq!(run(args(&["--data-dir", data_dir, "get", "42"])), 3);
+        assert_eq!(run(args(&["--data-dir", data_dir, "export", "--include-sensitive"])), 2);
+        assert_eq!(run(args(&["--data-dir", data_dir, "export", "--tag", "a", "-o", "out.json"])), 2);
+
+        let manager = ClipboardManager::new(
+            database,
+            Arc::new(Mutex::new(AppSettings::default())),
+            Arc::new(ImageStore::new(dir.path())),
+        );
+        manager
+            .enable_encryption(&KeyMaterial::Passphrase { passphrase: "cli-test".to_string() })
+            .unwrap();
+        drop(manager);
+        assert_eq!(run(args(&["--data-dir", data_dir, "list"])), 4);
+    }
+}
@@ -1,26 +1,135 @@
 use sha2::{Digest, Sha256};
-use std::io::ErrorKind;
-use std::sync::Arc;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
 use tokio::sync::Mutex;
 
+use crate::archive;
+use crate::encryption::{EncryptionConfig, EncryptionKey, KeyMaterial};
 use crate::models::{
-    AdvancedSearchRequest, AppSettings, ClipboardContentType, ClipboardItem, ClipboardMetadata, ClearHistoryRequest,
-    GetHistoryRequest, SearchRequest,
+    AdvancedSearchRequest, AppSettings, CaptureOutcome, ClipboardContentType, ClipboardItem, ClipboardMetadata,
+    ClearHistoryRequest, EncryptionStatus, ExportReport, ExportRequest, ExternalImportReport, GetHistoryRequest,
+    ImportReport, ImportSource, SearchRequest, SearchResult, SourceApp, StorageGcReport, Tag,
 };
+use crate::paste_rules::{CompiledRules, PasteRule, PasteRuleInput};
+use crate::platform::{SourceAppProvider, SystemSourceAppProvider};
+use crate::sensitive::{self, Finding, SensitiveAction, SensitiveDetector, SensitiveReport};
+use crate::image_handler::{ImageStore, StoredImage};
+use crate::importers;
 use crate::storage::Database;
+use crate::template;
+
+/// 关闭定期回收时，重新检查设置的间隔
+const IMAGE_GC_IDLE_CHECK: Duration = Duration::from_secs(60 * 60);
+
+/// 复制来源的检查结果
+enum SourceCheck {
+    /// 允许记录（来源未知时为 None）
+    Allowed(Option<SourceApp>),
+    /// 来源应用在忽略列表中
+    Ignored,
+}
 
 #[derive(Clone)]
 pub struct ClipboardManager {
     database: Arc<Database>,
     settings: Arc<Mutex<AppSettings>>,
+    image_store: Arc<ImageStore>,
+    source_apps: Arc<dyn SourceAppProvider>,
+    /// 编译好的粘贴按键规则（规则变更时清空，下次粘贴时重新读取）
+    paste_rules: Arc<RwLock<Option<Arc<CompiledRules>>>>,
 }
 
 impl ClipboardManager {
-    pub fn new(database: Arc<Database>, settings: Arc<Mutex<AppSettings>>) -> Self {
+    pub fn new(database: Arc<Database>, settings: Arc<Mutex<AppSettings>>, image_store: Arc<ImageStore>) -> Self {
         Self {
             database,
             settings,
+            image_store,
+            source_apps: Arc::new(SystemSourceAppProvider),
+            paste_rules: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    /// 替换来源应用查询实现（默认查询系统前台窗口）
+    pub fn with_source_app_provider(mut self, provider: Arc<dyn SourceAppProvider>) -> Self {
+        self.source_apps = provider;
+        self
+    }
+
+    /// 查询复制来源并检查忽略列表（应用内复制不查询来源）
+    async fn check_source(&self, is_internal_copy: bool) -> SourceCheck {
+        if is_internal_copy {
+            return SourceCheck::Allowed(None);
+        }
+        let source = match self.source_apps.foreground_app() {
+            Ok(source) => source,
+            Err(e) => {
+                eprintln!("获取来源应用失败: {}", e);
+                None
+            }
+        };
+        let Some(source) = source else {
+            return SourceCheck::Allowed(None);
+        };
+
+        let ignored = self
+            .settings
+            .lock()
+            .await
+            .ignored_apps
+            .iter()
+            .any(|name| source.matches(name));
+        if ignored {
+            println!("来源应用 {} 在忽略列表中，已忽略本次复制", source.process_name);
+            return SourceCheck::Ignored;
+        }
+        SourceCheck::Allowed(Some(source))
+    }
+
+    /// 用已保存的图片填充元数据（保留调用方提供的其他字段）
+    pub(crate) fn image_metadata(stored: &StoredImage, metadata: Option<ClipboardMetadata>) -> ClipboardMetadata {
+        ClipboardMetadata {
+            width: Some(stored.width),
+            height: Some(stored.height),
+            format: Some(stored.format.clone()),
+            ..metadata.unwrap_or_default()
+        }
+    }
+
+    /// 记录的内容哈希（文件类记录把路径列表也算进去），相同内容再次写入时合并到已有记录
+    pub(crate) fn content_hash(content: &str, file_paths: Option<&[String]>) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(content);
+        for path in file_paths.unwrap_or_default() {
+            hasher.update(path.as_bytes());
         }
+        format!("{:x}", hasher.finalize())
+    }
+
+    /// 处理剪贴板变化（图片数据）
+    ///
+    /// 图片按内容哈希保存到图片存储并生成缩略图，content 存原图路径，thumbnail_path 存缩略图路径。
+    pub async fn handle_clipboard_image(&self, bytes: &[u8], is_internal_copy: bool) -> Result<Option<ClipboardItem>, String> {
+        // 先检查来源，被忽略的图片不写入图片存储
+        let SourceCheck::Allowed(source_app) = self.check_source(is_internal_copy).await else {
+            return Ok(None);
+        };
+
+        let stored = self.image_store.store_bytes(bytes)?;
+        let metadata = Self::image_metadata(&stored, None);
+
+        self.insert_extended(
+            ClipboardContentType::Image,
+            stored.original_path,
+            None,
+            Some(stored.thumbnail_path),
+            Some(metadata),
+            is_internal_copy,
+            source_app,
+        )
+        .await
     }
 
     /// 处理剪贴板变化（文本/HTML类型）
@@ -29,9 +138,22 @@ impl ClipboardManager {
     /// - `text`: 纯文本内容
     /// - `html`: HTML内容（可选）
     /// - `is_internal_copy`: 是否是软件内部复制。true表示用户点击项目复制，false表示来自系统剪贴板
-    pub async fn handle_clipboard_change(&self, text: String, html: Option<String>, is_internal_copy: bool) -> Result<Option<ClipboardItem>, String> {
+    ///
+    /// 写入前先做敏感内容检测，命中时按设置丢弃/限时保存/打码，处理方式通过 `sensitive` 返回。
+    /// 来源应用在忽略列表中时不记录。
+    pub async fn handle_clipboard_change(&self, text: String, html: Option<String>, is_internal_copy: bool) -> Result<CaptureOutcome, String> {
+        let SourceCheck::Allowed(source_app) = self.check_source(is_internal_copy).await else {
+            return Ok(CaptureOutcome::default());
+        };
+
         let settings = self.settings.lock().await;
         let auto_sort = settings.auto_sort;
+        let sensitive_action = SensitiveAction::from_setting(&settings.sensitive_action);
+        let expire_minutes = settings.sensitive_expire_minutes.max(1);
+        let findings = match sensitive_action {
+            Some(_) => SensitiveDetector::lenient(&settings.sensitive_patterns).detect(&text),
+            None => Vec::new(),
+        };
 
         let count = self.database.get_count().map_err(|e| e.to_string())?;
         if count >= settings.max_history_count {
@@ -45,17 +167,34 @@ impl ClipboardManager {
         }
         drop(settings);
 
-        let (content_type, content, text_content) = if let Some(html_content) = html {
-            // HTML 类型：content 存储 HTML，text_content 存储纯文本
-            (ClipboardContentType::Html, html_content, Some(text))
-        } else {
-            // 纯文本类型：content 和 text_content 都存储纯文本
-            (ClipboardContentType::Text, text.clone(), Some(text))
+        let (action, report) = match sensitive_action.filter(|_| !findings.is_empty()) {
+            Some(action) => {
+                let expires_at = (action == SensitiveAction::Expire)
+                    .then(|| chrono::Utc::now() + chrono::Duration::minutes(expire_minutes));
+                (Some(action), Some(SensitiveReport::new(&findings, action, expires_at)))
+            }
+            None => (None, None),
         };
 
-        let mut hasher = Sha256::new();
-        hasher.update(&content);
-        let content_hash = format!("{:x}", hasher.finalize());
+        let (content_type, content, text_content) = match action {
+            Some(SensitiveAction::Drop) => {
+                println!("检测到敏感内容，已忽略本次复制");
+                return Ok(CaptureOutcome { item: None, sensitive: report });
+            }
+            Some(SensitiveAction::Mask) => {
+                // HTML 难以可靠打码，只保存打码后的纯文本
+                let masked = sensitive::mask(&text, &findings);
+                (ClipboardContentType::Text, masked.clone(), Some(masked))
+            }
+            _ => match html {
+                // HTML 类型：content 存储 HTML，text_content 存储纯文本
+                Some(html_content) => (ClipboardContentType::Html, html_content, Some(text)),
+                // 纯文本类型：content 和 text_content 都存储纯文本
+                None => (ClipboardContentType::Text, text.clone(), Some(text)),
+            },
+        };
+
+        let content_hash = Self::content_hash(&content, None);
 
         let item = ClipboardItem {
             id: 0,
@@ -68,6 +207,9 @@ impl ClipboardManager {
             file_paths: None,
             thumbnail_path: None,
             tags: None,
+            is_sensitive: action == Some(SensitiveAction::Expire),
+            expires_at: report.as_ref().and_then(|r| r.expires_at),
+            source_app,
         };
 
         let id = self.database.add_clipboard_item(&item, auto_sort, is_internal_copy).map_err(|e| e.to_string())?;
@@ -75,7 +217,10 @@ impl ClipboardManager {
         let mut item_with_id = item;
         item_with_id.id = id;
 
-        Ok(Some(item_with_id))
+        Ok(CaptureOutcome {
+            item: Some(item_with_id),
+            sensitive: report,
+        })
     }
 
     /// 处理剪贴板变化（扩展类型：图片、文件等）
@@ -87,6 +232,8 @@ impl ClipboardManager {
     /// - `thumbnail_path`: 缩略图路径（可选）
     /// - `metadata`: 元数据（可选）
     /// - `is_internal_copy`: 是否是软件内部复制。true表示用户点击项目复制，false表示来自系统剪贴板
+    ///
+    /// 来源应用在忽略列表中时不记录，返回 None。
     pub async fn handle_clipboard_change_extended(
         &self,
         content_type: ClipboardContentType,
@@ -95,6 +242,34 @@ impl ClipboardManager {
         thumbnail_path: Option<String>,
         metadata: Option<ClipboardMetadata>,
         is_internal_copy: bool,
+    ) -> Result<Option<ClipboardItem>, String> {
+        let SourceCheck::Allowed(source_app) = self.check_source(is_internal_copy).await else {
+            return Ok(None);
+        };
+
+        self.insert_extended(
+            content_type,
+            content,
+            file_paths,
+            thumbnail_path,
+            metadata,
+            is_internal_copy,
+            source_app,
+        )
+        .await
+    }
+
+    /// 写入扩展类型记录（来源已检查）
+    #[allow(clippy::too_many_arguments)]
+    async fn insert_extended(
+        &self,
+        content_type: ClipboardContentType,
+        content: String,
+        file_paths: Option<Vec<String>>,
+        thumbnail_path: Option<String>,
+        metadata: Option<ClipboardMetadata>,
+        is_internal_copy: bool,
+        source_app: Option<SourceApp>,
     ) -> Result<Option<ClipboardItem>, String> {
         let settings = self.settings.lock().await;
         let auto_sort = settings.auto_sort;
@@ -111,15 +286,17 @@ impl ClipboardManager {
         }
         drop(settings);
 
-        // 生成内容哈希
-        let mut hasher = Sha256::new();
-        hasher.update(&content);
-        if let Some(ref paths) = file_paths {
-            for path in paths {
-                hasher.update(path.as_bytes());
-            }
-        }
-        let content_hash = format!("{:x}", hasher.finalize());
+        // 存储外的图片（如前端保存的临时文件）先导入图片存储
+        let (content, thumbnail_path, metadata) =
+            if content_type == ClipboardContentType::Image && !self.image_store.contains(&content) {
+                let stored = self.image_store.import_file(&content)?;
+                let metadata = Self::image_metadata(&stored, metadata);
+                (stored.original_path, Some(stored.thumbnail_path), Some(metadata))
+            } else {
+                (content, thumbnail_path, metadata)
+            };
+
+        let content_hash = Self::content_hash(&content, file_paths.as_deref());
 
         let item = ClipboardItem {
             id: 0,
@@ -132,6 +309,9 @@ impl ClipboardManager {
             file_paths,
             thumbnail_path,
             tags: None,
+            is_sensitive: false,
+            expires_at: None,
+            source_app,
         };
 
         let id = self.database.add_clipboard_item(&item, auto_sort, is_internal_copy).map_err(|e| e.to_string())?;
@@ -169,25 +349,13 @@ impl ClipboardManager {
     }
 
     /// 高级搜索（支持标签和类型过滤）
-    pub fn search_history_advanced(&self, request: AdvancedSearchRequest) -> Result<Vec<ClipboardItem>, String> {
+    pub fn search_history_advanced(&self, request: AdvancedSearchRequest) -> Result<Vec<SearchResult>, String> {
         self.database
             .search_history_advanced(&request)
             .map_err(|e| e.to_string())
     }
 
     pub fn delete_item(&self, id: i64) -> Result<(), String> {
-        if let Some(image_path) = self
-            .database
-            .get_image_path_by_id(id)
-            .map_err(|e| e.to_string())?
-        {
-            if let Err(error) = std::fs::remove_file(&image_path) {
-                if error.kind() != ErrorKind::NotFound {
-                    eprintln!("删除图片本地文件失败 ({}): {}", image_path, error);
-                }
-            }
-        }
-
         self.database.delete_item(id).map_err(|e| e.to_string())
     }
 
@@ -197,6 +365,74 @@ impl ClipboardManager {
             .map_err(|e| e.to_string())
     }
 
+    /// 片段内容哈希（加类型前缀，避免与相同文本的历史记录冲突）
+    pub(crate) fn snippet_hash(body: &str) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(b"snippet:");
+        hasher.update(body);
+        format!("{:x}", hasher.finalize())
+    }
+
+    /// 校验片段正文（空内容、无效占位符都会被拒绝）
+    pub(crate) fn validate_snippet(body: &str) -> Result<(), String> {
+        if body.trim().is_empty() {
+            return Err("片段内容不能为空".to_string());
+        }
+        template::input_names(body).map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    /// 新建片段
+    pub fn create_snippet(&self, body: String, tags: Option<Vec<String>>) -> Result<ClipboardItem, String> {
+        Self::validate_snippet(&body)?;
+
+        let mut item = ClipboardItem {
+            id: 0,
+            content_type: ClipboardContentType::Snippet,
+            content: body.clone(),
+            created_at: chrono::Utc::now(),
+            content_hash: Self::snippet_hash(&body),
+            text_content: Some(body),
+            metadata: None,
+            file_paths: None,
+            thumbnail_path: None,
+            tags,
+            is_sensitive: false,
+            expires_at: None,
+            source_app: None,
+        };
+        item.id = self
+            .database
+            .add_clipboard_item(&item, true, false)
+            .map_err(|e| e.to_string())?;
+        Ok(item)
+    }
+
+    /// 修改片段正文
+    pub fn update_snippet(&self, id: i64, body: String) -> Result<ClipboardItem, String> {
+        Self::validate_snippet(&body)?;
+        self.get_snippet(id)?;
+
+        self.database
+            .update_snippet(id, &body, &Self::snippet_hash(&body))
+            .map_err(|e| e.to_string())?;
+        self.get_snippet(id)
+    }
+
+    /// 获取单条记录
+    pub fn get_item(&self, id: i64) -> Result<Option<ClipboardItem>, String> {
+        self.database.get_item(id).map_err(|e| e.to_string())
+    }
+
+    /// 获取片段（记录不存在或不是片段时报错）
+    pub fn get_snippet(&self, id: i64) -> Result<ClipboardItem, String> {
+        match self.get_item(id)? {
+            Some(item) if item.content_type == ClipboardContentType::Snippet => Ok(item),
+            Some(_) => Err(format!("记录 {} 不是片段", id)),
+            None => Err(format!("片段不存在: {}", id)),
+        }
+    }
+
     /// 启动时自动清理
     /// 
     /// 在程序启动时执行自动清理，保留有标签的记录，
@@ -215,16 +451,117 @@ impl ClipboardManager {
             .map_err(|e| e.to_string())
     }
 
+    /// 图片存储垃圾回收
+    ///
+    /// 1. 缩略图丢失但原图还在的记录：重新生成缩略图
+    /// 2. 原图已丢失的记录：记入报告，由用户决定是否删除
+    /// 3. 存储目录中不再被任何记录引用的文件：删除并统计回收的空间
+    pub fn collect_image_garbage(&self) -> Result<StorageGcReport, String> {
+        let mut report = StorageGcReport::default();
+        let references = self
+            .database
+            .get_image_references()
+            .map_err(|e| e.to_string())?;
+
+        let mut referenced: HashSet<PathBuf> = HashSet::new();
+        for (id, content_type, content, thumbnail_path) in references {
+            let mut thumbnail_path = thumbnail_path.filter(|p| !p.trim().is_empty());
+
+            if content_type == "image" {
+                if !Path::new(&content).exists() {
+                    report.broken_item_ids.push(id);
+                } else if !thumbnail_path.as_deref().is_some_and(|p| Path::new(p).exists()) {
+                    match self.image_store.ensure_thumbnail(&content) {
+                        Ok(path) => {
+                            self.database
+                                .update_thumbnail_path(id, &path)
+                                .map_err(|e| e.to_string())?;
+                            thumbnail_path = Some(path);
+                            report.rows_repaired += 1;
+                        }
+                        Err(e) => {
+                            eprintln!("重新生成缩略图失败 (id={}): {}", id, e);
+                            report.broken_item_ids.push(id);
+                        }
+                    }
+                }
+                referenced.insert(PathBuf::from(content));
+            }
+
+            if let Some(path) = thumbnail_path {
+                referenced.insert(PathBuf::from(path));
+            }
+        }
+
+        for (path, size, _) in self.image_store.list_files() {
+            report.files_scanned += 1;
+            if referenced.contains(&path) {
+                continue;
+            }
+
+            // 新写入的文件在宽限期内不回收（记录可能尚未写入数据库）
+            match ImageStore::remove_unreferenced_file(&path) {
+                Ok(true) => {
+                    report.files_deleted += 1;
+                    report.bytes_reclaimed += size;
+                }
+                Ok(false) => {}
+                Err(e) => eprintln!("删除孤立图片文件失败 ({}): {}", path.display(), e),
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// 定期执行图片存储垃圾回收（周期由设置 image_gc_interval_hours 决定）
+    pub async fn run_image_gc_schedule(&self) {
+        loop {
+            let interval_hours = self.settings.lock().await.image_gc_interval_hours;
+            if interval_hours <= 0 {
+                tokio::time::sleep(IMAGE_GC_IDLE_CHECK).await;
+                continue;
+            }
+            tokio::time::sleep(Duration::from_secs(interval_hours as u64 * 60 * 60)).await;
+
+            let manager = self.clone();
+            match tauri::async_runtime::spawn_blocking(move || manager.collect_image_garbage()).await {
+                Ok(Ok(report)) => {
+                    if report.files_deleted > 0 || report.rows_repaired > 0 || !report.broken_item_ids.is_empty() {
+                        println!(
+                            "图片存储回收完成：删除 {} 个文件，回收 {} 字节，修复 {} 条记录，{} 条记录文件丢失",
+                            report.files_deleted,
+                            report.bytes_reclaimed,
+                            report.rows_repaired,
+                            report.broken_item_ids.len()
+                        );
+                    }
+                }
+                Ok(Err(e)) => eprintln!("图片存储回收失败: {}", e),
+                Err(e) => eprintln!("图片存储回收任务异常: {}", e),
+            }
+        }
+    }
+
     pub fn get_settings(&self) -> Result<AppSettings, String> {
         self.database.get_settings().map_err(|e| e.to_string())
     }
 
+    /// 保存前端提交的设置
+    ///
+    /// 窗口位置和尺寸由后端在移动/缩放窗口时维护，设置页面提交的可能是旧值，这里保留当前值。
     pub async fn save_settings(&self, new_settings: &AppSettings) -> Result<(), String> {
         let mut settings = self.settings.lock().await;
+        let new_settings = AppSettings {
+            window_pos_x: settings.window_pos_x,
+            window_pos_y: settings.window_pos_y,
+            window_width: settings.window_width,
+            window_height: settings.window_height,
+            ..new_settings.clone()
+        };
         *settings = new_settings.clone();
 
         self.database
-            .save_settings(new_settings)
+            .save_settings(&new_settings)
             .map_err(|e| e.to_string())
     }
 
@@ -234,12 +571,341 @@ impl ClipboardManager {
             .map_err(|e| e.to_string())
     }
 
+    /// 给记录追加标签（保留原有标签），返回更新后的记录
+    pub fn append_tags(&self, id: i64, tags: &[String]) -> Result<ClipboardItem, String> {
+        let item = self.get_item(id)?.ok_or_else(|| format!("记录 {} 不存在", id))?;
+        let mut merged = item.tags.unwrap_or_default();
+        for tag in tags {
+            if !merged.contains(tag) {
+                merged.push(tag.clone());
+            }
+        }
+        self.update_tags(id, Some(merged))?;
+        self.get_item(id)?.ok_or_else(|| format!("记录 {} 不存在", id))
+    }
+
     pub fn get_all_tags(&self) -> Result<Vec<(String, i64)>, String> {
         self.database
             .get_all_tags()
             .map_err(|e| e.to_string())
     }
 
+    /// 规范化标签名（去掉首尾空白，不能为空）
+    fn normalize_tag_name(name: &str) -> Result<String, String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("标签名不能为空".to_string());
+        }
+        Ok(name.to_string())
+    }
+
+    /// 校验标签颜色（#RRGGBB）
+    fn validate_tag_color(color: &str) -> Result<String, String> {
+        let color = color.trim();
+        let valid = color.len() == 7
+            && color.starts_with('#')
+            && color[1..].chars().all(|c| c.is_ascii_hexdigit());
+        if !valid {
+            return Err(format!("无效的标签颜色: {}（格式应为 #RRGGBB）", color));
+        }
+        Ok(color.to_lowercase())
+    }
+
+    fn get_tag(&self, id: i64) -> Result<Tag, String> {
+        self.database
+            .get_tag(id)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("标签不存在: {}", id))
+    }
+
+    /// 获取所有标签（含颜色和使用次数）
+    pub fn get_tags(&self) -> Result<Vec<Tag>, String> {
+        self.database.get_tags().map_err(|e| e.to_string())
+    }
+
+    /// 创建标签（未指定颜色时使用默认颜色）
+    pub fn create_tag(&self, name: String, color: Option<String>) -> Result<Tag, String> {
+        let name = Self::normalize_tag_name(&name)?;
+        let color = match color {
+            Some(color) => Self::validate_tag_color(&color)?,
+            None => Database::default_tag_color(&name),
+        };
+
+        if self.database.find_tag_by_name(&name).map_err(|e| e.to_string())?.is_some() {
+            return Err(format!("标签已存在: {}", name));
+        }
+
+        let id = self.database.create_tag(&name, &color).map_err(|e| e.to_string())?;
+        self.get_tag(id)
+    }
+
+    /// 重命名标签
+    ///
+    /// 新名称已被其他标签占用时返回错误，需要改用合并。
+    pub fn rename_tag(&self, id: i64, name: String) -> Result<Tag, String> {
+        let name = Self::normalize_tag_name(&name)?;
+        self.get_tag(id)?;
+
+        if let Some(existing) = self.database.find_tag_by_name(&name).map_err(|e| e.to_string())? {
+            if existing.id != id {
+                return Err(format!("标签已存在: {}，请使用合并", existing.name));
+            }
+        }
+
+        self.database.rename_tag(id, &name).map_err(|e| e.to_string())?;
+        self.get_tag(id)
+    }
+
+    /// 修改标签颜色
+    pub fn recolor_tag(&self, id: i64, color: String) -> Result<Tag, String> {
+        let color = Self::validate_tag_color(&color)?;
+        self.get_tag(id)?;
+
+        self.database.recolor_tag(id, &color).map_err(|e| e.to_string())?;
+        self.get_tag(id)
+    }
+
+    /// 将多个标签合并到目标标签
+    pub fn merge_tags(&self, source_ids: Vec<i64>, target_id: i64) -> Result<Tag, String> {
+        self.get_tag(target_id)?;
+        for id in &source_ids {
+            self.get_tag(*id)?;
+        }
+
+        self.database
+            .merge_tags(&source_ids, target_id)
+            .map_err(|e| e.to_string())?;
+        self.get_tag(target_id)
+    }
+
+    /// 删除标签（从所有记录上移除）
+    pub fn delete_tag(&self, id: i64) -> Result<(), String> {
+        self.get_tag(id)?;
+        self.database.delete_tag(id).map_err(|e| e.to_string())
+    }
+
+    fn get_paste_rule(&self, id: i64) -> Result<PasteRule, String> {
+        self.database
+            .get_paste_rule(id)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("粘贴规则不存在: {}", id))
+    }
+
+    /// 获取所有粘贴按键规则（按匹配顺序）
+    pub fn get_paste_rules(&self) -> Result<Vec<PasteRule>, String> {
+        self.database.get_paste_rules().map_err(|e| e.to_string())
+    }
+
+    /// 获取编译好的粘贴按键规则（首次使用或规则变更后才读取数据库）
+    pub fn compiled_paste_rules(&self) -> Result<Arc<CompiledRules>, String> {
+        if let Some(rules) = self.paste_rules.read().map_err(|e| e.to_string())?.as_ref() {
+            return Ok(rules.clone());
+        }
+        let rules = Arc::new(CompiledRules::new(self.get_paste_rules()?));
+        *self.paste_rules.write().map_err(|e| e.to_string())? = Some(rules.clone());
+        Ok(rules)
+    }
+
+    /// 规则变更后丢弃编译缓存
+    fn invalidate_paste_rules(&self) {
+        if let Ok(mut rules) = self.paste_rules.write() {
+            *rules = None;
+        }
+    }
+
+    /// 创建粘贴按键规则（排在最后）
+    pub fn create_paste_rule(&self, rule: PasteRuleInput) -> Result<PasteRule, String> {
+        let rule = rule.validate()?;
+        let id = self.database.create_paste_rule(&rule).map_err(|e| e.to_string())?;
+        self.invalidate_paste_rules();
+        self.get_paste_rule(id)
+    }
+
+    /// 修改粘贴按键规则
+    pub fn update_paste_rule(&self, id: i64, rule: PasteRuleInput) -> Result<PasteRule, String> {
+        let rule = rule.validate()?;
+        self.get_paste_rule(id)?;
+        self.database.update_paste_rule(id, &rule).map_err(|e| e.to_string())?;
+        self.invalidate_paste_rules();
+        self.get_paste_rule(id)
+    }
+
+    /// 删除粘贴按键规则
+    pub fn delete_paste_rule(&self, id: i64) -> Result<(), String> {
+        self.get_paste_rule(id)?;
+        self.database.delete_paste_rule(id).map_err(|e| e.to_string())?;
+        self.invalidate_paste_rules();
+        Ok(())
+    }
+
+    /// 调整粘贴按键规则的匹配顺序
+    pub fn reorder_paste_rules(&self, ids: Vec<i64>) -> Result<Vec<PasteRule>, String> {
+        self.database.reorder_paste_rules(&ids).map_err(|e| e.to_string())?;
+        self.invalidate_paste_rules();
+        self.get_paste_rules()
+    }
+
+    /// 历史记录中出现过的来源应用
+    pub fn get_source_apps(&self) -> Result<Vec<String>, String> {
+        self.database.get_source_apps().map_err(|e| e.to_string())
+    }
+
+    /// 删除已过期的敏感记录，返回删除的记录 ID
+    pub fn purge_expired_items(&self) -> Result<Vec<i64>, String> {
+        self.database
+            .delete_expired_items(chrono::Utc::now())
+            .map_err(|e| e.to_string())
+    }
+
+    /// 用当前设置检测一段文本（供设置页试验规则）
+    ///
+    /// patterns 为 None 时使用已保存的自定义规则；自定义规则无效时报错。
+    pub async fn check_sensitive_content(&self, text: &str, patterns: Option<Vec<String>>) -> Result<Vec<Finding>, String> {
+        let patterns = match patterns {
+            Some(patterns) => patterns,
+            None => self.settings.lock().await.sensitive_patterns.clone(),
+        };
+        Ok(SensitiveDetector::new(&patterns)?.detect(text))
+    }
+
+    /// 数据库是否等待解锁
+    pub fn is_locked(&self) -> bool {
+        self.database.is_locked()
+    }
+
+    /// 读取图片存储中的原图/缩略图（加密文件自动解密）
+    pub fn read_image(&self, path: &str) -> Result<Vec<u8>, String> {
+        self.image_store.read_bytes(path)
+    }
+
+    /// 路径是否位于图片存储中
+    pub fn is_stored_image(&self, path: &str) -> bool {
+        self.image_store.contains(path)
+    }
+
+    /// 当前加密状态
+    pub fn encryption_status(&self) -> Result<EncryptionStatus, String> {
+        let config = EncryptionConfig::load(self.database.app_dir())?;
+        Ok(EncryptionStatus {
+            enabled: config.is_some(),
+            key_source: config.as_ref().map(|c| c.key_source),
+            key_file: config.and_then(|c| c.key_file),
+            locked: self.database.is_locked(),
+        })
+    }
+
+    /// 读取加密配置，未启用加密时报错
+    fn require_encryption_config(&self) -> Result<EncryptionConfig, String> {
+        EncryptionConfig::load(self.database.app_dir())?.ok_or_else(|| "未启用加密".to_string())
+    }
+
+    /// 还原并校验当前密钥
+    fn verify_current_key(&self, current: &KeyMaterial) -> Result<EncryptionKey, String> {
+        let config = self.require_encryption_config()?;
+        let key = current.resolve_key(&config)?;
+        self.database
+            .verify_key(&key)
+            .map_err(|_| "密钥错误，无法打开数据库".to_string())?;
+        Ok(key)
+    }
+
+    /// 解锁口令加密的数据库，并重新加载设置
+    pub async fn unlock(&self, material: &KeyMaterial) -> Result<AppSettings, String> {
+        let config = self.require_encryption_config()?;
+        let key = material.resolve_key(&config)?;
+        self.database
+            .unlock(&key)
+            .map_err(|e| format!("解锁失败（口令错误或数据库已损坏）: {}", e))?;
+        self.image_store.set_key(Some(key));
+
+        let settings = self.get_settings()?;
+        *self.settings.lock().await = settings.clone();
+        Ok(settings)
+    }
+
+    /// 启用加密：加密数据库并加密已有图片
+    pub fn enable_encryption(&self, material: &KeyMaterial) -> Result<EncryptionStatus, String> {
+        let app_dir = self.database.app_dir();
+        if EncryptionConfig::load(app_dir)?.is_some() {
+            return Err("已启用加密".to_string());
+        }
+
+        // 配置和新密钥文件先写到临时文件，数据库加密成功后再替换
+        let staged = material.create_key(app_dir, true)?.stage(app_dir)?;
+        let key = staged.key.clone();
+        self.database
+            .encrypt(&key)
+            .map_err(|e| format!("加密数据库失败: {}", e))?;
+        if let Err(error) = staged.commit() {
+            // 没有配置就无法再还原密钥，把数据库解密回明文
+            if let Err(e) = self.database.decrypt(&key) {
+                eprintln!("回滚数据库加密失败: {}", e);
+            }
+            let _ = EncryptionConfig::remove(app_dir);
+            return Err(error);
+        }
+
+        let count = self.image_store.change_key(None, Some(key))?;
+        println!("已启用加密，加密了 {} 个图片文件", count);
+
+        self.encryption_status()
+    }
+
+    /// 关闭加密：数据库和图片恢复为明文
+    pub fn disable_encryption(&self, current: &KeyMaterial) -> Result<EncryptionStatus, String> {
+        let key = self.verify_current_key(current)?;
+        self.database
+            .decrypt(&key)
+            .map_err(|e| format!("解密数据库失败: {}", e))?;
+        if let Err(error) = EncryptionConfig::remove(self.database.app_dir()) {
+            // 配置仍在时启动会按加密数据库打开，重新加密以保持一致
+            if let Err(e) = self.database.encrypt(&key) {
+                eprintln!("回滚数据库解密失败: {}", e);
+            }
+            return Err(error);
+        }
+
+        let count = self
+            .image_store
+            .change_key(Some(&key), None)
+            .map_err(|e| format!("{}，原密钥在本次运行期间保留，可稍后重试", e))?;
+        println!("已关闭加密，解密了 {} 个图片文件", count);
+
+        self.encryption_status()
+    }
+
+    /// 更换密钥：用新密钥重新加密数据库和所有图片
+    pub fn rotate_encryption(&self, current: &KeyMaterial, new: &KeyMaterial) -> Result<EncryptionStatus, String> {
+        let app_dir = self.database.app_dir();
+        let old_config = self.require_encryption_config()?;
+        let key = self.verify_current_key(current)?;
+
+        // 总是生成新密钥；配置和新密钥文件先写到临时文件，数据库换用新密钥后再替换
+        let staged = new.create_key(app_dir, false)?.stage(app_dir)?;
+        let new_key = staged.key.clone();
+        self.database
+            .rekey(&new_key)
+            .map_err(|e| format!("更换数据库密钥失败: {}", e))?;
+        if let Err(error) = staged.commit() {
+            // 新配置或密钥文件没能替换时，数据库换回原密钥并恢复原配置
+            if let Err(e) = self.database.rekey(&key) {
+                eprintln!("回滚数据库密钥失败: {}", e);
+            }
+            if let Err(e) = old_config.save(app_dir) {
+                eprintln!("恢复加密配置失败: {}", e);
+            }
+            return Err(error);
+        }
+
+        let count = self
+            .image_store
+            .change_key(Some(&key), Some(new_key))
+            .map_err(|e| format!("{}，原密钥在本次运行期间保留，可稍后重试", e))?;
+        println!("已更换密钥，重新加密了 {} 个图片文件", count);
+
+        self.encryption_status()
+    }
+
     pub fn export_data(&self) -> Result<String, String> {
         let items = self.database
             .get_history(10000, 0)
@@ -249,6 +915,31 @@ impl ClipboardManager {
             .map_err(|e| format!("导出失败: {}", e))
     }
 
+    /// 导出为 zip 归档（含图片），见 archive 模块
+    pub fn export_archive(&self, request: &ExportRequest, path: &Path) -> Result<ExportReport, String> {
+        archive::export_archive(&self.database, &self.image_store, request, path)
+    }
+
+    /// 导入备份文件：zip 归档，或旧版导出的 JSON
+    pub fn import_file(&self, path: &Path) -> Result<ImportReport, String> {
+        if archive::is_archive(path)? {
+            return archive::import_archive(&self.database, &self.image_store, path);
+        }
+        let json_data = std::fs::read_to_string(path).map_err(|e| format!("读取 {} 失败: {}", path.display(), e))?;
+        let imported = self.import_data(&json_data)?;
+        Ok(ImportReport {
+            imported: imported as usize,
+            ..Default::default()
+        })
+    }
+
+    /// 从其他剪贴板管理器的数据导入
+    pub fn import_from(&self, source: ImportSource, path: &Path) -> Result<ExternalImportReport, String> {
+        // 在 spawn_blocking 或命令行中调用，可以同步等待设置锁
+        let sensitive = importers::SensitiveRules::from_settings(&self.settings.blocking_lock());
+        importers::import(&self.database, &self.image_store, sensitive.as_ref(), source, path)
+    }
+
     pub fn import_data(&self, json_data: &str) -> Result<i64, String> {
         let items: Vec<ClipboardItem> = serde_json::from_str(json_data)
             .map_err(|e| format!("导入失败: JSON 格式错误 - {}", e))?;
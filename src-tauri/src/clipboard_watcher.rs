@@ -0,0 +1,754 @@
+//! 后端剪贴板监听服务
+//! 在 Rust 侧监听系统剪贴板变化并直接写入历史记录，不依赖 webview 是否存活
+
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tauri::Emitter;
+use tokio::sync::{mpsc, Mutex};
+
+use crate::clipboard::ClipboardManager;
+use crate::models::{CaptureOutcome, ClipboardContentType, ClipboardItem, ClipboardMetadata};
+
+/// 应用内复制标记的有效期（与前端 restoreToClipboard 的重置延迟保持一致）
+const INTERNAL_COPY_WINDOW_MS: u64 = 500;
+
+/// KDE/KeePassXC 的密码管理器标记（值为 "secret" 时不应记录）
+const KDE_PASSWORD_MANAGER_HINT: &str = "x-kde-passwordManagerHint";
+/// Windows：存在即表示剪贴板监视程序不应处理这次内容
+const WINDOWS_EXCLUDE_FROM_MONITOR: &str = "ExcludeClipboardContentFromMonitorProcessing";
+/// Windows：DWORD 值为 0 时表示不应进入剪贴板历史
+const WINDOWS_CAN_INCLUDE_IN_HISTORY: &str = "CanIncludeInClipboardHistory";
+
+/// 剪贴板读取后端
+///
+/// 系统实现基于 clipboard-rs，测试时可以替换为内存中的 mock。
+pub trait ClipboardBackend {
+    fn read_text(&self) -> Option<String>;
+    fn read_html(&self) -> Option<String>;
+    fn read_files(&self) -> Option<Vec<String>>;
+    /// 读取图片（PNG 编码）
+    fn read_image(&self) -> Option<Vec<u8>>;
+    /// 当前剪贴板提供的全部格式名（MIME 类型或 Windows 注册的格式名）
+    fn available_formats(&self) -> Vec<String>;
+    /// 读取指定格式的原始数据
+    fn read_format(&self, format: &str) -> Option<Vec<u8>>;
+}
+
+/// 从剪贴板读取并分类后的内容
+#[derive(Debug, Clone)]
+pub enum CapturedContent {
+    /// 纯文本 / HTML
+    Text { text: String, html: Option<String> },
+    /// 文件、文件夹
+    Files {
+        content_type: ClipboardContentType,
+        paths: Vec<String>,
+        metadata: ClipboardMetadata,
+    },
+    /// 图片（PNG 编码）
+    Image(Vec<u8>),
+    /// 带有密码管理器标记的内容（不读取内容本身，只记录命中的格式名）
+    Concealed(&'static str),
+}
+
+/// 剪贴板快照（粘贴后用于恢复用户原来的剪贴板内容）
+#[derive(Debug, Clone, Default)]
+pub struct ClipboardSnapshot {
+    pub text: Option<String>,
+    pub html: Option<String>,
+    /// 图片（PNG 编码）
+    pub image: Option<Vec<u8>>,
+}
+
+impl ClipboardSnapshot {
+    pub fn capture<B: ClipboardBackend + ?Sized>(backend: &B) -> Self {
+        Self {
+            text: backend.read_text(),
+            html: backend.read_html(),
+            image: backend.read_image(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_none() && self.html.is_none() && self.image.is_none()
+    }
+}
+
+/// 检查剪贴板是否带有"不要记录"的密码管理器标记，返回命中的格式名
+///
+/// 标记格式的值无法读取时按命中处理（宁可漏记也不记录密码）。
+pub fn concealment_marker<B: ClipboardBackend + ?Sized>(backend: &B) -> Option<&'static str> {
+    let formats = backend.available_formats();
+    let has = |name: &str| formats.iter().any(|f| f.eq_ignore_ascii_case(name));
+
+    if has(WINDOWS_EXCLUDE_FROM_MONITOR) {
+        return Some(WINDOWS_EXCLUDE_FROM_MONITOR);
+    }
+    if has(WINDOWS_CAN_INCLUDE_IN_HISTORY) {
+        let allowed = backend
+            .read_format(WINDOWS_CAN_INCLUDE_IN_HISTORY)
+            .is_some_and(|value| value.iter().any(|&b| b != 0));
+        if !allowed {
+            return Some(WINDOWS_CAN_INCLUDE_IN_HISTORY);
+        }
+    }
+    if has(KDE_PASSWORD_MANAGER_HINT) {
+        let is_secret = backend
+            .read_format(KDE_PASSWORD_MANAGER_HINT)
+            .is_none_or(|value| String::from_utf8_lossy(&value).trim() == "secret");
+        if is_secret {
+            return Some(KDE_PASSWORD_MANAGER_HINT);
+        }
+    }
+    None
+}
+
+/// 读取剪贴板并分类
+///
+/// 带有密码管理器标记时直接返回 `Concealed`，不读取内容；
+/// 其余按与前端一致的优先级: files > image > html > text
+pub fn capture<B: ClipboardBackend + ?Sized>(backend: &B) -> Option<CapturedContent> {
+    if let Some(marker) = concealment_marker(backend) {
+        return Some(CapturedContent::Concealed(marker));
+    }
+
+    if let Some(paths) = backend.read_files().filter(|p| !p.is_empty()) {
+        let paths: Vec<String> = paths.iter().map(|p| normalize_file_path(p)).collect();
+        let (content_type, metadata) = classify_paths(&paths);
+        return Some(CapturedContent::Files {
+            content_type,
+            paths,
+            metadata,
+        });
+    }
+
+    if let Some(image) = backend.read_image().filter(|png| !png.is_empty()) {
+        return Some(CapturedContent::Image(image));
+    }
+
+    let text = backend.read_text().unwrap_or_default();
+    let html = backend.read_html().filter(|h| !h.trim().is_empty());
+
+    if text.trim().is_empty() && html.is_none() {
+        return None;
+    }
+
+    Some(CapturedContent::Text { text, html })
+}
+
+/// 去掉 `file://` 前缀（部分平台以 URI 形式返回文件列表）
+fn normalize_file_path(path: &str) -> String {
+    path.strip_prefix("file://").unwrap_or(path).to_string()
+}
+
+/// 根据路径判断文件类型并生成元数据
+pub(crate) fn classify_paths(paths: &[String]) -> (ClipboardContentType, ClipboardMetadata) {
+    if paths.len() > 1 {
+        let metadata = ClipboardMetadata {
+            item_count: Some(paths.len() as u32),
+            ..Default::default()
+        };
+        return (ClipboardContentType::Files, metadata);
+    }
+
+    let path = Path::new(&paths[0]);
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| paths[0].clone());
+
+    if path.is_dir() {
+        let item_count = std::fs::read_dir(path).ok().map(|entries| entries.count() as u32);
+        let metadata = ClipboardMetadata {
+            folder_name: Some(name),
+            item_count,
+            ..Default::default()
+        };
+        (ClipboardContentType::Folder, metadata)
+    } else {
+        let metadata = ClipboardMetadata {
+            file_name: Some(name),
+            file_size: std::fs::metadata(path).ok().map(|m| m.len()),
+            ..Default::default()
+        };
+        (ClipboardContentType::File, metadata)
+    }
+}
+
+/// 后端剪贴板监听器
+#[derive(Clone)]
+pub struct ClipboardWatcher {
+    clipboard_manager: ClipboardManager,
+    /// 最近一次应用内复制的时间（用于区分用户点击条目复制与外部复制）
+    internal_copy_at: Arc<Mutex<Option<Instant>>>,
+    /// 最近一次不记录到历史的写入时间（如展开后的片段）
+    ignored_change_at: Arc<Mutex<Option<Instant>>>,
+    /// 因密码管理器标记而跳过的次数
+    concealed_count: Arc<AtomicU64>,
+}
+
+impl ClipboardWatcher {
+    pub fn new(clipboard_manager: ClipboardManager) -> Self {
+        Self {
+            clipboard_manager,
+            internal_copy_at: Arc::new(Mutex::new(None)),
+            ignored_change_at: Arc::new(Mutex::new(None)),
+            concealed_count: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// 标记下一次剪贴板变化来自应用内复制
+    pub async fn mark_internal_copy(&self) {
+        *self.internal_copy_at.lock().await = Some(Instant::now());
+    }
+
+    /// 取出应用内复制标记（过期即视为外部复制）
+    async fn take_internal_copy(&self) -> bool {
+        self.internal_copy_at
+            .lock()
+            .await
+            .take()
+            .is_some_and(|at| at.elapsed() <= Duration::from_millis(INTERNAL_COPY_WINDOW_MS))
+    }
+
+    /// 取出忽略标记（过期即视为正常的剪贴板变化）
+    async fn take_ignored_change(&self) -> bool {
+        self.ignored_change_at
+            .lock()
+            .await
+            .take()
+            .is_some_and(|at| at.elapsed() <= Duration::from_millis(INTERNAL_COPY_WINDOW_MS))
+    }
+
+    /// 标记下一次剪贴板变化不记录到历史
+    async fn mark_ignored_change(&self) {
+        *self.ignored_change_at.lock().await = Some(Instant::now());
+    }
+
+    /// 写入文本到系统剪贴板，且这次变化不记录到历史
+    pub async fn write_text_unrecorded(&self, text: &str) -> Result<(), String> {
+        self.mark_ignored_change().await;
+        system::write_text(text)
+    }
+
+    /// 将记录写入系统剪贴板（按应用内复制处理）
+    pub async fn write_item(&self, item: &ClipboardItem) -> Result<(), String> {
+        // 图片存储可能已加密，先经由图片存储读出原图
+        let image = match item.content_type {
+            ClipboardContentType::Image => Some(self.clipboard_manager.read_image(&item.content)?),
+            _ => None,
+        };
+        self.mark_internal_copy().await;
+        system::write_item(item, image.as_deref())
+    }
+
+    /// 将图片数据写入系统剪贴板
+    pub fn write_image(&self, bytes: &[u8]) -> Result<(), String> {
+        system::write_image(bytes)
+    }
+
+    /// 保存系统剪贴板当前的内容（文本、HTML、图片）
+    pub fn snapshot(&self) -> Result<ClipboardSnapshot, String> {
+        Ok(ClipboardSnapshot::capture(&system::SystemClipboard::new()?))
+    }
+
+    /// 将快照写回系统剪贴板（快照为空时清空剪贴板），且这次变化不记录到历史
+    pub async fn restore_unrecorded(&self, snapshot: &ClipboardSnapshot) -> Result<(), String> {
+        self.mark_ignored_change().await;
+        system::write_snapshot(snapshot)
+    }
+
+    /// 读取系统剪贴板当前的文本
+    pub fn read_text(&self) -> Option<String> {
+        system::SystemClipboard::new().ok()?.read_text()
+    }
+
+    /// 将捕获到的内容写入历史记录
+    pub async fn record(&self, captured: CapturedContent) -> Result<CaptureOutcome, String> {
+        if self.take_ignored_change().await {
+            return Ok(CaptureOutcome::default());
+        }
+        // 数据库锁定期间不记录（避免图片以明文写入存储目录）
+        if self.clipboard_manager.is_locked() {
+            return Ok(CaptureOutcome::default());
+        }
+        let is_internal_copy = self.take_internal_copy().await;
+
+        match captured {
+            CapturedContent::Text { text, html } => {
+                self.clipboard_manager
+                    .handle_clipboard_change(text, html, is_internal_copy)
+                    .await
+            }
+            CapturedContent::Files {
+                content_type,
+                paths,
+                metadata,
+            } => {
+                self.clipboard_manager
+                    .handle_clipboard_change_extended(
+                        content_type,
+                        paths.join("\n"),
+                        Some(paths),
+                        None,
+                        Some(metadata),
+                        is_internal_copy,
+                    )
+                    .await
+                    .map(CaptureOutcome::from)
+            }
+            CapturedContent::Image(png) => {
+                self.clipboard_manager
+                    .handle_clipboard_image(&png, is_internal_copy)
+                    .await
+                    .map(CaptureOutcome::from)
+            }
+            CapturedContent::Concealed(marker) => {
+                // 只记录次数，不输出内容
+                let count = self.concealed_count.fetch_add(1, Ordering::Relaxed) + 1;
+                eprintln!("剪贴板内容带有 {} 标记，已跳过（累计 {} 次）", marker, count);
+                Ok(CaptureOutcome::default())
+            }
+        }
+    }
+
+    /// 启动监听
+    ///
+    /// 系统剪贴板事件在独立线程中读取（部分平台的剪贴板句柄不能跨线程），
+    /// 读取结果通过 channel 交给异步任务写入数据库，并发送 `clipboard-item-added` 事件；
+    /// 命中敏感内容规则时另外发送 `sensitive-content-detected` 事件。
+    pub fn start(&self, app: tauri::AppHandle) {
+        let (tx, mut rx) = mpsc::unbounded_channel::<CapturedContent>();
+
+        std::thread::spawn(move || {
+            if let Err(e) = system::watch(tx) {
+                eprintln!("剪贴板监听启动失败: {}", e);
+            }
+        });
+
+        let watcher = self.clone();
+        tauri::async_runtime::spawn(async move {
+            while let Some(captured) = rx.recv().await {
+                match watcher.record(captured).await {
+                    Ok(outcome) => {
+                        if let Some(item) = &outcome.item {
+                            let _ = app.emit("clipboard-item-added", item);
+                        }
+                        if let Some(report) = &outcome.sensitive {
+                            let _ = app.emit("sensitive-content-detected", report);
+                        }
+                    }
+                    Err(e) => eprintln!("记录剪贴板变化失败: {}", e),
+                }
+            }
+        });
+    }
+}
+
+/// 基于 clipboard-rs 的系统剪贴板实现
+mod system {
+    use clipboard_rs::common::{RustImage, RustImageData};
+    use clipboard_rs::{
+        Clipboard, ClipboardContent, ClipboardContext, ClipboardHandler, ClipboardWatcher,
+        ClipboardWatcherContext, ContentFormat,
+    };
+    use tokio::sync::mpsc::UnboundedSender;
+
+    use super::{capture, CapturedContent, ClipboardBackend, ClipboardSnapshot};
+    use crate::models::{ClipboardContentType, ClipboardItem};
+
+    pub struct SystemClipboard {
+        ctx: ClipboardContext,
+    }
+
+    impl SystemClipboard {
+        pub fn new() -> Result<Self, String> {
+            let ctx = ClipboardContext::new().map_err(|e| e.to_string())?;
+            Ok(Self { ctx })
+        }
+    }
+
+    impl ClipboardBackend for SystemClipboard {
+        fn read_text(&self) -> Option<String> {
+            if !self.ctx.has(ContentFormat::Text) {
+                return None;
+            }
+            self.ctx.get_text().ok()
+        }
+
+        fn read_html(&self) -> Option<String> {
+            if !self.ctx.has(ContentFormat::Html) {
+                return None;
+            }
+            self.ctx.get_html().ok()
+        }
+
+        fn read_files(&self) -> Option<Vec<String>> {
+            if !self.ctx.has(ContentFormat::Files) {
+                return None;
+            }
+            self.ctx.get_files().ok()
+        }
+
+        fn read_image(&self) -> Option<Vec<u8>> {
+            if !self.ctx.has(ContentFormat::Image) {
+                return None;
+            }
+            let image = self.ctx.get_image().ok()?;
+            Some(image.to_png().ok()?.get_bytes().to_vec())
+        }
+
+        fn available_formats(&self) -> Vec<String> {
+            self.ctx.available_formats().unwrap_or_default()
+        }
+
+        fn read_format(&self, format: &str) -> Option<Vec<u8>> {
+            self.ctx.get_buffer(format).ok()
+        }
+    }
+
+    struct ChangeHandler {
+        backend: SystemClipboard,
+        tx: UnboundedSender<CapturedContent>,
+    }
+
+    impl ClipboardHandler for ChangeHandler {
+        fn on_clipboard_change(&mut self) {
+            if let Some(captured) = capture(&self.backend) {
+                let _ = self.tx.send(captured);
+            }
+        }
+    }
+
+    /// 写入文本到系统剪贴板
+    pub fn write_text(text: &str) -> Result<(), String> {
+        let ctx = ClipboardContext::new().map_err(|e| e.to_string())?;
+        ctx.set_text(text.to_string()).map_err(|e| e.to_string())
+    }
+
+    /// 写入图片数据到系统剪贴板
+    pub fn write_image(bytes: &[u8]) -> Result<(), String> {
+        let ctx = ClipboardContext::new().map_err(|e| e.to_string())?;
+        let image = RustImageData::from_bytes(bytes).map_err(|e| format!("图片解码失败: {}", e))?;
+        ctx.set_image(image).map_err(|e| e.to_string())
+    }
+
+    /// 将快照写回系统剪贴板（各格式一次性写入，只触发一次剪贴板变化）
+    pub fn write_snapshot(snapshot: &ClipboardSnapshot) -> Result<(), String> {
+        let ctx = ClipboardContext::new().map_err(|e| e.to_string())?;
+        if snapshot.is_empty() {
+            return ctx.clear().map_err(|e| e.to_string());
+        }
+
+        let mut contents = Vec::new();
+        if let Some(text) = &snapshot.text {
+            contents.push(ClipboardContent::Text(text.clone()));
+        }
+        if let Some(html) = &snapshot.html {
+            contents.push(ClipboardContent::Html(html.clone()));
+        }
+        if let Some(bytes) = &snapshot.image {
+            let image = RustImageData::from_bytes(bytes).map_err(|e| format!("图片解码失败: {}", e))?;
+            contents.push(ClipboardContent::Image(image));
+        }
+        ctx.set(contents).map_err(|e| e.to_string())
+    }
+
+    /// 将记录写入系统剪贴板（HTML/RTF 同时写入纯文本，图片使用 image 中的原图数据）
+    pub fn write_item(item: &ClipboardItem, image: Option<&[u8]>) -> Result<(), String> {
+        let ctx = ClipboardContext::new().map_err(|e| e.to_string())?;
+        let plain_text = item.text_content.clone().unwrap_or_else(|| item.content.clone());
+
+        let result = match item.content_type {
+            ClipboardContentType::Html => ctx.set(vec![
+                ClipboardContent::Html(item.content.clone()),
+                ClipboardContent::Text(plain_text),
+            ]),
+            ClipboardContentType::Rtf => ctx.set(vec![
+                ClipboardContent::Rtf(item.content.clone()),
+                ClipboardContent::Text(plain_text),
+            ]),
+            ClipboardContentType::Image => {
+                let bytes = image.ok_or("缺少图片数据")?;
+                let image = RustImageData::from_bytes(bytes).map_err(|e| format!("图片解码失败: {}", e))?;
+                ctx.set_image(image)
+            }
+            ClipboardContentType::File | ClipboardContentType::Folder | ClipboardContentType::Files => {
+                let paths = item
+                    .file_paths
+                    .clone()
+                    .unwrap_or_else(|| item.content.lines().map(String::from).collect());
+                ctx.set_files(paths)
+            }
+            ClipboardContentType::Text | ClipboardContentType::Snippet => ctx.set_text(item.content.clone()),
+        };
+        result.map_err(|e| e.to_string())
+    }
+
+    /// 阻塞监听系统剪贴板变化
+    pub fn watch(tx: UnboundedSender<CapturedContent>) -> Result<(), String> {
+        let handler = ChangeHandler {
+            backend: SystemClipboard::new()?,
+            tx,
+        };
+
+        let mut watcher: ClipboardWatcherContext<ChangeHandler> =
+            ClipboardWatcherContext::new().map_err(|e| e.to_string())?;
+        watcher.add_handler(handler);
+        watcher.start_watch();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::image_handler::ImageStore;
+    use crate::models::AppSettings;
+    use crate::platform::NoSourceApp;
+    use crate::storage::Database;
+    use std::collections::HashMap;
+
+    /// 内存中的剪贴板
+    #[derive(Default)]
+    struct FakeBackend {
+        text: Option<String>,
+        html: Option<String>,
+        files: Option<Vec<String>>,
+        image: Option<Vec<u8>>,
+        /// 额外的格式及其数据（None 表示格式存在但读取失败）
+        formats: HashMap<String, Option<Vec<u8>>>,
+    }
+
+    impl FakeBackend {
+        fn text(text: &str) -> Self {
+            Self {
+                text: Some(text.to_string()),
+                ..Default::default()
+            }
+        }
+
+        fn image(png: Vec<u8>) -> Self {
+            Self {
+                image: Some(png),
+                ..Default::default()
+            }
+        }
+
+        fn with_format(mut self, format: &str, value: Option<&[u8]>) -> Self {
+            self.formats.insert(format.to_string(), value.map(|v| v.to_vec()));
+            self
+        }
+    }
+
+    impl ClipboardBackend for FakeBackend {
+        fn read_text(&self) -> Option<String> {
+            self.text.clone()
+        }
+        fn read_html(&self) -> Option<String> {
+            self.html.clone()
+        }
+        fn read_files(&self) -> Option<Vec<String>> {
+            self.files.clone()
+        }
+        fn read_image(&self) -> Option<Vec<u8>> {
+            self.image.clone()
+        }
+        fn available_formats(&self) -> Vec<String> {
+            let mut formats: Vec<String> = self.formats.keys().cloned().collect();
+            if self.text.is_some() {
+                formats.push("text/plain".to_string());
+            }
+            formats
+        }
+        fn read_format(&self, format: &str) -> Option<Vec<u8>> {
+            self.formats.get(format).cloned().flatten()
+        }
+    }
+
+    fn tiny_png() -> Vec<u8> {
+        let image = image::RgbaImage::from_pixel(2, 2, image::Rgba([255, 0, 0, 255]));
+        let mut out = std::io::Cursor::new(Vec::new());
+        image.write_to(&mut out, image::ImageFormat::Png).unwrap();
+        out.into_inner()
+    }
+
+    fn watcher(dir: &Path) -> (Arc<Database>, ClipboardWatcher) {
+        let database = Arc::new(Database::new(dir.to_path_buf()).unwrap());
+        let manager = ClipboardManager::new(
+            database.clone(),
+            Arc::new(Mutex::new(AppSettings::default())),
+            Arc::new(ImageStore::new(dir)),
+        )
+        .with_source_app_provider(Arc::new(NoSourceApp));
+        (database, ClipboardWatcher::new(manager))
+    }
+
+    fn record(watcher: &ClipboardWatcher, backend: &FakeBackend) -> Option<ClipboardItem> {
+        let captured = capture(backend).expect("剪贴板内容为空");
+        tauri::async_runtime::block_on(watcher.record(captured))
+            .unwrap()
+            .item
+    }
+
+    #[test]
+    fn capture_text_and_html() {
+        let captured = capture(&FakeBackend {
+            html: Some("<b>hi</b>".to_string()),
+            ..FakeBackend::text("hi")
+        });
+        assert!(matches!(
+            captured,
+            Some(CapturedContent::Text { ref text, html: Some(ref html) }) if text == "hi" && html == "<b>hi</b>"
+        ));
+
+        // 空白文本不记录
+        assert!(capture(&FakeBackend::text("  \n")).is_none());
+        assert!(capture(&FakeBackend::default()).is_none());
+    }
+
+    #[test]
+    fn capture_prefers_files_then_image() {
+        let png = tiny_png();
+        let captured = capture(&FakeBackend {
+            files: Some(vec!["file:///tmp/a.txt".to_string(), "/tmp/b.txt".to_string()]),
+            image: Some(png.clone()),
+            ..FakeBackend::text("a.txt")
+        });
+        match captured {
+            Some(CapturedContent::Files { content_type, paths, .. }) => {
+                assert_eq!(content_type, ClipboardContentType::Files);
+                assert_eq!(paths, vec!["/tmp/a.txt", "/tmp/b.txt"]);
+            }
+            other => panic!("应识别为文件: {:?}", other),
+        }
+
+        let captured = capture(&FakeBackend {
+            image: Some(png.clone()),
+            ..FakeBackend::text("image.png")
+        });
+        assert!(matches!(captured, Some(CapturedContent::Image(ref bytes)) if *bytes == png));
+    }
+
+    #[test]
+    fn concealment_markers() {
+        let password = || FakeBackend::text("hunter2");
+
+        assert_eq!(concealment_marker(&password()), None);
+        assert_eq!(
+            concealment_marker(&password().with_format(WINDOWS_EXCLUDE_FROM_MONITOR, Some(b""))),
+            Some(WINDOWS_EXCLUDE_FROM_MONITOR)
+        );
+        // 格式名不区分大小写
+        assert_eq!(
+            concealment_marker(&password().with_format("x-kde-passwordmanagerhint", Some(b"secret\n"))),
+            Some(KDE_PASSWORD_MANAGER_HINT)
+        );
+        assert_eq!(
+            concealment_marker(&password().with_format(WINDOWS_CAN_INCLUDE_IN_HISTORY, Some(&[0, 0, 0, 0]))),
+            Some(WINDOWS_CAN_INCLUDE_IN_HISTORY)
+        );
+    }
+
+    #[test]
+    fn concealment_marker_values() {
+        let password = || FakeBackend::text("hunter2");
+
+        // 允许进入历史、非 secret 的提示不算标记
+        assert_eq!(
+            concealment_marker(&password().with_format(WINDOWS_CAN_INCLUDE_IN_HISTORY, Some(&[1, 0, 0, 0]))),
+            None
+        );
+        assert_eq!(
+            concealment_marker(&password().with_format(KDE_PASSWORD_MANAGER_HINT, Some(b"other"))),
+            None
+        );
+        // 标记的值读不出来时按命中处理
+        assert_eq!(
+            concealment_marker(&password().with_format(KDE_PASSWORD_MANAGER_HINT, None)),
+            Some(KDE_PASSWORD_MANAGER_HINT)
+        );
+        assert_eq!(
+            concealment_marker(&password().with_format(WINDOWS_CAN_INCLUDE_IN_HISTORY, None)),
+            Some(WINDOWS_CAN_INCLUDE_IN_HISTORY)
+        );
+    }
+
+    #[test]
+    fn concealed_content_is_not_recorded() {
+        let captured = capture(&FakeBackend::text("hunter2").with_format(KDE_PASSWORD_MANAGER_HINT, Some(b"secret")));
+        assert!(matches!(captured, Some(CapturedContent::Concealed(KDE_PASSWORD_MANAGER_HINT))));
+
+        let dir = tempfile::tempdir().unwrap();
+        let (database, watcher) = watcher(dir.path());
+        let outcome = tauri::async_runtime::block_on(watcher.record(captured.unwrap())).unwrap();
+        assert!(outcome.item.is_none());
+        assert_eq!(database.get_count().unwrap(), 0);
+        assert_eq!(watcher.concealed_count.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn record_text_and_image() {
+        let dir = tempfile::tempdir().unwrap();
+        let (database, watcher) = watcher(dir.path());
+
+        let item = record(&watcher, &FakeBackend::text("hello")).unwrap();
+        assert_eq!(item.content_type, ClipboardContentType::Text);
+        assert_eq!(item.content, "hello");
+
+        let item = record(&watcher, &FakeBackend::image(tiny_png())).unwrap();
+        assert_eq!(item.content_type, ClipboardContentType::Image);
+        assert!(item.thumbnail_path.is_some());
+        assert_eq!(database.get_count().unwrap(), 2);
+    }
+
+    #[test]
+    fn record_duplicate_merges_into_last_item() {
+        let dir = tempfile::tempdir().unwrap();
+        let (database, watcher) = watcher(dir.path());
+
+        let first = record(&watcher, &FakeBackend::text("same")).unwrap();
+        let second = record(&watcher, &FakeBackend::text("same")).unwrap();
+        assert_eq!(first.id, second.id);
+        assert_eq!(database.get_count().unwrap(), 1);
+    }
+
+    #[test]
+    fn internal_copy_keeps_position_within_window() {
+        let dir = tempfile::tempdir().unwrap();
+        let (database, watcher) = watcher(dir.path());
+        let created_at = |id: i64| database.get_item(id).unwrap().unwrap().created_at;
+
+        let first = record(&watcher, &FakeBackend::text("first")).unwrap();
+        record(&watcher, &FakeBackend::text("second")).unwrap();
+        let original = created_at(first.id);
+
+        // 点击条目复制：未开启自动排序时不更新时间
+        tauri::async_runtime::block_on(watcher.mark_internal_copy());
+        let copied = record(&watcher, &FakeBackend::text("first")).unwrap();
+        assert_eq!(copied.id, first.id);
+        assert_eq!(created_at(first.id), original);
+
+        // 标记过期后按外部复制处理，移到最前
+        *watcher.internal_copy_at.blocking_lock() =
+            Some(Instant::now() - Duration::from_millis(INTERNAL_COPY_WINDOW_MS + 100));
+        record(&watcher, &FakeBackend::text("first")).unwrap();
+        assert!(created_at(first.id) > original);
+    }
+
+    #[test]
+    fn restored_clipboard_is_not_recorded() {
+        let dir = tempfile::tempdir().unwrap();
+        let (database, watcher) = watcher(dir.path());
+
+        // 粘贴后恢复原剪贴板内容引起的变化
+        tauri::async_runtime::block_on(watcher.mark_ignored_change());
+        assert!(record(&watcher, &FakeBackend::text("restored")).is_none());
+        assert_eq!(database.get_count().unwrap(), 0);
+
+        // 忽略标记只作用于一次变化
+        assert!(record(&watcher, &FakeBackend::text("restored")).is_some());
+    }
+}
@@ -0,0 +1,606 @@
+//! 静态加密
+//! - 数据库：SQLCipher 整库加密（含全文索引），使用 32 字节原始密钥
+//! - 图片存储：ChaCha20-Poly1305 逐文件加密，密钥由主密钥派生
+//!
+//! 主密钥来自口令（Argon2id 派生，盐保存在 `encryption.json`）或本地密钥文件（64 位十六进制）。
+//! 口令模式下启动时数据库处于锁定状态，需要用户输入口令解锁。
+
+use argon2::Argon2;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+/// 主密钥长度（字节）
+const KEY_LEN: usize = 32;
+/// 口令派生使用的盐长度（字节）
+const SALT_LEN: usize = 16;
+/// ChaCha20-Poly1305 nonce 长度（字节）
+const NONCE_LEN: usize = 12;
+/// 加密文件头（用于区分加密文件与明文文件）
+const FILE_MAGIC: &[u8] = b"PLENC1\0";
+/// 加密配置文件名
+const CONFIG_FILE: &str = "encryption.json";
+/// 默认密钥文件名
+const DEFAULT_KEY_FILE: &str = "clipboard.key";
+
+/// 密钥来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeySource {
+    /// 用户口令
+    Passphrase,
+    /// 本地密钥文件
+    KeyFile,
+}
+
+/// 加密配置（明文保存在应用数据目录，不包含密钥本身）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncryptionConfig {
+    pub key_source: KeySource,
+    /// 口令派生的盐（十六进制，口令模式）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub salt: Option<String>,
+    /// 密钥文件路径（密钥文件模式）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_file: Option<String>,
+}
+
+impl EncryptionConfig {
+    fn path(app_dir: &Path) -> PathBuf {
+        app_dir.join(CONFIG_FILE)
+    }
+
+    /// 读取加密配置，未启用加密时返回 None
+    pub fn load(app_dir: &Path) -> Result<Option<Self>, String> {
+        let path = Self::path(app_dir);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let json = std::fs::read_to_string(&path).map_err(|e| format!("读取加密配置失败: {}", e))?;
+        serde_json::from_str(&json)
+            .map(Some)
+            .map_err(|e| format!("加密配置格式错误: {}", e))
+    }
+
+    /// 保存加密配置（先写临时文件再替换，避免写到一半）
+    pub fn save(&self, app_dir: &Path) -> Result<(), String> {
+        self.stage(app_dir)?.commit()
+    }
+
+    /// 把配置写到临时文件，`commit` 时才替换正式配置
+    ///
+    /// 用于先确认配置能写入、再改动数据库的场景：数据库改用新密钥之前配置已经落盘，
+    /// 之后只剩一次 rename。
+    pub fn stage(&self, app_dir: &Path) -> Result<StagedFile, String> {
+        let path = Self::path(app_dir);
+        let tmp_path = path.with_extension("json.tmp");
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        std::fs::write(&tmp_path, json).map_err(|e| format!("保存加密配置失败: {}", e))?;
+        Ok(StagedFile { tmp_path, path })
+    }
+
+    /// 删除加密配置（关闭加密后调用）
+    pub fn remove(app_dir: &Path) -> Result<(), String> {
+        match std::fs::remove_file(Self::path(app_dir)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(format!("删除加密配置失败: {}", e))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// 已写到临时路径、尚未替换正式文件的文件（配置或新密钥文件）
+///
+/// 没有 `commit` 就丢弃时删除临时文件。
+#[derive(Debug)]
+pub struct StagedFile {
+    tmp_path: PathBuf,
+    path: PathBuf,
+}
+
+impl StagedFile {
+    /// 用临时文件替换正式文件
+    pub fn commit(mut self) -> Result<(), String> {
+        let tmp_path = std::mem::take(&mut self.tmp_path);
+        std::fs::rename(&tmp_path, &self.path).map_err(|e| {
+            let _ = std::fs::remove_file(&tmp_path);
+            format!("替换 {} 失败: {}", self.path.display(), e)
+        })
+    }
+}
+
+impl Drop for StagedFile {
+    fn drop(&mut self) {
+        if !self.tmp_path.as_os_str().is_empty() {
+            let _ = std::fs::remove_file(&self.tmp_path);
+        }
+    }
+}
+
+/// 为启用加密/轮换密钥生成的新密钥，数据库改用新密钥之后再 `commit` 写入配置和密钥文件
+#[derive(Debug)]
+pub struct NewKey {
+    pub key: EncryptionKey,
+    pub config: EncryptionConfig,
+    /// 新生成的密钥文件（复用已有密钥文件时为 None）
+    key_file: Option<StagedFile>,
+}
+
+impl NewKey {
+    /// 把配置写到临时文件（确认配置能写入后再改动数据库）
+    pub fn stage(self, app_dir: &Path) -> Result<StagedKey, String> {
+        let config = self.config.stage(app_dir)?;
+        Ok(StagedKey {
+            key: self.key,
+            config,
+            key_file: self.key_file,
+        })
+    }
+}
+
+/// 配置和密钥文件都已写到临时路径的新密钥
+#[derive(Debug)]
+pub struct StagedKey {
+    pub key: EncryptionKey,
+    config: StagedFile,
+    key_file: Option<StagedFile>,
+}
+
+impl StagedKey {
+    /// 替换正式的配置和密钥文件（先配置后密钥文件，任一步失败都应回滚数据库并恢复原配置）
+    pub fn commit(self) -> Result<(), String> {
+        self.config.commit()?;
+        match self.key_file {
+            Some(key_file) => key_file.commit(),
+            None => Ok(()),
+        }
+    }
+}
+
+/// 用户提供的密钥材料
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "source", rename_all = "snake_case")]
+pub enum KeyMaterial {
+    /// 口令
+    Passphrase { passphrase: String },
+    /// 密钥文件（不指定路径时使用应用数据目录下的 clipboard.key）
+    KeyFile { path: Option<String> },
+}
+
+impl KeyMaterial {
+    /// 为启用加密/轮换密钥生成新密钥和对应配置
+    ///
+    /// 口令模式每次生成新的盐。密钥文件模式下 `reuse_key_file` 为 true（启用加密）且文件已存在时
+    /// 使用其中的密钥；否则（包括轮换密钥）总是生成随机密钥，先写到 `.new` 临时文件，
+    /// 提交后才替换指定路径，数据库改用新密钥之前原密钥文件保持不变。
+    pub fn create_key(&self, app_dir: &Path, reuse_key_file: bool) -> Result<NewKey, String> {
+        match self {
+            Self::Passphrase { passphrase } => {
+                let salt = random_hex(SALT_LEN);
+                let key = EncryptionKey::derive(passphrase, &salt)?;
+                let config = EncryptionConfig {
+                    key_source: KeySource::Passphrase,
+                    salt: Some(salt),
+                    key_file: None,
+                };
+                Ok(NewKey {
+                    key,
+                    config,
+                    key_file: None,
+                })
+            }
+            Self::KeyFile { path } => {
+                let path = path
+                    .as_ref()
+                    .map(PathBuf::from)
+                    .unwrap_or_else(|| app_dir.join(DEFAULT_KEY_FILE));
+                let config = EncryptionConfig {
+                    key_source: KeySource::KeyFile,
+                    salt: None,
+                    key_file: Some(path.to_string_lossy().to_string()),
+                };
+                if reuse_key_file && path.exists() {
+                    return Ok(NewKey {
+                        key: EncryptionKey::read_key_file(&path)?,
+                        config,
+                        key_file: None,
+                    });
+                }
+
+                let mut tmp_path = path.clone().into_os_string();
+                tmp_path.push(".new");
+                let tmp_path = PathBuf::from(tmp_path);
+                let _ = std::fs::remove_file(&tmp_path);
+                let key = EncryptionKey::generate();
+                key.write_key_file(&tmp_path)?;
+                Ok(NewKey {
+                    key,
+                    config,
+                    key_file: Some(StagedFile { tmp_path, path }),
+                })
+            }
+        }
+    }
+
+    /// 按现有配置还原密钥（用于解锁和校验当前密钥）
+    pub fn resolve_key(&self, config: &EncryptionConfig) -> Result<EncryptionKey, String> {
+        match (self, config.key_source) {
+            (Self::Passphrase { passphrase }, KeySource::Passphrase) => {
+                let salt = config.salt.as_deref().ok_or("加密配置缺少盐")?;
+                EncryptionKey::derive(passphrase, salt)
+            }
+            (Self::KeyFile { path }, KeySource::KeyFile) => {
+                let path = path
+                    .as_deref()
+                    .or(config.key_file.as_deref())
+                    .ok_or("加密配置缺少密钥文件路径")?;
+                EncryptionKey::read_key_file(Path::new(path))
+            }
+            _ => Err("密钥来源与当前加密方式不一致".to_string()),
+        }
+    }
+}
+
+/// 主密钥
+#[derive(Clone, PartialEq, Eq)]
+pub struct EncryptionKey([u8; KEY_LEN]);
+
+impl Drop for EncryptionKey {
+    fn drop(&mut self) {
+        self.0.fill(0);
+    }
+}
+
+impl std::fmt::Debug for EncryptionKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("EncryptionKey(..)")
+    }
+}
+
+impl EncryptionKey {
+    /// 随机生成
+    pub fn generate() -> Self {
+        let mut key = [0u8; KEY_LEN];
+        OsRng.fill_bytes(&mut key);
+        Self(key)
+    }
+
+    /// 从口令派生（Argon2id）
+    pub fn derive(passphrase: &str, salt_hex: &str) -> Result<Self, String> {
+        if passphrase.is_empty() {
+            return Err("口令不能为空".to_string());
+        }
+        let salt = decode_hex(salt_hex).ok_or("加密配置中的盐格式错误")?;
+        let mut key = [0u8; KEY_LEN];
+        Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), &salt, &mut key)
+            .map_err(|e| format!("口令派生密钥失败: {}", e))?;
+        Ok(Self(key))
+    }
+
+    /// 读取密钥文件（64 位十六进制）
+    pub fn read_key_file(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("读取密钥文件失败 ({}): {}", path.display(), e))?;
+        let bytes = decode_hex(text.trim()).ok_or("密钥文件格式错误")?;
+        let key: [u8; KEY_LEN] = bytes.try_into().map_err(|_| "密钥文件长度错误".to_string())?;
+        Ok(Self(key))
+    }
+
+    /// 写入密钥文件（Unix 下仅当前用户可读写）
+    pub fn write_key_file(&self, path: &Path) -> Result<(), String> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| format!("创建密钥目录失败: {}", e))?;
+        }
+
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+
+        use std::io::Write;
+        let mut file = options
+            .open(path)
+            .map_err(|e| format!("创建密钥文件失败 ({}): {}", path.display(), e))?;
+        file.write_all(encode_hex(&self.0).as_bytes())
+            .map_err(|e| format!("写入密钥文件失败: {}", e))
+    }
+
+    /// SQLCipher 原始密钥（`PRAGMA key` 的值，跳过 SQLCipher 自身的口令派生）
+    pub fn sqlcipher_key(&self) -> String {
+        format!("x'{}'", encode_hex(&self.0))
+    }
+
+    /// 图片加密使用的子密钥（与数据库密钥分离）
+    fn file_cipher(&self) -> ChaCha20Poly1305 {
+        let mut hasher = Sha256::new();
+        hasher.update(b"paste-library/images/v1");
+        hasher.update(self.0);
+        let subkey = hasher.finalize();
+        ChaCha20Poly1305::new(Key::from_slice(&subkey))
+    }
+
+    /// 加密文件内容：文件头 + nonce + 密文
+    pub fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, String> {
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .file_cipher()
+            .encrypt(&nonce, plaintext)
+            .map_err(|_| "加密失败".to_string())?;
+
+        let mut data = Vec::with_capacity(FILE_MAGIC.len() + NONCE_LEN + ciphertext.len());
+        data.extend_from_slice(FILE_MAGIC);
+        data.extend_from_slice(&nonce);
+        data.extend_from_slice(&ciphertext);
+        Ok(data)
+    }
+
+    /// 解密 `encrypt` 生成的内容
+    pub fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>, String> {
+        if !is_encrypted(data) || data.len() < FILE_MAGIC.len() + NONCE_LEN {
+            return Err("不是加密文件".to_string());
+        }
+        let (nonce, ciphertext) = data[FILE_MAGIC.len()..].split_at(NONCE_LEN);
+        self.file_cipher()
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| "解密失败：密钥错误或文件已损坏".to_string())
+    }
+}
+
+/// 数据是否为加密文件
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.starts_with(FILE_MAGIC)
+}
+
+fn random_hex(len: usize) -> String {
+    let mut bytes = vec![0u8; len];
+    OsRng.fill_bytes(&mut bytes);
+    encode_hex(&bytes)
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn decode_hex(text: &str) -> Option<Vec<u8>> {
+    text.as_bytes()
+        .chunks(2)
+        .map(|pair| {
+            if pair.len() != 2 || !pair.iter().all(u8::is_ascii_hexdigit) {
+                return None;
+            }
+            u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clipboard::ClipboardManager;
+    use crate::image_handler::ImageStore;
+    use crate::models::AppSettings;
+    use crate::storage::Database;
+    use std::sync::Arc;
+    use tokio::sync::Mutex;
+
+    #[test]
+    fn hex_and_sqlcipher_key_format() {
+        assert_eq!(encode_hex(&[0x00, 0xab, 0x7f]), "00ab7f");
+        assert_eq!(decode_hex("00AB7f"), Some(vec![0x00, 0xab, 0x7f]));
+        assert_eq!(decode_hex("abc"), None);
+        assert_eq!(decode_hex("zz"), None);
+
+        let key = EncryptionKey([0xab; KEY_LEN]);
+        assert_eq!(key.sqlcipher_key(), format!("x'{}'", "ab".repeat(KEY_LEN)));
+        assert_eq!(format!("{:?}", key), "EncryptionKey(..)");
+    }
+
+    #[test]
+    fn file_encryption_round_trip() {
+        let key = EncryptionKey::generate();
+        let encrypted = key.encrypt(b"image bytes").unwrap();
+        assert!(is_encrypted(&encrypted));
+        assert!(!is_encrypted(b"image bytes"));
+        // 每次使用新的 nonce
+        assert_ne!(encrypted, key.encrypt(b"image bytes").unwrap());
+        assert_eq!(key.decrypt(&encrypted).unwrap(), b"image bytes");
+    }
+
+    #[test]
+    fn rejects_tampered_or_foreign_ciphertext() {
+        let key = EncryptionKey::generate();
+        let mut encrypted = key.encrypt(b"image bytes").unwrap();
+
+        assert!(EncryptionKey::generate().decrypt(&encrypted).is_err());
+        assert!(key.decrypt(&encrypted[..FILE_MAGIC.len() + 4]).is_err());
+        assert!(key.decrypt(b"image bytes").is_err());
+
+        let last = encrypted.len() - 1;
+        encrypted[last] ^= 1;
+        assert!(key.decrypt(&encrypted).is_err());
+    }
+
+    #[test]
+    fn key_file_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("keys").join("clipboard.key");
+        let key = EncryptionKey::generate();
+        key.write_key_file(&path).unwrap();
+        assert_eq!(EncryptionKey::read_key_file(&path).unwrap(), key);
+        // 不覆盖已有的密钥文件
+        assert!(EncryptionKey::generate().write_key_file(&path).is_err());
+
+        std::fs::write(&path, "abcd").unwrap();
+        assert!(EncryptionKey::read_key_file(&path).is_err());
+    }
+
+    #[test]
+    fn resolve_key_checks_passphrase_and_source() {
+        let dir = tempfile::tempdir().unwrap();
+        let material = KeyMaterial::Passphrase {
+            passphrase: "hunter2".to_string(),
+        };
+        let new_key = material.create_key(dir.path(), false).unwrap();
+        assert_eq!(material.resolve_key(&new_key.config).unwrap(), new_key.key);
+
+        let wrong = KeyMaterial::Passphrase {
+            passphrase: "hunter3".to_string(),
+        };
+        assert_ne!(wrong.resolve_key(&new_key.config).unwrap(), new_key.key);
+        let empty = KeyMaterial::Passphrase {
+            passphrase: String::new(),
+        };
+        assert!(empty.resolve_key(&new_key.config).is_err());
+        assert!(KeyMaterial::KeyFile { path: None }.resolve_key(&new_key.config).is_err());
+
+        let no_salt = EncryptionConfig {
+            salt: None,
+            ..new_key.config.clone()
+        };
+        assert!(material.resolve_key(&no_salt).is_err());
+    }
+
+    #[test]
+    fn rotation_always_generates_a_new_key_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(DEFAULT_KEY_FILE);
+        let material = KeyMaterial::KeyFile { path: None };
+
+        let first = material.create_key(dir.path(), true).unwrap().stage(dir.path()).unwrap();
+        let first_key = first.key.clone();
+        first.commit().unwrap();
+        assert_eq!(EncryptionKey::read_key_file(&path).unwrap(), first_key);
+
+        // 启用加密时复用已有的密钥文件
+        assert_eq!(material.create_key(dir.path(), true).unwrap().key, first_key);
+
+        // 轮换时生成新密钥，提交前不改动原密钥文件，丢弃时删除临时文件
+        let rotated = material.create_key(dir.path(), false).unwrap();
+        assert_ne!(rotated.key, first_key);
+        assert_eq!(EncryptionKey::read_key_file(&path).unwrap(), first_key);
+        drop(rotated);
+        assert!(!dir.path().join("clipboard.key.new").exists());
+
+        let rotated = material.create_key(dir.path(), false).unwrap().stage(dir.path()).unwrap();
+        let rotated_key = rotated.key.clone();
+        rotated.commit().unwrap();
+        assert_eq!(EncryptionKey::read_key_file(&path).unwrap(), rotated_key);
+        let config = EncryptionConfig::load(dir.path()).unwrap().unwrap();
+        assert_eq!(material.resolve_key(&config).unwrap(), rotated_key);
+    }
+
+    fn manager(dir: &Path) -> (Arc<Database>, ClipboardManager) {
+        let database = Arc::new(Database::new(dir.to_path_buf()).unwrap());
+        let manager = ClipboardManager::new(
+            database.clone(),
+            Arc::new(Mutex::new(AppSettings::default())),
+            Arc::new(ImageStore::new(dir)),
+        );
+        (database, manager)
+    }
+
+    fn tiny_png() -> Vec<u8> {
+        let image = image::RgbaImage::from_pixel(2, 2, image::Rgba([0, 0, 255, 255]));
+        let mut out = std::io::Cursor::new(Vec::new());
+        image.write_to(&mut out, image::ImageFormat::Png).unwrap();
+        out.into_inner()
+    }
+
+    // 按当前配置还原密钥并重新打开数据库
+    fn reopen(dir: &Path, material: &KeyMaterial) -> Database {
+        let config = EncryptionConfig::load(dir).unwrap().unwrap();
+        let key = material.resolve_key(&config).unwrap();
+        Database::open(dir.to_path_buf(), Some(&key)).unwrap()
+    }
+
+    #[test]
+    fn enable_rotate_disable() {
+        let dir = tempfile::tempdir().unwrap();
+        let (database, manager) = manager(dir.path());
+        let image = tauri::async_runtime::block_on(manager.handle_clipboard_image(&tiny_png(), false))
+            .unwrap()
+            .unwrap();
+        let db_path = dir.path().join("clipboard.db");
+        let is_plaintext_db = || std::fs::read(&db_path).unwrap().starts_with(b"SQLite format 3");
+
+        let passphrase = KeyMaterial::Passphrase {
+            passphrase: "hunter2".to_string(),
+        };
+        let status = manager.enable_encryption(&passphrase).unwrap();
+        assert!(status.enabled);
+        assert!(!is_plaintext_db());
+        assert!(is_encrypted(&std::fs::read(&image.content).unwrap()));
+        assert_eq!(reopen(dir.path(), &passphrase).get_count().unwrap(), 1);
+
+        let key_file = KeyMaterial::KeyFile { path: None };
+        manager.rotate_encryption(&passphrase, &key_file).unwrap();
+        let first_key = EncryptionKey::read_key_file(&dir.path().join(DEFAULT_KEY_FILE)).unwrap();
+        assert_eq!(reopen(dir.path(), &key_file).get_count().unwrap(), 1);
+
+        // 密钥文件模式轮换到同一路径也会换成新密钥
+        manager.rotate_encryption(&key_file, &key_file).unwrap();
+        let second_key = EncryptionKey::read_key_file(&dir.path().join(DEFAULT_KEY_FILE)).unwrap();
+        assert_ne!(first_key, second_key);
+        assert!(Database::open(dir.path().to_path_buf(), Some(&first_key)).is_err());
+        assert_eq!(reopen(dir.path(), &key_file).get_count().unwrap(), 1);
+        assert_eq!(manager.read_image(&image.content).unwrap(), tiny_png());
+
+        manager.disable_encryption(&key_file).unwrap();
+        assert!(EncryptionConfig::load(dir.path()).unwrap().is_none());
+        assert!(is_plaintext_db());
+        assert_eq!(std::fs::read(&image.content).unwrap(), tiny_png());
+        assert_eq!(database.get_count().unwrap(), 1);
+    }
+
+    #[test]
+    fn enable_fails_before_encrypting_when_config_cannot_be_written() {
+        let dir = tempfile::tempdir().unwrap();
+        let (database, manager) = manager(dir.path());
+        // 临时配置路径被目录占用，配置写不进去
+        std::fs::create_dir(dir.path().join("encryption.json.tmp")).unwrap();
+
+        let passphrase = KeyMaterial::Passphrase {
+            passphrase: "hunter2".to_string(),
+        };
+        assert!(manager.enable_encryption(&passphrase).is_err());
+        assert!(EncryptionConfig::load(dir.path()).unwrap().is_none());
+        assert!(std::fs::read(dir.path().join("clipboard.db"))
+            .unwrap()
+            .starts_with(b"SQLite format 3"));
+        assert_eq!(database.get_count().unwrap(), 0);
+    }
+
+    #[test]
+    fn rotation_rolls_back_when_key_file_cannot_be_replaced() {
+        let dir = tempfile::tempdir().unwrap();
+        let (database, manager) = manager(dir.path());
+        let passphrase = KeyMaterial::Passphrase {
+            passphrase: "hunter2".to_string(),
+        };
+        manager.enable_encryption(&passphrase).unwrap();
+
+        // 目标路径是非空目录，新密钥文件无法替换过去
+        let blocked = dir.path().join("blocked");
+        std::fs::create_dir(&blocked).unwrap();
+        std::fs::write(blocked.join("file"), "x").unwrap();
+        let key_file = KeyMaterial::KeyFile {
+            path: Some(blocked.to_string_lossy().to_string()),
+        };
+        assert!(manager.rotate_encryption(&passphrase, &key_file).is_err());
+
+        // 数据库换回原密钥，配置仍是口令模式
+        let config = EncryptionConfig::load(dir.path()).unwrap().unwrap();
+        assert_eq!(config.key_source, KeySource::Passphrase);
+        assert_eq!(reopen(dir.path(), &passphrase).get_count().unwrap(), 0);
+        assert_eq!(database.get_count().unwrap(), 0);
+        assert!(!dir.path().join("blocked.new").exists());
+    }
+}
@@ -0,0 +1,387 @@
+//! 图片存储模块
+//! 原图按内容 SHA-256 命名保存（内容寻址，相同图片只保存一份），并在 Rust 侧生成缩略图
+//! 启用静态加密后，原图和缩略图都以加密形式写入磁盘，读取时统一经过 `read_bytes` 解密
+
+use image::{DynamicImage, GenericImageView, ImageFormat};
+use sha2::{Digest, Sha256};
+use std::io::{Cursor, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, SystemTime};
+
+use crate::encryption::{self, EncryptionKey};
+
+/// 缩略图最大边长（像素）
+const THUMBNAIL_SIZE: u32 = 200;
+
+/// 最近写入或复用过的文件在此时间内不会被删除（对应的记录可能尚未写入数据库），
+/// 之后仍未被引用时由垃圾回收删除
+pub const RECENT_FILE_GRACE_PERIOD: Duration = Duration::from_secs(10 * 60);
+
+/// 文件锁：保存图片时的“复用已有文件或写入”与删除文件时的“检查修改时间后删除”互斥，
+/// 保证保存图片返回时文件一定存在，且在宽限期内不会被删除
+static FILE_LOCK: Mutex<()> = Mutex::new(());
+
+fn lock_files() -> std::sync::MutexGuard<'static, ()> {
+    FILE_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// 已保存的图片
+#[derive(Debug, Clone)]
+pub struct StoredImage {
+    /// 原图路径
+    pub original_path: String,
+    /// 缩略图路径（原图不超过缩略图尺寸时与原图相同）
+    pub thumbnail_path: String,
+    pub width: u32,
+    pub height: u32,
+    /// 图片格式（png/jpeg/gif...）
+    pub format: String,
+}
+
+/// 内容寻址的图片存储
+///
+/// 目录结构：
+/// - `images/{sha256}.{ext}`: 原图
+/// - `thumbnails/{sha256}.png`: 缩略图
+pub struct ImageStore {
+    image_dir: PathBuf,
+    thumbnail_dir: PathBuf,
+    /// 文件加密密钥（未启用加密或尚未解锁时为 None）
+    key: RwLock<Option<EncryptionKey>>,
+    /// 更换密钥时还有文件没有重写成功，保留原密钥用于读取和之后的重写
+    retired_keys: RwLock<Vec<EncryptionKey>>,
+}
+
+impl ImageStore {
+    pub fn new(app_dir: &Path) -> Self {
+        Self {
+            image_dir: app_dir.join("images"),
+            thumbnail_dir: app_dir.join("thumbnails"),
+            key: RwLock::new(None),
+            retired_keys: RwLock::new(Vec::new()),
+        }
+    }
+
+    /// 设置文件加密密钥（None 表示之后以明文写入）
+    pub fn set_key(&self, key: Option<EncryptionKey>) {
+        *self.key.write().unwrap() = key;
+    }
+
+    /// 写入文件（设置了密钥时加密）
+    fn write_file(&self, path: &Path, bytes: &[u8]) -> Result<(), String> {
+        let key = self.key.read().unwrap();
+        let data = match key.as_ref() {
+            Some(key) => key.encrypt(bytes)?,
+            None => bytes.to_vec(),
+        };
+        std::fs::write(path, data).map_err(|e| format!("写入图片失败 ({}): {}", path.display(), e))
+    }
+
+    /// 读取图片内容（加密文件自动解密）
+    pub fn read_bytes(&self, path: &str) -> Result<Vec<u8>, String> {
+        let data = std::fs::read(path).map_err(|e| format!("读取图片失败 ({}): {}", path, e))?;
+        if !encryption::is_encrypted(&data) {
+            return Ok(data);
+        }
+        let key = self.key.read().unwrap();
+        let Some(key) = key.as_ref() else {
+            return Err("图片已加密，请先解锁".to_string());
+        };
+        key.decrypt(&data).or_else(|error| {
+            self.retired_keys
+                .read()
+                .unwrap()
+                .iter()
+                .find_map(|old| old.decrypt(&data).ok())
+                .ok_or(error)
+        })
+    }
+
+    /// 保存图片数据（已存在相同内容时直接复用）
+    pub fn store_bytes(&self, bytes: &[u8]) -> Result<StoredImage, String> {
+        let format = image::guess_format(bytes).map_err(|e| format!("无法识别图片格式: {}", e))?;
+        let decoded = image::load_from_memory_with_format(bytes, format)
+            .map_err(|e| format!("图片解码失败: {}", e))?;
+        let (width, height) = decoded.dimensions();
+
+        let hash = format!("{:x}", Sha256::digest(bytes));
+        let extension = format.extensions_str().first().copied().unwrap_or("png");
+
+        std::fs::create_dir_all(&self.image_dir).map_err(|e| format!("创建图片目录失败: {}", e))?;
+        let original_path = self.image_dir.join(format!("{}.{}", hash, extension));
+        let _files = lock_files();
+        if original_path.exists() {
+            Self::touch(&original_path);
+        } else {
+            self.write_file(&original_path, bytes)?;
+        }
+
+        let thumbnail_path = self.write_thumbnail(&hash, &decoded, &original_path)?;
+
+        Ok(StoredImage {
+            original_path: original_path.to_string_lossy().to_string(),
+            thumbnail_path: thumbnail_path.to_string_lossy().to_string(),
+            width,
+            height,
+            format: extension.to_string(),
+        })
+    }
+
+    /// 为原图（重新）生成缩略图，返回缩略图路径
+    ///
+    /// 用于修复缩略图丢失的记录，原图不会被复制。
+    pub fn ensure_thumbnail(&self, original_path: &str) -> Result<String, String> {
+        let bytes = self.read_bytes(original_path)?;
+        let decoded = image::load_from_memory(&bytes).map_err(|e| format!("图片解码失败: {}", e))?;
+        let hash = format!("{:x}", Sha256::digest(&bytes));
+
+        let _files = lock_files();
+        let thumbnail_path = self.write_thumbnail(&hash, &decoded, Path::new(original_path))?;
+        Ok(thumbnail_path.to_string_lossy().to_string())
+    }
+
+    /// 生成缩略图（原图不超过缩略图尺寸时直接使用原图），调用方需持有文件锁
+    fn write_thumbnail(&self, hash: &str, decoded: &DynamicImage, original_path: &Path) -> Result<PathBuf, String> {
+        let (width, height) = decoded.dimensions();
+        if width <= THUMBNAIL_SIZE && height <= THUMBNAIL_SIZE {
+            return Ok(original_path.to_path_buf());
+        }
+
+        std::fs::create_dir_all(&self.thumbnail_dir).map_err(|e| format!("创建缩略图目录失败: {}", e))?;
+        let thumbnail_path = self.thumbnail_dir.join(format!("{}.png", hash));
+        if thumbnail_path.exists() {
+            Self::touch(&thumbnail_path);
+        } else {
+            let mut png = Cursor::new(Vec::new());
+            decoded
+                .thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE)
+                .write_to(&mut png, ImageFormat::Png)
+                .map_err(|e| format!("生成缩略图失败: {}", e))?;
+            self.write_file(&thumbnail_path, png.get_ref())?;
+        }
+        Ok(thumbnail_path)
+    }
+
+    /// 刷新文件修改时间
+    ///
+    /// 复用已有文件时调用，避免记录写入数据库之前文件被当作孤立文件删除。
+    fn touch(path: &Path) {
+        if let Ok(file) = std::fs::File::options().write(true).open(path) {
+            let _ = file.set_modified(SystemTime::now());
+        }
+    }
+
+    /// 删除不再被引用的文件，返回是否删除
+    ///
+    /// 宽限期内写入或复用过的文件跳过（可能正被尚未写入数据库的记录使用），留给垃圾回收；
+    /// 检查和删除在文件锁内完成，不会与 `store_bytes` 交错。
+    pub fn remove_unreferenced_file(path: &Path) -> std::io::Result<bool> {
+        let _files = lock_files();
+        let modified = match std::fs::metadata(path) {
+            Ok(metadata) => metadata.modified().unwrap_or_else(|_| SystemTime::now()),
+            Err(error) if error.kind() == ErrorKind::NotFound => return Ok(false),
+            Err(error) => return Err(error),
+        };
+        let age = SystemTime::now().duration_since(modified).unwrap_or_default();
+        if age < RECENT_FILE_GRACE_PERIOD {
+            return Ok(false);
+        }
+        std::fs::remove_file(path)?;
+        Ok(true)
+    }
+
+    /// 列出存储目录中的所有文件（路径, 大小, 修改时间）
+    pub fn list_files(&self) -> Vec<(PathBuf, u64, SystemTime)> {
+        let mut files = Vec::new();
+        for dir in [&self.image_dir, &self.thumbnail_dir] {
+            let Ok(entries) = std::fs::read_dir(dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let Ok(metadata) = entry.metadata() else {
+                    continue;
+                };
+                if metadata.is_file() {
+                    let modified = metadata.modified().unwrap_or_else(|_| SystemTime::now());
+                    files.push((entry.path(), metadata.len(), modified));
+                }
+            }
+        }
+        files
+    }
+
+    /// 更换文件加密密钥并用新密钥重写存储目录中的所有文件，返回重写的文件数
+    ///
+    /// - `old_key`: 解密现有加密文件的密钥（None 表示现有文件应为明文）
+    /// - `new_key`: 重新加密使用的密钥（None 表示写回明文）
+    ///
+    /// 先切换到新密钥再重写，重写期间新保存的图片不会被重复处理；已经是目标状态的文件会被跳过。
+    /// 单个文件失败不会中断，最后汇总报错，此时原密钥会保留（读取仍可用），
+    /// 直到之后某次更换密钥把所有文件都重写成功。
+    pub fn change_key(&self, old_key: Option<&EncryptionKey>, new_key: Option<EncryptionKey>) -> Result<usize, String> {
+        let old_keys = {
+            let mut retired = self.retired_keys.write().unwrap();
+            if let Some(old_key) = old_key {
+                if !retired.contains(old_key) {
+                    retired.push(old_key.clone());
+                }
+            }
+            retired.clone()
+        };
+        self.set_key(new_key.clone());
+
+        let rewritten = self.reencrypt_all(&old_keys, new_key.as_ref())?;
+        self.retired_keys.write().unwrap().clear();
+        Ok(rewritten)
+    }
+
+    fn reencrypt_all(&self, old_keys: &[EncryptionKey], new_key: Option<&EncryptionKey>) -> Result<usize, String> {
+        let mut rewritten = 0;
+        let mut failed = 0;
+
+        for (path, _, _) in self.list_files() {
+            match Self::reencrypt_file(&path, old_keys, new_key) {
+                Ok(true) => rewritten += 1,
+                Ok(false) => {}
+                Err(error) => {
+                    eprintln!("重写图片文件失败 ({}): {}", path.display(), error);
+                    failed += 1;
+                }
+            }
+        }
+
+        if failed > 0 {
+            return Err(format!("{} 个图片文件处理失败", failed));
+        }
+        Ok(rewritten)
+    }
+
+    /// 重写单个文件，返回是否发生了写入
+    fn reencrypt_file(path: &Path, old_keys: &[EncryptionKey], new_key: Option<&EncryptionKey>) -> Result<bool, String> {
+        let data = std::fs::read(path).map_err(|e| e.to_string())?;
+
+        let plaintext = if encryption::is_encrypted(&data) {
+            if new_key.is_some_and(|key| key.decrypt(&data).is_ok()) {
+                return Ok(false);
+            }
+            if old_keys.is_empty() {
+                return Err("文件已加密但未提供原密钥".to_string());
+            }
+            old_keys
+                .iter()
+                .find_map(|key| key.decrypt(&data).ok())
+                .ok_or("解密失败：原密钥错误或文件已损坏")?
+        } else {
+            if new_key.is_none() {
+                return Ok(false);
+            }
+            data
+        };
+
+        let output = match new_key {
+            Some(key) => key.encrypt(&plaintext)?,
+            None => plaintext,
+        };
+
+        // 先写临时文件再替换，避免中断时留下半个文件
+        let tmp_path = path.with_extension("tmp");
+        std::fs::write(&tmp_path, output).map_err(|e| e.to_string())?;
+        std::fs::rename(&tmp_path, path).map_err(|e| e.to_string())?;
+        Ok(true)
+    }
+
+    /// 导入外部图片文件（如前端插件保存的临时图片）
+    pub fn import_file(&self, path: &str) -> Result<StoredImage, String> {
+        let bytes = std::fs::read(path).map_err(|e| format!("读取图片失败 ({}): {}", path, e))?;
+        self.store_bytes(&bytes)
+    }
+
+    /// 路径是否位于存储目录中
+    pub fn contains(&self, path: &str) -> bool {
+        let path = Path::new(path);
+        path.starts_with(&self.image_dir) || path.starts_with(&self.thumbnail_dir)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+
+    fn png(color: u8) -> Vec<u8> {
+        let image = image::RgbaImage::from_pixel(4, 4, image::Rgba([color, 0, 0, 255]));
+        let mut out = Cursor::new(Vec::new());
+        image.write_to(&mut out, ImageFormat::Png).unwrap();
+        out.into_inner()
+    }
+
+    fn age(path: &str, by: Duration) {
+        let file = File::options().write(true).open(path).unwrap();
+        file.set_modified(SystemTime::now() - by).unwrap();
+    }
+
+    #[test]
+    fn recently_stored_file_is_kept() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = ImageStore::new(dir.path());
+        let stored = store.store_bytes(&png(1)).unwrap();
+
+        // 刚保存的文件可能属于尚未写入数据库的记录
+        assert!(!ImageStore::remove_unreferenced_file(Path::new(&stored.original_path)).unwrap());
+        assert!(Path::new(&stored.original_path).exists());
+    }
+
+    #[test]
+    fn reusing_a_file_restarts_the_grace_period() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = ImageStore::new(dir.path());
+        let bytes = png(2);
+        let stored = store.store_bytes(&bytes).unwrap();
+        let path = Path::new(&stored.original_path);
+
+        age(&stored.original_path, RECENT_FILE_GRACE_PERIOD * 2);
+        // 另一条记录复用同一文件（删除旧记录与写入新记录之间）
+        store.store_bytes(&bytes).unwrap();
+        assert!(!ImageStore::remove_unreferenced_file(path).unwrap());
+        assert!(path.exists());
+
+        // 宽限期过后才删除
+        age(&stored.original_path, RECENT_FILE_GRACE_PERIOD * 2);
+        assert!(ImageStore::remove_unreferenced_file(path).unwrap());
+        assert!(!path.exists());
+        // 已不存在的文件不算错误
+        assert!(!ImageStore::remove_unreferenced_file(path).unwrap());
+
+        // 文件被删除后再次保存时重新写入
+        let restored = store.store_bytes(&bytes).unwrap();
+        assert_eq!(restored.original_path, stored.original_path);
+        assert_eq!(store.read_bytes(&restored.original_path).unwrap(), bytes);
+    }
+
+    #[test]
+    fn old_key_is_kept_until_every_file_is_rewritten() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = ImageStore::new(dir.path());
+        let old_key = EncryptionKey::generate();
+        let new_key = EncryptionKey::generate();
+        store.change_key(None, Some(old_key.clone())).unwrap();
+        let stored = store.store_bytes(&png(3)).unwrap();
+
+        // 用其他密钥加密的文件无法重写，更换密钥失败
+        let foreign = dir.path().join("images").join("foreign.png");
+        std::fs::write(&foreign, EncryptionKey::generate().encrypt(&png(4)).unwrap()).unwrap();
+        assert!(store.change_key(Some(&old_key), Some(new_key.clone())).is_err());
+        assert_eq!(store.read_bytes(&stored.original_path).unwrap(), png(3));
+
+        // 仍使用原密钥的文件在原密钥保留期间可以读取，之后的更换密钥会继续重写
+        let pending = dir.path().join("images").join("pending.png");
+        std::fs::write(&pending, old_key.encrypt(&png(5)).unwrap()).unwrap();
+        assert_eq!(store.read_bytes(&pending.to_string_lossy()).unwrap(), png(5));
+
+        std::fs::remove_file(&foreign).unwrap();
+        store.change_key(Some(&new_key), Some(new_key.clone())).unwrap();
+        assert!(store.retired_keys.read().unwrap().is_empty());
+        assert_eq!(new_key.decrypt(&std::fs::read(&pending).unwrap()).unwrap(), png(5));
+    }
+}
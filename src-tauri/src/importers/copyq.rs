@@ -0,0 +1,488 @@
+//! CopyQ 导出文件（.cpq）导入
+//!
+//! 导出文件是 Qt 4.7 版本的 QDataStream（大端序）：
+//! - 头部 `CopyQ v3`/`CopyQ v4`，之后是一个 QVariantMap，`tabs` 为标签页列表
+//! - 每个标签页是 QVariantMap：`name` 为标签页名，`data` 为序列化后的条目（QByteArray）
+//! - 条目数据：qint32 条目数，每个条目以 qint32 `-2` 开头，之后是 MIME 类型数和
+//!   (压缩的 MIME 名, 是否 zlib 压缩, 数据) 列表；更早的格式直接以 MIME 类型数开头，数据总是压缩的
+//!
+//! CopyQ 不记录条目的复制时间：按标签页内的顺序（最新的在前）从导出文件的修改时间起每条递减一秒。
+//! 标签页名（默认的 clipboard 标签页除外）和条目自己的标签都作为标签。
+//! 导出时如果包含了配置或命令，其中 CopyQ 特有的数据类型可能无法解析，只导出标签页即可。
+
+use chrono::{DateTime, Duration, Utc};
+use flate2::read::ZlibDecoder;
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::path::Path;
+
+use super::{Content, Entry, OnRow, Row};
+
+const MIME_TEXT: &str = "text/plain";
+const MIME_HTML: &str = "text/html";
+const MIME_URI_LIST: &str = "text/uri-list";
+const MIME_PNG: &str = "image/png";
+const MIME_TAGS: &str = "application/x-copyq-tags";
+const COPYQ_MIME_PREFIX: &str = "application/x-copyq-";
+
+/// 默认标签页（名称中的 & 是快捷键标记）
+const DEFAULT_TAB: &str = "clipboard";
+
+/// 条目数据使用 MIME 名压缩的格式
+const ITEM_FORMAT_V2: i32 = -2;
+
+/// 压缩 MIME 名的前缀：首字符为序号，0 表示未压缩
+const MIME_PREFIXES: [&str; 6] = [
+    "application/x-copyq-item",
+    "application/x-copyq-",
+    "text/plain",
+    "text/",
+    "application/",
+    "image/",
+];
+
+pub(super) fn read(path: &Path, on_row: &mut OnRow) -> Result<(), String> {
+    let data = std::fs::read(path).map_err(|e| format!("读取 {} 失败: {}", path.display(), e))?;
+    let exported_at = std::fs::metadata(path)
+        .and_then(|m| m.modified())
+        .map(DateTime::<Utc>::from)
+        .unwrap_or_else(|_| Utc::now());
+
+    let mut stream = Stream::new(&data);
+    let header = stream
+        .bytes()
+        .ok()
+        .flatten()
+        .map(|bytes| header_text(&bytes))
+        .unwrap_or_default();
+    match header.as_str() {
+        "CopyQ v3" | "CopyQ v4" => {}
+        "CopyQ v2" => return Err("不支持 CopyQ 2.x 的旧版导出文件，请用新版 CopyQ 重新导出".to_string()),
+        _ => return Err(format!("{} 不是 CopyQ 导出文件", path.display())),
+    }
+
+    let tabs = stream
+        .find_map_entry("tabs")
+        .map_err(|e| format!("CopyQ 导出文件格式错误: {}", e))?;
+    let Some(Variant::List(tabs)) = tabs else {
+        return Ok(());
+    };
+
+    for tab in tabs {
+        let Variant::Map(mut tab) = tab else {
+            continue;
+        };
+        let name = match tab.remove("name") {
+            Some(Variant::String(name)) => name.replace('&', ""),
+            _ => String::new(),
+        };
+        let Some(Variant::Bytes(items)) = tab.remove("data") else {
+            continue;
+        };
+        let tab_tag = (!name.is_empty() && name != DEFAULT_TAB).then_some(name.clone());
+        read_tab(&name, &items, tab_tag, exported_at, on_row)?;
+    }
+    Ok(())
+}
+
+// 头部在 v4 中是 QByteArray，v3 中是 QString（UTF-16）
+fn header_text(bytes: &[u8]) -> String {
+    if bytes.starts_with(b"CopyQ") {
+        return String::from_utf8_lossy(bytes).to_string();
+    }
+    let units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+        .collect();
+    String::from_utf16_lossy(&units)
+}
+
+fn read_tab(
+    name: &str,
+    data: &[u8],
+    tab_tag: Option<String>,
+    exported_at: DateTime<Utc>,
+    on_row: &mut OnRow,
+) -> Result<(), String> {
+    let mut stream = Stream::new(data);
+    let count = match stream.i32() {
+        Ok(count) => count.max(0),
+        Err(e) => return on_row(Row::Failed(format!("CopyQ 标签页 {}: {}", name, e))),
+    };
+
+    for index in 0..count {
+        let formats = match stream.item() {
+            Ok(formats) => formats,
+            Err(e) => {
+                // 数据已经错位，标签页剩下的条目都无法读取
+                for rest in index..count {
+                    on_row(Row::Failed(format!("CopyQ 标签页 {} 第 {} 条: {}", name, rest + 1, e)))?;
+                }
+                break;
+            }
+        };
+
+        let mut tags: Vec<String> = tab_tag.iter().cloned().collect();
+        if let Some(item_tags) = formats.get(MIME_TAGS) {
+            tags.extend(String::from_utf8_lossy(item_tags).split(',').map(|t| t.trim().to_string()));
+        }
+        let row = match entry_content(&formats) {
+            Some(content) => Row::Entry(Entry {
+                content,
+                created_at: exported_at - Duration::seconds(index as i64),
+                tags,
+            }),
+            None => {
+                let item = format!("CopyQ 标签页 {} 第 {} 条", name, index + 1);
+                // application/x-copyq-* 是 CopyQ 自己的元数据（标签、备注等），不算条目内容
+                let mimes = formats.keys().map(String::as_str).filter(|mime| !mime.starts_with(COPYQ_MIME_PREFIX));
+                match super::unsupported_formats(mimes, &[MIME_TEXT, MIME_HTML, MIME_URI_LIST]) {
+                    Some(formats) => Row::Unsupported { item, formats },
+                    None => Row::Skipped(format!("{}内容为空", item)),
+                }
+            }
+        };
+        on_row(row)?;
+    }
+    Ok(())
+}
+
+// 按 文件 > 图片 > HTML > 文本 的优先级选出内容；uri-list 只有全部是本地文件时才按文件导入
+fn entry_content(formats: &BTreeMap<String, Vec<u8>>) -> Option<Content> {
+    let utf8 = |bytes: &Vec<u8>| String::from_utf8_lossy(bytes).to_string();
+
+    if let Some(uris) = formats.get(MIME_URI_LIST) {
+        let lines: Vec<&str> = std::str::from_utf8(uris)
+            .unwrap_or_default()
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .collect();
+        let files: Vec<String> = lines.iter().filter_map(|line| super::file_url_to_path(line)).collect();
+        if !files.is_empty() && files.len() == lines.len() {
+            return Some(Content::Files(files));
+        }
+    }
+
+    let image = formats.get(MIME_PNG).or_else(|| {
+        formats
+            .iter()
+            .find(|(mime, _)| mime.starts_with("image/") && !mime.starts_with("image/svg"))
+            .map(|(_, bytes)| bytes)
+    });
+    if let Some(image) = image {
+        return Some(Content::Image(image.clone()));
+    }
+
+    super::text_content(formats.get(MIME_HTML).map(utf8), formats.get(MIME_TEXT).map(utf8))
+}
+
+/// QVariant 中用到的值（其余类型读取后丢弃）
+enum Variant {
+    String(String),
+    Bytes(Vec<u8>),
+    List(Vec<Variant>),
+    Map(BTreeMap<String, Variant>),
+    Other,
+}
+
+/// QDataStream 读取（Qt 4.7 格式，大端序）
+struct Stream<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Stream<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self.pos.checked_add(len).filter(|end| *end <= self.data.len());
+        let Some(end) = end else {
+            return Err("数据不完整".to_string());
+        };
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn i32(&mut self) -> Result<i32, String> {
+        Ok(self.u32()? as i32)
+    }
+
+    /// QByteArray（长度 0xFFFFFFFF 表示空值）
+    fn bytes(&mut self) -> Result<Option<Vec<u8>>, String> {
+        match self.u32()? {
+            u32::MAX => Ok(None),
+            len => Ok(Some(self.take(len as usize)?.to_vec())),
+        }
+    }
+
+    /// QString（UTF-16 大端序，长度为字节数）
+    fn string(&mut self) -> Result<String, String> {
+        let Some(bytes) = self.bytes()? else {
+            return Ok(String::new());
+        };
+        let units: Vec<u16> = bytes
+            .chunks_exact(2)
+            .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+            .collect();
+        Ok(String::from_utf16_lossy(&units))
+    }
+
+    /// QVariant：类型 ID、是否为空，之后是对应类型的值
+    fn variant(&mut self) -> Result<Variant, String> {
+        let type_id = self.u32()?;
+        self.u8()?;
+        let value = match type_id {
+            0 => Variant::Other,
+            // bool
+            1 => {
+                self.take(1)?;
+                Variant::Other
+            }
+            // int/uint、QDate、QTime
+            2 | 3 | 14 | 15 => {
+                self.take(4)?;
+                Variant::Other
+            }
+            // qlonglong/qulonglong/double
+            4..=6 => {
+                self.take(8)?;
+                Variant::Other
+            }
+            // QChar
+            7 => {
+                self.take(2)?;
+                Variant::Other
+            }
+            8 => Variant::Map(self.map()?),
+            9 => {
+                let len = self.u32()?;
+                let mut list = Vec::new();
+                for _ in 0..len {
+                    list.push(self.variant()?);
+                }
+                Variant::List(list)
+            }
+            10 => Variant::String(self.string()?),
+            // QStringList
+            11 => {
+                for _ in 0..self.u32()? {
+                    self.string()?;
+                }
+                Variant::Other
+            }
+            12 => Variant::Bytes(self.bytes()?.unwrap_or_default()),
+            // QDateTime：QDate、QTime、时区类型
+            16 => {
+                self.take(9)?;
+                Variant::Other
+            }
+            other => return Err(format!("不支持的数据类型 {}", other)),
+        };
+        Ok(value)
+    }
+
+    fn map(&mut self) -> Result<BTreeMap<String, Variant>, String> {
+        let len = self.u32()?;
+        let mut map = BTreeMap::new();
+        for _ in 0..len {
+            let key = self.string()?;
+            map.insert(key, self.variant()?);
+        }
+        Ok(map)
+    }
+
+    /// 读取顶层 QVariantMap 中的一项，找到后不再读取其余的项（配置中可能有无法解析的类型）
+    fn find_map_entry(&mut self, name: &str) -> Result<Option<Variant>, String> {
+        let len = self.u32()?;
+        for _ in 0..len {
+            let key = self.string()?;
+            let value = self.variant()?;
+            if key == name {
+                return Ok(Some(value));
+            }
+        }
+        Ok(None)
+    }
+
+    /// 一个条目：MIME 类型 -> 数据
+    fn item(&mut self) -> Result<BTreeMap<String, Vec<u8>>, String> {
+        let mut formats = BTreeMap::new();
+        match self.i32()? {
+            ITEM_FORMAT_V2 => {
+                let len = self.i32()?;
+                for _ in 0..len {
+                    let mime = decompress_mime(&self.string()?)?;
+                    let compressed = self.u8()? != 0;
+                    let bytes = self.bytes()?.unwrap_or_default();
+                    let bytes = if compressed { q_uncompress(&bytes)? } else { bytes };
+                    formats.insert(mime, bytes);
+                }
+            }
+            len if len >= 0 => {
+                for _ in 0..len {
+                    let mime = self.string()?;
+                    let bytes = self.bytes()?.unwrap_or_default();
+                    formats.insert(mime, q_uncompress(&bytes)?);
+                }
+            }
+            other => return Err(format!("不支持的条目格式 {}", other)),
+        }
+        Ok(formats)
+    }
+}
+
+fn decompress_mime(mime: &str) -> Result<String, String> {
+    let mut chars = mime.chars();
+    let index = chars
+        .next()
+        .and_then(|c| c.to_digit(16))
+        .ok_or_else(|| format!("无效的 MIME 类型: {}", mime))? as usize;
+    let rest = chars.as_str();
+    match index {
+        0 => Ok(rest.to_string()),
+        _ => MIME_PREFIXES
+            .get(index - 1)
+            .map(|prefix| format!("{}{}", prefix, rest))
+            .ok_or_else(|| format!("无效的 MIME 类型: {}", mime)),
+    }
+}
+
+// qCompress 的数据：4 字节大端序的原始长度，之后是 zlib 流
+fn q_uncompress(bytes: &[u8]) -> Result<Vec<u8>, String> {
+    if bytes.len() <= 4 {
+        return Ok(Vec::new());
+    }
+    let mut out = Vec::new();
+    ZlibDecoder::new(&bytes[4..])
+        .read_to_end(&mut out)
+        .map_err(|e| format!("解压数据失败: {}", e))?;
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ClipboardContentType, ImportSource};
+    use flate2::write::ZlibEncoder;
+    use std::io::Write;
+
+    // QDataStream 写入（与 Stream 对应）
+    #[derive(Default)]
+    struct Writer(Vec<u8>);
+
+    impl Writer {
+        fn u32(&mut self, value: u32) -> &mut Self {
+            self.0.extend_from_slice(&value.to_be_bytes());
+            self
+        }
+
+        fn bytes(&mut self, bytes: &[u8]) -> &mut Self {
+            self.u32(bytes.len() as u32);
+            self.0.extend_from_slice(bytes);
+            self
+        }
+
+        fn string(&mut self, text: &str) -> &mut Self {
+            let bytes: Vec<u8> = text.encode_utf16().flat_map(u16::to_be_bytes).collect();
+            self.bytes(&bytes)
+        }
+
+        // QVariant 头：类型 ID 和非空标记
+        fn variant(&mut self, type_id: u32) -> &mut Self {
+            self.u32(type_id);
+            self.0.push(0);
+            self
+        }
+    }
+
+    fn q_compress(bytes: &[u8]) -> Vec<u8> {
+        let mut encoder = ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(bytes).unwrap();
+        let mut out = (bytes.len() as u32).to_be_bytes().to_vec();
+        out.extend(encoder.finish().unwrap());
+        out
+    }
+
+    // 条目的 (压缩的 MIME 名, 是否压缩, 数据)
+    fn tab_data(items: &[Vec<(&str, bool, Vec<u8>)>]) -> Vec<u8> {
+        let mut data = Writer::default();
+        data.u32(items.len() as u32);
+        for formats in items {
+            data.u32(ITEM_FORMAT_V2 as u32).u32(formats.len() as u32);
+            for (mime, compressed, bytes) in formats {
+                data.string(mime);
+                data.0.push(*compressed as u8);
+                data.bytes(bytes);
+            }
+        }
+        data.0
+    }
+
+    fn fixture() -> Vec<u8> {
+        let work = tab_data(&[
+            vec![("3", false, b"first".to_vec()), ("2tags", false, b"a, b".to_vec())],
+            vec![("4html", true, q_compress(b"<i>html</i>"))],
+            vec![("6svg+xml", false, b"<svg/>".to_vec())],
+            vec![("2item-notes", false, b"note".to_vec())],
+        ]);
+        let clipboard = tab_data(&[vec![("0text/plain", false, b"second".to_vec())]]);
+
+        let mut file = Writer::default();
+        file.bytes(b"CopyQ v4");
+        file.u32(1).string("tabs").variant(9).u32(2);
+        for (name, data) in [("工作", work), ("&clipboard", clipboard)] {
+            file.variant(8).u32(2);
+            file.string("name").variant(10).string(name);
+            file.string("data").variant(12).bytes(&data);
+        }
+        file.0
+    }
+
+    #[test]
+    fn imports_copyq_export() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("export.cpq");
+        std::fs::write(&path, fixture()).unwrap();
+        let exported_at = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(exported_at.into())
+            .unwrap();
+
+        let (report, items) = crate::importers::import_fixture(ImportSource::Copyq, &path);
+        assert_eq!(report.imported, 3);
+        assert_eq!(report.skipped, 1);
+        assert_eq!(report.unsupported, 1);
+        assert_eq!(report.unsupported_reasons, vec!["不支持的格式：image/svg+xml".to_string()]);
+        assert_eq!(report.failed, 0);
+
+        // 标签页内按顺序每条递减一秒
+        let first = &items["first"];
+        assert_eq!(first.content_type, ClipboardContentType::Text);
+        assert_eq!(first.created_at, exported_at);
+        assert_eq!(first.tags, Some(vec!["工作".to_string(), "a".to_string(), "b".to_string()]));
+
+        let html = &items["<i>html</i>"];
+        assert_eq!(html.content_type, ClipboardContentType::Html);
+        assert_eq!(html.created_at, exported_at - Duration::seconds(1));
+
+        let second = &items["second"];
+        assert_eq!(second.created_at, exported_at);
+        assert_eq!(second.tags, None);
+    }
+}
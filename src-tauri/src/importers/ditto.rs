@@ -0,0 +1,337 @@
+//! Ditto（Windows）数据库导入
+//!
+//! Ditto.db 的结构：
+//! - `Main`: 每行一个条目或分组（`bIsGroup = 1`），`lDate` 为 Unix 时间（秒），
+//!   `mText` 为纯文本描述或分组名，`lParentID` 为所在分组
+//! - `Data`: 条目的各个剪贴板格式，`lParentID` 指向 `Main.lID`，`strClipBoardFormat` 为格式名，
+//!   `ooData` 为该格式的原始数据（与 Windows 剪贴板中的数据一致）
+//!
+//! 分组不导入为记录，条目所在分组的名称作为标签。
+
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::path::Path;
+
+use super::{Content, Entry, OnRow, Row};
+use crate::models::ImportSource;
+
+const CF_UNICODETEXT: &str = "CF_UNICODETEXT";
+const CF_TEXT: &str = "CF_TEXT";
+const CF_HTML: &str = "HTML Format";
+const CF_DIB: &str = "CF_DIB";
+const CF_DIBV5: &str = "CF_DIBV5";
+const CF_HDROP: &str = "CF_HDROP";
+const PNG: &str = "PNG";
+
+/// 能导入的格式，条目只有其他格式（如 CF_ENHMETAFILE、CF_BITMAP）时按不支持的格式报告
+const SUPPORTED_FORMATS: [&str; 7] = [CF_UNICODETEXT, CF_TEXT, CF_HTML, CF_DIB, CF_DIBV5, CF_HDROP, PNG];
+
+/// BITMAPINFOHEADER 的 biCompression = BI_BITFIELDS 时，颜色掩码紧跟在 40 字节的头之后
+const BI_BITFIELDS: u32 = 3;
+
+pub(super) fn read(path: &Path, on_row: &mut OnRow) -> Result<(), String> {
+    let conn = super::open_sqlite(path, &["Main", "Data"], ImportSource::Ditto)?;
+    let query_error = |e: rusqlite::Error| format!("读取 Ditto 数据库失败: {}", e);
+
+    let mut groups: HashMap<i64, String> = HashMap::new();
+    {
+        let mut stmt = conn
+            .prepare("SELECT lID, mText FROM Main WHERE bIsGroup = 1")
+            .map_err(query_error)?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, Option<String>>(1)?)))
+            .map_err(query_error)?;
+        for row in rows {
+            let (id, name) = row.map_err(query_error)?;
+            groups.insert(id, name.unwrap_or_default());
+            on_row(Row::Skipped(format!("Ditto 分组 {}", id)))?;
+        }
+    }
+
+    let mut items = conn
+        .prepare("SELECT lID, lDate, mText, lParentID FROM Main WHERE bIsGroup = 0 ORDER BY lID")
+        .map_err(query_error)?;
+    let mut formats = conn
+        .prepare("SELECT strClipBoardFormat, ooData FROM Data WHERE lParentID = ?1")
+        .map_err(query_error)?;
+
+    let mut rows = items.query([]).map_err(query_error)?;
+    while let Some(row) = rows.next().map_err(query_error)? {
+        let id: i64 = row.get(0).map_err(query_error)?;
+        let fields = (|| {
+            Ok::<_, rusqlite::Error>((
+                row.get::<_, Option<i64>>(1)?,
+                row.get::<_, Option<String>>(2)?,
+                row.get::<_, Option<i64>>(3)?,
+            ))
+        })();
+        let (date, description, parent) = match fields {
+            Ok(fields) => fields,
+            Err(e) => {
+                on_row(Row::Failed(format!("Ditto 条目 {}: {}", id, e)))?;
+                continue;
+            }
+        };
+
+        let data = formats
+            .query_map([id], |row| Ok((row.get::<_, String>(0)?, row.get::<_, Vec<u8>>(1)?)))
+            .and_then(|rows| rows.collect::<Result<HashMap<_, _>, _>>());
+        let data = match data {
+            Ok(data) => data,
+            Err(e) => {
+                on_row(Row::Failed(format!("Ditto 条目 {}: {}", id, e)))?;
+                continue;
+            }
+        };
+
+        // 先排除只有不支持格式的条目，否则 mText 中的格式描述会被当作文本导入
+        if let Some(formats) = super::unsupported_formats(data.keys().map(String::as_str), &SUPPORTED_FORMATS) {
+            on_row(Row::Unsupported {
+                item: format!("Ditto 条目 {}", id),
+                formats,
+            })?;
+            continue;
+        }
+
+        let row = match entry_content(&data, description) {
+            Ok(Some(content)) => Row::Entry(Entry {
+                content,
+                created_at: date
+                    .and_then(|secs| DateTime::from_timestamp(secs, 0))
+                    .unwrap_or_else(Utc::now),
+                tags: parent.and_then(|p| groups.get(&p)).cloned().into_iter().collect(),
+            }),
+            Ok(None) => Row::Skipped(format!("Ditto 条目 {} 内容为空", id)),
+            Err(e) => Row::Failed(format!("Ditto 条目 {}: {}", id, e)),
+        };
+        on_row(row)?;
+    }
+    Ok(())
+}
+
+// 按 文件 > 图片 > HTML > 文本 的优先级选出内容；没有文本格式时用 mText 作为纯文本
+fn entry_content(data: &HashMap<String, Vec<u8>>, description: Option<String>) -> Result<Option<Content>, String> {
+    if let Some(drop) = data.get(CF_HDROP) {
+        return Ok(Some(Content::Files(parse_hdrop(drop)?)));
+    }
+    if let Some(png) = data.get(PNG) {
+        return Ok(Some(Content::Image(png.clone())));
+    }
+    if let Some(dib) = data.get(CF_DIBV5).or_else(|| data.get(CF_DIB)) {
+        return Ok(Some(Content::Image(dib_to_png(dib)?)));
+    }
+
+    let text = data
+        .get(CF_UNICODETEXT)
+        .map(|bytes| decode_utf16le(bytes))
+        .or_else(|| data.get(CF_TEXT).map(|bytes| until_nul(&String::from_utf8_lossy(bytes)).to_string()))
+        .or(description);
+    let html = data.get(CF_HTML).map(|bytes| html_fragment(bytes));
+    Ok(super::text_content(html, text))
+}
+
+// UTF-16LE 文本，去掉结尾的 NUL
+fn decode_utf16le(bytes: &[u8]) -> String {
+    let units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .collect();
+    until_nul(&String::from_utf16_lossy(&units)).to_string()
+}
+
+fn until_nul(text: &str) -> &str {
+    text.split('\0').next().unwrap_or_default()
+}
+
+// CF_HDROP（DROPFILES 结构）：pFiles 偏移之后是以 NUL 分隔、双 NUL 结尾的路径列表，fWide 表示 UTF-16
+fn parse_hdrop(data: &[u8]) -> Result<Vec<String>, String> {
+    if data.len() < 20 {
+        return Err("CF_HDROP 数据不完整".to_string());
+    }
+    let offset = u32::from_le_bytes([data[0], data[1], data[2], data[3]]) as usize;
+    let wide = u32::from_le_bytes([data[16], data[17], data[18], data[19]]) != 0;
+    let list = data.get(offset..).ok_or("CF_HDROP 数据不完整")?;
+
+    let text = if wide {
+        let units: Vec<u16> = list
+            .chunks_exact(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .collect();
+        String::from_utf16_lossy(&units)
+    } else {
+        String::from_utf8_lossy(list).to_string()
+    };
+    Ok(text
+        .split("\0\0")
+        .next()
+        .unwrap_or_default()
+        .split('\0')
+        .filter(|path| !path.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+// CF_DIB/CF_DIBV5 缺少 BMP 文件头，补上后解码并转为 PNG
+fn dib_to_png(dib: &[u8]) -> Result<Vec<u8>, String> {
+    if dib.len() < 40 {
+        return Err("位图数据不完整".to_string());
+    }
+    let u32_at = |i: usize| u32::from_le_bytes([dib[i], dib[i + 1], dib[i + 2], dib[i + 3]]);
+    let header_size = u32_at(0);
+    let bit_count = u16::from_le_bytes([dib[14], dib[15]]);
+    let compression = u32_at(16);
+    let colors_used = u32_at(32);
+
+    let palette = match colors_used {
+        0 if bit_count <= 8 => 1u32 << bit_count,
+        n => n,
+    };
+    let masks = if header_size == 40 && compression == BI_BITFIELDS { 12 } else { 0 };
+    // 损坏的头部可能给出极大的值，溢出时交给解码报错
+    let pixel_offset = header_size
+        .saturating_add(14 + masks)
+        .saturating_add(palette.saturating_mul(4));
+
+    let mut bmp = Vec::with_capacity(14 + dib.len());
+    bmp.extend_from_slice(b"BM");
+    bmp.extend_from_slice(&((14 + dib.len()) as u32).to_le_bytes());
+    bmp.extend_from_slice(&[0; 4]);
+    bmp.extend_from_slice(&pixel_offset.to_le_bytes());
+    bmp.extend_from_slice(dib);
+
+    let image = image::load_from_memory_with_format(&bmp, image::ImageFormat::Bmp)
+        .map_err(|e| format!("位图解码失败: {}", e))?;
+    super::encode_png(&image)
+}
+
+// CF_HTML：头部的 StartFragment/EndFragment 是片段的字节偏移，偏移无效时退回注释标记
+fn html_fragment(data: &[u8]) -> String {
+    let text = String::from_utf8_lossy(data);
+    let offset = |name: &str| {
+        text.lines()
+            .take_while(|line| !line.starts_with('<'))
+            .find_map(|line| line.strip_prefix(name))
+            .and_then(|value| value.trim().parse::<usize>().ok())
+    };
+    if let (Some(start), Some(end)) = (offset("StartFragment:"), offset("EndFragment:")) {
+        if let Some(fragment) = data.get(start..end) {
+            return String::from_utf8_lossy(fragment).to_string();
+        }
+    }
+
+    const START: &str = "<!--StartFragment-->";
+    const END: &str = "<!--EndFragment-->";
+    if let (Some(start), Some(end)) = (text.find(START), text.rfind(END)) {
+        if start + START.len() <= end {
+            return text[start + START.len()..end].to_string();
+        }
+    }
+    match text.find('<') {
+        Some(start) => until_nul(&text[start..]).to_string(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ClipboardContentType;
+    use rusqlite::{params, Connection};
+
+    fn utf16le(text: &str) -> Vec<u8> {
+        text.encode_utf16().chain([0]).flat_map(u16::to_le_bytes).collect()
+    }
+
+    // 2x2、24 位、无压缩的 BITMAPINFOHEADER + 像素（每行补齐到 4 字节）
+    fn tiny_dib() -> Vec<u8> {
+        let mut dib = Vec::new();
+        for value in [40u32, 2, 2] {
+            dib.extend_from_slice(&value.to_le_bytes());
+        }
+        dib.extend_from_slice(&1u16.to_le_bytes());
+        dib.extend_from_slice(&24u16.to_le_bytes());
+        for value in [0u32, 16, 0, 0, 0, 0] {
+            dib.extend_from_slice(&value.to_le_bytes());
+        }
+        for _ in 0..2 {
+            dib.extend_from_slice(&[0, 0, 255, 0, 0, 255, 0, 0]);
+        }
+        dib
+    }
+
+    fn fixture(path: &Path) {
+        let conn = Connection::open(path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE Main (lID INTEGER PRIMARY KEY, lDate INTEGER, mText TEXT, lParentID INTEGER, bIsGroup INTEGER);
+             CREATE TABLE Data (lID INTEGER PRIMARY KEY, lParentID INTEGER, strClipBoardFormat TEXT, ooData BLOB);",
+        )
+        .unwrap();
+        let main = [
+            (1, 0, "工作", 0, 1),
+            (2, 1_700_000_000, "hello", 1, 0),
+            (3, 1_700_000_100, "粗体", 0, 0),
+            (4, 1_700_000_200, "PNG", 0, 0),
+            (5, 1_700_000_300, "CF_DIB", 0, 0),
+            (6, 1_700_000_400, "CF_ENHMETAFILE", 0, 0),
+            (7, 1_700_000_500, "", 0, 0),
+            (8, 1_700_000_600, "文件", 0, 0),
+        ];
+        for (id, date, text, parent, group) in main {
+            conn.execute("INSERT INTO Main VALUES (?1, ?2, ?3, ?4, ?5)", params![id, date, text, parent, group])
+                .unwrap();
+        }
+        let html = "Version:0.9\r\n<html><body><!--StartFragment--><b>粗体</b><!--EndFragment--></body></html>";
+        let data: [(i64, &str, Vec<u8>); 7] = [
+            (2, CF_UNICODETEXT, utf16le("hello")),
+            (3, CF_HTML, html.as_bytes().to_vec()),
+            (4, PNG, crate::importers::tiny_png([0, 128, 255])),
+            (5, CF_DIB, tiny_dib()),
+            (6, "CF_ENHMETAFILE", vec![1, 2, 3]),
+            (7, CF_UNICODETEXT, utf16le("   ")),
+            (8, CF_HDROP, vec![20, 0, 0]),
+        ];
+        for (parent, format, bytes) in data {
+            conn.execute(
+                "INSERT INTO Data (lParentID, strClipBoardFormat, ooData) VALUES (?1, ?2, ?3)",
+                params![parent, format, bytes],
+            )
+            .unwrap();
+        }
+    }
+
+    #[test]
+    fn imports_ditto_database() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Ditto.db");
+        fixture(&path);
+
+        let (report, items) = crate::importers::import_fixture(ImportSource::Ditto, &path);
+        assert_eq!(report.imported, 4);
+        assert_eq!(report.images, 2);
+        // 分组和空白文本
+        assert_eq!(report.skipped, 2);
+        assert_eq!(report.unsupported, 1);
+        assert_eq!(report.unsupported_reasons, vec!["不支持的格式：CF_ENHMETAFILE".to_string()]);
+        assert_eq!(report.failed, 1);
+
+        let text = &items["hello"];
+        assert_eq!(text.content_type, ClipboardContentType::Text);
+        assert_eq!(text.created_at.timestamp(), 1_700_000_000);
+        assert_eq!(text.tags, Some(vec!["工作".to_string()]));
+
+        let html = &items["<b>粗体</b>"];
+        assert_eq!(html.content_type, ClipboardContentType::Html);
+        assert_eq!(html.text_content.as_deref(), Some("粗体"));
+        assert_eq!(html.created_at.timestamp(), 1_700_000_100);
+
+        let mut images: Vec<i64> = items
+            .values()
+            .filter(|item| item.content_type == ClipboardContentType::Image)
+            .map(|item| item.created_at.timestamp())
+            .collect();
+        images.sort();
+        assert_eq!(images, vec![1_700_000_200, 1_700_000_300]);
+        // 只有不支持格式的条目不会把 mText 当作文本导入
+        assert!(!items.contains_key("CF_ENHMETAFILE"));
+    }
+}
@@ -0,0 +1,425 @@
+//! 从其他剪贴板管理器导入（Ditto、CopyQ、Maccy、纯文本片段）
+//!
+//! 各格式的读取模块只负责把源数据转换成 [`Row`] 逐条交给回调；图片写入图片存储、
+//! 生成记录和按批写入数据库统一在这里处理，所以每种来源的导入结果结构相同。
+//! 外部数据库一律只读打开，导入前最好先退出对应的应用。
+
+mod copyq;
+mod ditto;
+mod maccy;
+mod snippets;
+
+use chrono::{DateTime, Duration, Utc};
+use rusqlite::{Connection, OpenFlags};
+use std::path::Path;
+
+use crate::clipboard::ClipboardManager;
+use crate::clipboard_watcher::classify_paths;
+use crate::image_handler::ImageStore;
+use crate::models::{AppSettings, ClipboardContentType, ClipboardItem, ExternalImportReport, ImportSource};
+use crate::sensitive::{self, SensitiveAction, SensitiveDetector};
+use crate::storage::Database;
+
+/// 每个事务写入的记录数
+const BATCH_SIZE: usize = 500;
+
+/// 从源数据读出的一个条目
+pub enum Row {
+    Entry(Entry),
+    /// 有意不导入（如分组、空内容），附带原因
+    Skipped(String),
+    /// 条目的格式都不支持，附带条目说明和格式名
+    Unsupported { item: String, formats: Vec<String> },
+    /// 数据损坏等原因无法导入，附带原因
+    Failed(String),
+}
+
+/// 可以导入的条目
+pub struct Entry {
+    pub content: Content,
+    pub created_at: DateTime<Utc>,
+    pub tags: Vec<String>,
+}
+
+/// 条目内容（按与剪贴板监听相同的优先级选出一种：文件 > 图片 > HTML > 文本）
+pub enum Content {
+    Text(String),
+    Html { html: String, text: String },
+    /// 图片原始数据（PNG/JPEG 等 image crate 能识别的格式）
+    Image(Vec<u8>),
+    Files(Vec<String>),
+    Snippet(String),
+}
+
+/// 导入文本时的敏感内容处理（与剪贴板监听使用相同的设置）
+pub struct SensitiveRules {
+    action: SensitiveAction,
+    detector: SensitiveDetector,
+    expire_minutes: i64,
+}
+
+impl SensitiveRules {
+    /// 设置中关闭了敏感内容检测时返回 None
+    pub fn from_settings(settings: &AppSettings) -> Option<Self> {
+        Some(Self {
+            action: SensitiveAction::from_setting(&settings.sensitive_action)?,
+            detector: SensitiveDetector::lenient(&settings.sensitive_patterns),
+            expire_minutes: settings.sensitive_expire_minutes.max(1),
+        })
+    }
+}
+
+/// 读取回调：返回错误时中止导入（只有数据库写入失败这类无法继续的错误）
+type OnRow<'a> = dyn FnMut(Row) -> Result<(), String> + 'a;
+
+/// 从 path 导入 source 格式的数据，文本内容按 sensitive 检测敏感信息
+pub fn import(
+    database: &Database,
+    image_store: &ImageStore,
+    sensitive: Option<&SensitiveRules>,
+    source: ImportSource,
+    path: &Path,
+) -> Result<ExternalImportReport, String> {
+    if !path.exists() {
+        return Err(format!("{} 不存在", path.display()));
+    }
+
+    let mut writer = Writer {
+        database,
+        image_store,
+        sensitive,
+        report: ExternalImportReport::new(source),
+        batch: Vec::with_capacity(BATCH_SIZE),
+    };
+    let mut on_row = |row: Row| writer.push(row);
+    match source {
+        ImportSource::Ditto => ditto::read(path, &mut on_row)?,
+        ImportSource::Copyq => copyq::read(path, &mut on_row)?,
+        ImportSource::Maccy => maccy::read(path, &mut on_row)?,
+        ImportSource::Snippets => snippets::read(path, &mut on_row)?,
+    }
+    writer.flush()?;
+
+    let report = writer.report;
+    println!(
+        "从 {} 导入 {} 条记录（跳过 {} 条，不支持的格式 {} 条，失败 {} 条）",
+        source.label(),
+        report.imported,
+        report.skipped,
+        report.unsupported,
+        report.failed
+    );
+    Ok(report)
+}
+
+// 把条目转换成记录并按批写入
+struct Writer<'a> {
+    database: &'a Database,
+    image_store: &'a ImageStore,
+    sensitive: Option<&'a SensitiveRules>,
+    report: ExternalImportReport,
+    batch: Vec<ClipboardItem>,
+}
+
+impl Writer<'_> {
+    fn push(&mut self, row: Row) -> Result<(), String> {
+        match row {
+            Row::Entry(entry) => match self.build_item(entry) {
+                Ok(Some(item)) => {
+                    self.batch.push(item);
+                    if self.batch.len() >= BATCH_SIZE {
+                        self.flush()?;
+                    }
+                }
+                Ok(None) => self.report.skipped += 1,
+                Err(e) => {
+                    eprintln!("导入条目失败: {}", e);
+                    self.report.failed += 1;
+                }
+            },
+            Row::Skipped(reason) => {
+                eprintln!("跳过条目: {}", reason);
+                self.report.skipped += 1;
+            }
+            Row::Unsupported { item, formats } => {
+                let reason = format!("不支持的格式：{}", formats.join(", "));
+                eprintln!("跳过 {}: {}", item, reason);
+                self.report.unsupported += 1;
+                if !self.report.unsupported_reasons.contains(&reason) {
+                    self.report.unsupported_reasons.push(reason);
+                }
+            }
+            Row::Failed(reason) => {
+                eprintln!("导入条目失败: {}", reason);
+                self.report.failed += 1;
+            }
+        }
+        Ok(())
+    }
+
+    // 与剪贴板监听写入的记录结构一致（内容哈希相同，之后再次复制会合并到导入的记录）；空内容返回 None
+    fn build_item(&mut self, entry: Entry) -> Result<Option<ClipboardItem>, String> {
+        let mut item = ClipboardItem {
+            id: 0,
+            content_type: ClipboardContentType::Text,
+            content: String::new(),
+            created_at: entry.created_at,
+            content_hash: String::new(),
+            text_content: None,
+            metadata: None,
+            file_paths: None,
+            thumbnail_path: None,
+            tags: None,
+            is_sensitive: false,
+            expires_at: None,
+            source_app: None,
+        };
+
+        match entry.content {
+            Content::Text(text) => {
+                if text.trim().is_empty() {
+                    return Ok(None);
+                }
+                item.content_hash = ClipboardManager::content_hash(&text, None);
+                item.content = text.clone();
+                item.text_content = Some(text);
+            }
+            Content::Html { html, text } => {
+                if html.trim().is_empty() {
+                    return Ok(None);
+                }
+                item.content_type = ClipboardContentType::Html;
+                item.content_hash = ClipboardManager::content_hash(&html, None);
+                item.content = html;
+                item.text_content = Some(text);
+            }
+            Content::Image(bytes) => {
+                let stored = self.image_store.store_bytes(&bytes)?;
+                self.report.images += 1;
+                item.content_type = ClipboardContentType::Image;
+                item.content_hash = ClipboardManager::content_hash(&stored.original_path, None);
+                item.metadata = Some(ClipboardManager::image_metadata(&stored, None));
+                item.content = stored.original_path;
+                item.thumbnail_path = Some(stored.thumbnail_path);
+            }
+            Content::Files(paths) => {
+                if paths.is_empty() {
+                    return Ok(None);
+                }
+                let (content_type, metadata) = classify_paths(&paths);
+                item.content_type = content_type;
+                item.content = paths.join("\n");
+                item.content_hash = ClipboardManager::content_hash(&item.content, Some(&paths));
+                item.metadata = Some(metadata);
+                item.file_paths = Some(paths);
+            }
+            Content::Snippet(body) => {
+                if body.trim().is_empty() {
+                    return Ok(None);
+                }
+                ClipboardManager::validate_snippet(&body)?;
+                item.content_type = ClipboardContentType::Snippet;
+                item.content_hash = ClipboardManager::snippet_hash(&body);
+                item.content = body.clone();
+                item.text_content = Some(body);
+            }
+        }
+
+        if matches!(item.content_type, ClipboardContentType::Text | ClipboardContentType::Html) && !self.apply_sensitive(&mut item) {
+            return Ok(None);
+        }
+
+        let mut tags: Vec<String> = Vec::new();
+        for tag in entry.tags.iter().map(|t| t.trim()).filter(|t| !t.is_empty()) {
+            if !tags.iter().any(|t| t == tag) {
+                tags.push(tag.to_string());
+            }
+        }
+        item.tags = (!tags.is_empty()).then_some(tags);
+        Ok(Some(item))
+    }
+
+    // 与剪贴板监听相同：丢弃（返回 false）、打码后保存为纯文本，或标记为敏感并设置过期时间
+    fn apply_sensitive(&self, item: &mut ClipboardItem) -> bool {
+        let Some(rules) = self.sensitive else {
+            return true;
+        };
+        let text = item.text_content.clone().unwrap_or_default();
+        let findings = rules.detector.detect(&text);
+        if findings.is_empty() {
+            return true;
+        }
+
+        match rules.action {
+            SensitiveAction::Drop => {
+                eprintln!("跳过条目: 检测到敏感内容");
+                return false;
+            }
+            SensitiveAction::Mask => {
+                let masked = sensitive::mask(&text, &findings);
+                item.content_type = ClipboardContentType::Text;
+                item.content_hash = ClipboardManager::content_hash(&masked, None);
+                item.content = masked.clone();
+                item.text_content = Some(masked);
+            }
+            SensitiveAction::Expire => {
+                item.is_sensitive = true;
+                item.expires_at = Some(Utc::now() + Duration::minutes(rules.expire_minutes));
+            }
+        }
+        true
+    }
+
+    fn flush(&mut self) -> Result<(), String> {
+        if self.batch.is_empty() {
+            return Ok(());
+        }
+        for result in self.database.import_items(&self.batch).map_err(|e| e.to_string())? {
+            match result {
+                Ok(_) => self.report.imported += 1,
+                Err(e) => {
+                    eprintln!("写入记录失败: {}", e);
+                    self.report.failed += 1;
+                }
+            }
+        }
+        self.batch.clear();
+        Ok(())
+    }
+}
+
+/// 只读打开外部 SQLite 数据库，并确认包含所需的表
+fn open_sqlite(path: &Path, tables: &[&str], source: ImportSource) -> Result<Connection, String> {
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX)
+        .map_err(|e| format!("打开 {} 失败: {}", path.display(), e))?;
+    for table in tables {
+        let exists = conn
+            .query_row(
+                "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = ?1",
+                [table],
+                |row| row.get::<_, i64>(0),
+            )
+            .map_err(|_| format!("{} 不是 SQLite 数据库", path.display()))?;
+        if exists == 0 {
+            return Err(format!("不是 {} 的数据库：缺少 {} 表", source.label(), table));
+        }
+    }
+    Ok(conn)
+}
+
+/// 条目至少有一种格式、且都不在 supported 中时返回排序后的格式名，否则返回 None
+fn unsupported_formats<'a>(formats: impl IntoIterator<Item = &'a str>, supported: &[&str]) -> Option<Vec<String>> {
+    let mut names: Vec<String> = Vec::new();
+    for format in formats {
+        if supported.contains(&format) {
+            return None;
+        }
+        names.push(format.to_string());
+    }
+    names.sort();
+    names.dedup();
+    (!names.is_empty()).then_some(names)
+}
+
+/// 把图片重新编码为 PNG（BMP/TIFF 等 WebView 不一定能显示的格式）
+fn encode_png(image: &image::DynamicImage) -> Result<Vec<u8>, String> {
+    let mut out = std::io::Cursor::new(Vec::new());
+    image
+        .write_to(&mut out, image::ImageFormat::Png)
+        .map_err(|e| format!("图片编码失败: {}", e))?;
+    Ok(out.into_inner())
+}
+
+/// `file://` URL 转本地路径（解码百分号转义；Windows 的 `file:///C:/...` 去掉开头的 /）
+fn file_url_to_path(url: &str) -> Option<String> {
+    let rest = url.trim().strip_prefix("file://")?;
+    // 跳过主机名（通常为空或 localhost）
+    let path = &rest[rest.find('/')?..];
+
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .filter(|_| bytes[i] == b'%')
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match hex {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    let path = String::from_utf8(decoded).ok()?;
+
+    let is_drive = path.len() >= 3 && path.as_bytes()[2] == b':' && path.as_bytes()[1].is_ascii_alphabetic();
+    Some(if is_drive { path[1..].to_string() } else { path })
+}
+
+/// 没有纯文本时从 HTML 粗略提取文本（去掉标签，解码常见实体）
+fn html_to_text(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    text.replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+        .trim()
+        .to_string()
+}
+
+/// 选出 HTML 或纯文本内容（HTML 缺少纯文本时从 HTML 提取）
+fn text_content(html: Option<String>, text: Option<String>) -> Option<Content> {
+    let text = text.filter(|t| !t.trim().is_empty());
+    match html.filter(|h| !h.trim().is_empty()) {
+        Some(html) => {
+            let text = text.unwrap_or_else(|| html_to_text(&html));
+            Some(Content::Html { html, text })
+        }
+        None => text.map(Content::Text),
+    }
+}
+
+/// 把 path 导入到临时目录中的新数据库，返回导入结果和导入后的记录（按内容索引）
+#[cfg(test)]
+fn import_fixture(
+    source: ImportSource,
+    path: &Path,
+) -> (ExternalImportReport, std::collections::HashMap<String, ClipboardItem>) {
+    let app_dir = tempfile::tempdir().unwrap();
+    let database = Database::new(app_dir.path().to_path_buf()).unwrap();
+    let image_store = ImageStore::new(app_dir.path());
+    let report = import(&database, &image_store, None, source, path).unwrap();
+    let items = database
+        .get_history(1000, 0)
+        .unwrap()
+        .into_iter()
+        .map(|item| (item.content.clone(), item))
+        .collect();
+    (report, items)
+}
+
+/// 测试用的单色 PNG
+#[cfg(test)]
+fn tiny_png(rgb: [u8; 3]) -> Vec<u8> {
+    let image = image::RgbImage::from_pixel(2, 2, image::Rgb(rgb));
+    let mut out = std::io::Cursor::new(Vec::new());
+    image.write_to(&mut out, image::ImageFormat::Png).unwrap();
+    out.into_inner()
+}
@@ -0,0 +1,102 @@
+//! 纯文本片段集合导入
+//!
+//! - 目录：其中每个文件（含子目录，跳过隐藏文件）是一个片段，所在子目录的相对路径作为标签
+//! - 单个文件：以只有 `---` 的行分隔多个片段
+//!
+//! 时间取文件的修改时间；不是 UTF-8 文本的文件跳过，占位符写法无效的片段记为失败。
+
+use chrono::{DateTime, Utc};
+use std::path::Path;
+
+use super::{Content, Entry, OnRow, Row};
+
+/// 单个文件内的片段分隔行
+const SEPARATOR: &str = "---";
+
+pub(super) fn read(path: &Path, on_row: &mut OnRow) -> Result<(), String> {
+    if path.is_dir() {
+        return read_dir(path, on_row);
+    }
+
+    let (text, modified) = match read_text(path)? {
+        Some(file) => file,
+        None => return on_row(Row::Skipped(format!("{} 不是 UTF-8 文本", path.display()))),
+    };
+    let mut body = Vec::new();
+    for line in text.lines().chain(std::iter::once(SEPARATOR)) {
+        if line.trim_end() != SEPARATOR {
+            body.push(line);
+            continue;
+        }
+        let snippet = body.join("\n").trim_matches('\n').to_string();
+        body.clear();
+        // 连续的分隔行、文件开头或结尾的分隔行不算空片段
+        if snippet.trim().is_empty() {
+            continue;
+        }
+        on_row(Row::Entry(Entry {
+            content: Content::Snippet(snippet),
+            created_at: modified,
+            tags: Vec::new(),
+        }))?;
+    }
+    Ok(())
+}
+
+// 按文件名排序遍历，保证每次导入的顺序相同
+fn read_dir(root: &Path, on_row: &mut OnRow) -> Result<(), String> {
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let mut entries: Vec<_> = std::fs::read_dir(&dir)
+            .map_err(|e| format!("读取目录 {} 失败: {}", dir.display(), e))?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                !path
+                    .file_name()
+                    .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+            })
+            .collect();
+        entries.sort();
+
+        for path in entries {
+            if path.is_dir() {
+                pending.push(path);
+                continue;
+            }
+            let row = match read_text(&path) {
+                Ok(Some((text, modified))) => Row::Entry(Entry {
+                    content: Content::Snippet(text.trim_end_matches(['\r', '\n']).to_string()),
+                    created_at: modified,
+                    tags: folder_tag(root, &path).into_iter().collect(),
+                }),
+                Ok(None) => Row::Skipped(format!("{} 不是 UTF-8 文本", path.display())),
+                Err(e) => Row::Failed(e),
+            };
+            on_row(row)?;
+        }
+    }
+    Ok(())
+}
+
+// 文件所在目录相对根目录的路径（以 / 分隔），根目录下的文件没有标签
+fn folder_tag(root: &Path, path: &Path) -> Option<String> {
+    let parent = path.parent()?.strip_prefix(root).ok()?;
+    let parts: Vec<String> = parent
+        .components()
+        .map(|part| part.as_os_str().to_string_lossy().to_string())
+        .collect();
+    (!parts.is_empty()).then(|| parts.join("/"))
+}
+
+// 读取文本文件（去掉 BOM），不是 UTF-8 时返回 None
+fn read_text(path: &Path) -> Result<Option<(String, DateTime<Utc>)>, String> {
+    let bytes = std::fs::read(path).map_err(|e| format!("读取 {} 失败: {}", path.display(), e))?;
+    let modified = std::fs::metadata(path)
+        .and_then(|m| m.modified())
+        .map(DateTime::<Utc>::from)
+        .unwrap_or_else(|_| Utc::now());
+    let text = String::from_utf8(bytes)
+        .ok()
+        .map(|text| text.strip_prefix('\u{feff}').map(str::to_string).unwrap_or(text));
+    Ok(text.map(|text| (text, modified)))
+}
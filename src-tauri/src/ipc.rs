@@ -0,0 +1,433 @@
+//! 本地 IPC 服务（供编辑器插件、启动器等外部程序调用）
+//! Unix 下监听 Unix 域套接字，Windows 下监听命名管道。协议为按行分隔的 JSON-RPC 2.0：
+//! 每行一个请求对象，服务端对每个带 id 的请求返回一行响应（不带 id 的通知不返回）。
+//! 鉴权依赖文件权限：套接字所在目录为 0700、套接字为 0600，只有当前用户可以连接；
+//! 命名管道使用默认安全描述符（只有创建者和管理员可写）并拒绝远程客户端。
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+
+/// JSON-RPC 错误码
+pub mod error_code {
+    /// 请求不是合法的 JSON
+    pub const PARSE_ERROR: i64 = -32700;
+    /// 不是合法的请求对象
+    pub const INVALID_REQUEST: i64 = -32600;
+    pub const METHOD_NOT_FOUND: i64 = -32601;
+    pub const INVALID_PARAMS: i64 = -32602;
+    /// 执行失败（错误信息见 message）
+    pub const SERVER_ERROR: i64 = -32000;
+    /// 记录不存在
+    pub const NOT_FOUND: i64 = -32001;
+}
+
+/// 套接字文件名
+const SOCKET_FILE: &str = "ipc.sock";
+
+/// 单行请求的最大字节数（超过时返回错误并断开连接）
+const MAX_LINE_BYTES: usize = 4 * 1024 * 1024;
+
+/// 接受连接失败后重试前的等待时间（避免文件描述符耗尽等持续错误时空转）
+const ACCEPT_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+/// JSON-RPC 错误
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl RpcError {
+    pub fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+
+    pub fn method_not_found(method: &str) -> Self {
+        Self::new(error_code::METHOD_NOT_FOUND, format!("未知方法: {}", method))
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(error_code::NOT_FOUND, message)
+    }
+}
+
+impl From<String> for RpcError {
+    fn from(message: String) -> Self {
+        Self::new(error_code::SERVER_ERROR, message)
+    }
+}
+
+/// 请求对象
+#[derive(Debug, Deserialize)]
+struct Request {
+    jsonrpc: String,
+    /// 没有 id 的请求是通知，不返回响应
+    #[serde(default)]
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+/// 响应对象（result 与 error 二选一）
+#[derive(Debug, Serialize)]
+struct Response {
+    jsonrpc: &'static str,
+    id: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<RpcError>,
+}
+
+impl Response {
+    fn new(id: Value, result: Result<Value, RpcError>) -> Self {
+        let (result, error) = match result {
+            Ok(value) => (Some(value), None),
+            Err(error) => (None, Some(error)),
+        };
+        Self {
+            jsonrpc: "2.0",
+            id,
+            result,
+            error,
+        }
+    }
+}
+
+/// 方法的实际实现
+pub trait Handler: Send + Sync + 'static {
+    fn handle(&self, method: String, params: Value) -> impl Future<Output = Result<Value, RpcError>> + Send;
+}
+
+/// 解析方法参数（省略 params 时按空对象处理）
+pub fn params<T: DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+    let params = if params.is_null() {
+        Value::Object(Default::default())
+    } else {
+        params
+    };
+    serde_json::from_value(params).map_err(|e| RpcError::new(error_code::INVALID_PARAMS, format!("参数错误: {}", e)))
+}
+
+/// 把结果序列化为 JSON 值
+pub fn to_value<T: Serialize>(value: T) -> Result<Value, RpcError> {
+    serde_json::to_value(value).map_err(|e| RpcError::from(format!("序列化失败: {}", e)))
+}
+
+/// 处理一行请求，返回要写回的一行响应（通知返回 None）
+pub async fn handle_line<H: Handler>(handler: &H, line: &str) -> Option<String> {
+    let value: Value = match serde_json::from_str(line) {
+        Ok(value) => value,
+        Err(e) => {
+            let error = RpcError::new(error_code::PARSE_ERROR, format!("JSON 格式错误: {}", e));
+            return Some(encode(Response::new(Value::Null, Err(error))));
+        }
+    };
+    let request: Request = match serde_json::from_value(value) {
+        Ok(request) => request,
+        Err(e) => {
+            let error = RpcError::new(error_code::INVALID_REQUEST, format!("无效的请求: {}", e));
+            return Some(encode(Response::new(Value::Null, Err(error))));
+        }
+    };
+    if request.jsonrpc != "2.0" {
+        let error = RpcError::new(error_code::INVALID_REQUEST, "只支持 JSON-RPC 2.0");
+        return Some(encode(Response::new(request.id.unwrap_or(Value::Null), Err(error))));
+    }
+
+    let result = handler.handle(request.method, request.params).await;
+    request.id.map(|id| encode(Response::new(id, result)))
+}
+
+fn encode(response: Response) -> String {
+    serde_json::to_string(&response).unwrap_or_else(|e| {
+        format!(
+            r#"{{"jsonrpc":"2.0","id":null,"error":{{"code":{},"message":"序列化响应失败: {}"}}}}"#,
+            error_code::SERVER_ERROR,
+            e
+        )
+    })
+}
+
+/// 逐行读取请求并写回响应，直到客户端断开
+///
+/// 单行超过 `MAX_LINE_BYTES` 时返回错误响应并断开（最多缓存这么多字节，超长请求不会占满内存）。
+async fn serve_connection<S, H>(stream: S, handler: Arc<H>) -> Result<(), String>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+    H: Handler,
+{
+    let (reader, mut writer) = tokio::io::split(stream);
+    let mut reader = BufReader::new(reader);
+    let mut buffer = Vec::new();
+
+    loop {
+        buffer.clear();
+        let read = (&mut reader)
+            .take(MAX_LINE_BYTES as u64 + 1)
+            .read_until(b'\n', &mut buffer)
+            .await
+            .map_err(|e| e.to_string())?;
+        if read == 0 {
+            return Ok(());
+        }
+
+        let response = if !buffer.ends_with(b"\n") && buffer.len() > MAX_LINE_BYTES {
+            let error = RpcError::new(
+                error_code::INVALID_REQUEST,
+                format!("请求超过 {} 字节", MAX_LINE_BYTES),
+            );
+            Some(encode(Response::new(Value::Null, Err(error))))
+        } else {
+            match std::str::from_utf8(&buffer) {
+                Ok(line) if line.trim().is_empty() => continue,
+                Ok(line) => handle_line(handler.as_ref(), line).await,
+                Err(_) => {
+                    let error = RpcError::new(error_code::PARSE_ERROR, "请求不是 UTF-8 文本");
+                    Some(encode(Response::new(Value::Null, Err(error))))
+                }
+            }
+        };
+
+        if let Some(mut response) = response {
+            response.push('\n');
+            writer.write_all(response.as_bytes()).await.map_err(|e| e.to_string())?;
+            writer.flush().await.map_err(|e| e.to_string())?;
+        }
+        if buffer.len() > MAX_LINE_BYTES {
+            return Err(format!("请求超过 {} 字节，已断开", MAX_LINE_BYTES));
+        }
+    }
+}
+
+/// IPC 套接字路径：Linux 优先使用 $XDG_RUNTIME_DIR/paste-library，其次为数据目录下的 ipc 目录
+pub fn socket_path(app_dir: &Path) -> PathBuf {
+    let runtime_dir = std::env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .filter(|dir| cfg!(target_os = "linux") && dir.is_absolute());
+    match runtime_dir {
+        Some(dir) => dir.join("paste-library").join(SOCKET_FILE),
+        None => app_dir.join("ipc").join(SOCKET_FILE),
+    }
+}
+
+/// 启动 IPC 服务（持续运行，只在无法监听时返回错误；单个连接出错只记录日志）
+#[cfg(unix)]
+pub async fn serve<H: Handler>(path: PathBuf, handler: Arc<H>) -> Result<(), String> {
+    use std::os::unix::fs::PermissionsExt;
+    use tokio::net::{UnixListener, UnixStream};
+
+    let dir = path.parent().ok_or("无效的套接字路径")?;
+    std::fs::create_dir_all(dir).map_err(|e| format!("创建 IPC 目录失败: {}", e))?;
+    // 目录只允许当前用户访问，避免绑定到设置权限之间被其他用户连接
+    std::fs::set_permissions(dir, std::fs::Permissions::from_mode(0o700))
+        .map_err(|e| format!("设置 IPC 目录权限失败: {}", e))?;
+
+    if path.exists() {
+        if UnixStream::connect(&path).await.is_ok() {
+            return Err(format!("IPC 套接字 {} 已被其他实例使用", path.display()));
+        }
+        // 上次异常退出留下的套接字文件
+        std::fs::remove_file(&path).map_err(|e| format!("删除旧的 IPC 套接字失败: {}", e))?;
+    }
+
+    let listener = UnixListener::bind(&path).map_err(|e| format!("监听 IPC 套接字失败: {}", e))?;
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))
+        .map_err(|e| format!("设置 IPC 套接字权限失败: {}", e))?;
+    println!("IPC 服务已启动: {}", path.display());
+
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(e) => {
+                eprintln!("接受 IPC 连接失败: {}", e);
+                tokio::time::sleep(ACCEPT_RETRY_DELAY).await;
+                continue;
+            }
+        };
+        let handler = handler.clone();
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = serve_connection(stream, handler).await {
+                eprintln!("IPC 连接异常断开: {}", e);
+            }
+        });
+    }
+}
+
+/// 命名管道名称（按用户区分）
+#[cfg(windows)]
+pub fn pipe_name() -> String {
+    let user = std::env::var("USERNAME").unwrap_or_else(|_| "default".to_string());
+    format!(r"\\.\pipe\paste-library-{}", user)
+}
+
+/// 启动 IPC 服务（持续运行，只在无法监听时返回错误；单个连接出错只记录日志）
+#[cfg(windows)]
+pub async fn serve<H: Handler>(_path: PathBuf, handler: Arc<H>) -> Result<(), String> {
+    use tokio::net::windows::named_pipe::ServerOptions;
+
+    let name = pipe_name();
+    let mut server = ServerOptions::new()
+        .first_pipe_instance(true)
+        .reject_remote_clients(true)
+        .create(&name)
+        .map_err(|e| format!("创建命名管道 {} 失败（可能已被其他实例使用）: {}", name, e))?;
+    println!("IPC 服务已启动: {}", name);
+
+    loop {
+        let connected = server.connect().await;
+        // 先创建下一个管道实例再处理当前连接，避免客户端连接时没有可用实例；
+        // 连接失败的实例同样替换掉
+        let next = match ServerOptions::new().reject_remote_clients(true).create(&name) {
+            Ok(next) => next,
+            Err(e) => {
+                eprintln!("创建命名管道失败: {}", e);
+                tokio::time::sleep(ACCEPT_RETRY_DELAY).await;
+                continue;
+            }
+        };
+        let client = std::mem::replace(&mut server, next);
+        if let Err(e) = connected {
+            eprintln!("接受 IPC 连接失败: {}", e);
+            tokio::time::sleep(ACCEPT_RETRY_DELAY).await;
+            continue;
+        }
+        let handler = handler.clone();
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = serve_connection(client, handler).await {
+                eprintln!("IPC 连接异常断开: {}", e);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// 只支持 echo 方法，记录调用次数
+    #[derive(Default)]
+    struct EchoHandler {
+        calls: AtomicUsize,
+    }
+
+    impl Handler for EchoHandler {
+        async fn handle(&self, method: String, params: Value) -> Result<Value, RpcError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            match method.as_str() {
+                "echo" => Ok(params),
+                _ => Err(RpcError::method_not_found(&method)),
+            }
+        }
+    }
+
+    fn call(handler: &EchoHandler, line: &str) -> Option<Value> {
+        tauri::async_runtime::block_on(handle_line(handler, line))
+            .map(|response| serde_json::from_str(&response).unwrap())
+    }
+
+    fn error_code(response: &Value) -> i64 {
+        response["error"]["code"].as_i64().unwrap()
+    }
+
+    #[test]
+    fn echo_returns_result_with_id() {
+        let handler = EchoHandler::default();
+        let response = call(&handler, r#"{"jsonrpc":"2.0","id":7,"method":"echo","params":{"a":1}}"#).unwrap();
+        assert_eq!(response["id"], 7);
+        assert_eq!(response["result"]["a"], 1);
+        assert!(response.get("error").is_none());
+    }
+
+    #[test]
+    fn parse_error() {
+        let handler = EchoHandler::default();
+        let response = call(&handler, r#"{"jsonrpc":"2.0","id":1,"#).unwrap();
+        assert_eq!(error_code(&response), error_code::PARSE_ERROR);
+        assert_eq!(response["id"], Value::Null);
+        assert_eq!(handler.calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn invalid_requests() {
+        let handler = EchoHandler::default();
+
+        // 版本不对时仍带回请求的 id
+        let response = call(&handler, r#"{"jsonrpc":"1.0","id":3,"method":"echo"}"#).unwrap();
+        assert_eq!(error_code(&response), error_code::INVALID_REQUEST);
+        assert_eq!(response["id"], 3);
+
+        // 缺少 method
+        let response = call(&handler, r#"{"jsonrpc":"2.0","id":4}"#).unwrap();
+        assert_eq!(error_code(&response), error_code::INVALID_REQUEST);
+        assert_eq!(handler.calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn notification_has_no_response() {
+        let handler = EchoHandler::default();
+        assert!(call(&handler, r#"{"jsonrpc":"2.0","method":"echo","params":[1]}"#).is_none());
+        // 通知出错也不返回
+        assert!(call(&handler, r#"{"jsonrpc":"2.0","method":"missing"}"#).is_none());
+        assert_eq!(handler.calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn method_not_found() {
+        let handler = EchoHandler::default();
+        let response = call(&handler, r#"{"jsonrpc":"2.0","id":"x","method":"missing"}"#).unwrap();
+        assert_eq!(error_code(&response), error_code::METHOD_NOT_FOUND);
+        assert_eq!(response["id"], "x");
+    }
+
+    /// 把 input 写给连接处理函数，返回它写回的全部内容和返回值
+    fn serve_input(input: Vec<u8>) -> (String, Result<(), String>) {
+        tauri::async_runtime::block_on(async move {
+            let (mut client, server) = tokio::io::duplex(input.len() + 64 * 1024);
+            client.write_all(&input).await.unwrap();
+            client.shutdown().await.unwrap();
+
+            let result = serve_connection(server, Arc::new(EchoHandler::default())).await;
+            let mut output = String::new();
+            client.read_to_string(&mut output).await.unwrap();
+            (output, result)
+        })
+    }
+
+    #[test]
+    fn connection_answers_each_line() {
+        let input = b"{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"echo\"}\n\n{\"jsonrpc\":\"2.0\",\"id\":2,\"method\":\"echo\"}";
+        let (output, result) = serve_input(input.to_vec());
+        assert!(result.is_ok());
+        let ids: Vec<i64> = output
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).unwrap()["id"].as_i64().unwrap())
+            .collect();
+        assert_eq!(ids, vec![1, 2]);
+    }
+
+    #[test]
+    fn oversized_line_is_rejected() {
+        let mut input = vec![b'x'; MAX_LINE_BYTES + 10];
+        input.extend_from_slice(b"\n{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"echo\"}\n");
+        let (output, result) = serve_input(input);
+
+        assert!(result.is_err());
+        // 只返回超长错误，之后的请求不再处理
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 1);
+        let response: Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(error_code(&response), error_code::INVALID_REQUEST);
+    }
+}
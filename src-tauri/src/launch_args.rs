@@ -0,0 +1,127 @@
+//! 启动参数（首次启动和再次启动时转发给已运行实例的参数）
+//!
+//! - `--hidden`：静默启动，不显示任何窗口（开机自启使用）
+//! - `--show`：显示剪贴板窗口
+//! - `--settings`：显示设置窗口
+//! - `--paste <id>`：把指定记录粘贴到当前前台窗口
+
+/// 解析后的启动参数
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LaunchArgs {
+    pub hidden: bool,
+    pub show: bool,
+    pub settings: bool,
+    pub paste: Option<i64>,
+}
+
+impl LaunchArgs {
+    /// 从完整的命令行解析（第一个元素是程序路径）；无法识别的参数只打印警告，不影响启动
+    pub fn parse<I, S>(args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut parsed = Self::default();
+        let mut args = args.into_iter().skip(1).peekable();
+        while let Some(arg) = args.next() {
+            match arg.as_ref() {
+                "--hidden" => parsed.hidden = true,
+                "--show" => parsed.show = true,
+                "--settings" => parsed.settings = true,
+                // 下一个参数不是 ID 时不消耗它（如 --paste --show）
+                "--paste" => match args.peek().and_then(|id| id.as_ref().parse::<i64>().ok()) {
+                    Some(id) => {
+                        parsed.paste = Some(id);
+                        args.next();
+                    }
+                    None => eprintln!("启动参数 --paste 需要一个记录 ID"),
+                },
+                other => match other.strip_prefix("--paste=") {
+                    Some(id) => match id.parse::<i64>() {
+                        Ok(id) => parsed.paste = Some(id),
+                        Err(_) => eprintln!("启动参数 --paste 需要一个记录 ID"),
+                    },
+                    None => eprintln!("忽略无法识别的启动参数: {}", other),
+                },
+            }
+        }
+        parsed
+    }
+
+    /// 是否请求了任何操作（--hidden 不算）
+    pub fn has_action(&self) -> bool {
+        self.show || self.settings || self.paste.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> LaunchArgs {
+        LaunchArgs::parse(std::iter::once("paste-library").chain(args.iter().copied()))
+    }
+
+    #[test]
+    fn parses_flags() {
+        assert_eq!(parse(&[]), LaunchArgs::default());
+        assert_eq!(
+            parse(&["--hidden", "--show", "--settings"]),
+            LaunchArgs {
+                hidden: true,
+                show: true,
+                settings: true,
+                paste: None,
+            }
+        );
+        // 第一个参数是程序路径，不参与解析
+        assert_eq!(LaunchArgs::parse(["--show"]), LaunchArgs::default());
+    }
+
+    #[test]
+    fn parses_paste_id() {
+        assert_eq!(parse(&["--paste=42"]).paste, Some(42));
+        assert_eq!(parse(&["--paste", "42"]).paste, Some(42));
+        assert_eq!(parse(&["--paste", "1", "--paste=2"]).paste, Some(2));
+        assert_eq!(parse(&["--paste=abc"]).paste, None);
+        assert_eq!(parse(&["--paste"]).paste, None);
+    }
+
+    #[test]
+    fn paste_without_id_keeps_next_flag() {
+        assert_eq!(
+            parse(&["--paste", "--show"]),
+            LaunchArgs {
+                show: true,
+                ..LaunchArgs::default()
+            }
+        );
+        assert_eq!(
+            parse(&["--paste", "abc", "--settings"]),
+            LaunchArgs {
+                settings: true,
+                ..LaunchArgs::default()
+            }
+        );
+    }
+
+    #[test]
+    fn ignores_unknown_flags() {
+        assert_eq!(
+            parse(&["--verbose", "file.txt", "--hidden"]),
+            LaunchArgs {
+                hidden: true,
+                ..LaunchArgs::default()
+            }
+        );
+    }
+
+    #[test]
+    fn hidden_is_not_an_action() {
+        assert!(!parse(&[]).has_action());
+        assert!(!parse(&["--hidden"]).has_action());
+        assert!(parse(&["--show"]).has_action());
+        assert!(parse(&["--settings"]).has_action());
+        assert!(parse(&["--hidden", "--paste=3"]).has_action());
+    }
+}
@@ -1,67 +1,88 @@
+mod archive;
+pub mod cli;
 mod clipboard;
+mod clipboard_watcher;
+mod encryption;
+mod image_handler;
+mod importers;
+mod ipc;
+mod launch_args;
+mod migrations;
 mod models;
+mod paste_queue;
+mod paste_target;
+mod paste_rules;
 mod platform;
+mod search;
+mod sensitive;
 mod storage;
+mod template;
+mod typing;
+mod window_geometry;
 mod window_manager;
 mod shortcut_manager;
 mod tray_manager;
 mod prevent_default;
 
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
 use clipboard::ClipboardManager;
+use clipboard_watcher::{ClipboardSnapshot, ClipboardWatcher};
+use encryption::{EncryptionConfig, EncryptionKey, KeyMaterial, KeySource};
+use image_handler::ImageStore;
+use launch_args::LaunchArgs;
 use models::{
-    AdvancedSearchRequest, AppSettings, ClipboardItem, ClipboardContentType, ClipboardMetadata, ClearHistoryRequest,
-    GetHistoryRequest, SearchRequest,
+    AdvancedSearchRequest, AppSettings, CaptureOutcome, ClipboardItem, ClipboardContentType, ClipboardMetadata, ClearHistoryRequest,
+    EncryptionStatus, ExportReport, ExportRequest, ExternalImportReport, GetHistoryRequest, ImportReport, ImportSource,
+    PasteQueueState, QueueSeparator, SearchRequest, SearchResult, StorageGcReport, Tag,
 };
+use paste_queue::PasteQueue;
+use paste_rules::{PasteKeystroke, PastePlan, PasteRule, PasteRuleInput};
 use storage::Database;
+use template::{TemplateContext, TemplateError};
+use typing::{NewlineMode, TypingBackend, TypingOptions};
 use tauri::Manager;
 use tauri::Emitter;
 use window_manager::WindowManager;
 use shortcut_manager::validate_hotkey;
 use tauri_plugin_global_shortcut::GlobalShortcutExt;
 
+/// 过期敏感记录的检查间隔
+const SENSITIVE_PURGE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+/// 粘贴前等待目标窗口获得焦点的最长时间
+const PASTE_FOCUS_TIMEOUT_MS: u64 = 1000;
+/// 无法记录目标窗口时，隐藏窗口后等待焦点自行转移的时间
+const PASTE_FOCUS_FALLBACK_MS: u64 = 80;
+
 pub struct AppState {
     clipboard_manager: ClipboardManager,
     window_manager: WindowManager,
-}
-
-/// 粘贴队列管理器（用于串行化处理快速连续粘贴）
-pub struct PasteQueue {
-    last_paste_time: Mutex<std::time::Instant>,
-}
-
-impl PasteQueue {
-    pub fn new() -> Self {
-        Self {
-            last_paste_time: Mutex::new(std::time::Instant::now() - std::time::Duration::from_secs(1)),
-        }
-    }
-
-    /// 等待合适的粘贴间隔（串行化处理 <100ms 的连续粘贴）
-    pub async fn wait_for_paste_interval(&self) {
-        let mut last_time = self.last_paste_time.lock().await;
-        let elapsed = last_time.elapsed().as_millis() as u64;
-        
-        // 如果距离上次粘贴 <100ms，等待剩余时间
-        if elapsed < 100 {
-            let wait_ms = 100 - elapsed;
-            drop(last_time);
-            tokio::time::sleep(tokio::time::Duration::from_millis(wait_ms)).await;
-        }
-        
-        // 更新上次粘贴时间
-        *self.last_paste_time.lock().await = std::time::Instant::now();
-    }
+    clipboard_watcher: ClipboardWatcher,
+    paste_queue: Arc<PasteQueue>,
+    /// 模拟输入的取消标记（输入期间按 Escape 设置）
+    typing_cancel: Arc<AtomicBool>,
 }
 
 impl AppState {
-    pub fn new(database: Arc<Database>, settings: Arc<Mutex<AppSettings>>) -> Self {
+    pub fn new(
+        database: Arc<Database>,
+        settings: Arc<Mutex<AppSettings>>,
+        app_dir: PathBuf,
+        image_key: Option<EncryptionKey>,
+    ) -> Self {
+        let image_store = Arc::new(ImageStore::new(&app_dir));
+        image_store.set_key(image_key);
+        let clipboard_manager = ClipboardManager::new(database.clone(), settings.clone(), image_store);
         Self {
-            clipboard_manager: ClipboardManager::new(database.clone(), settings.clone()),
+            clipboard_watcher: ClipboardWatcher::new(clipboard_manager.clone()),
+            clipboard_manager,
             window_manager: WindowManager::new(settings, database),
+            paste_queue: Arc::new(PasteQueue::new()),
+            typing_cancel: Arc::new(AtomicBool::new(false)),
         }
     }
 }
@@ -72,7 +93,7 @@ async fn add_clipboard_item(
     text: String,
     html: Option<String>,
     is_internal_copy: Option<bool>,
-) -> Result<Option<ClipboardItem>, String> {
+) -> Result<CaptureOutcome, String> {
     let state = state.lock().await;
     // 默认认为是外部复制（来自系统剪贴板）
     let is_internal = is_internal_copy.unwrap_or(false);
@@ -102,6 +123,36 @@ async fn add_clipboard_item_extended(
     ).await
 }
 
+/// 历史记录中出现过的来源应用（搜索过滤用）
+#[tauri::command]
+async fn get_source_apps(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+) -> Result<Vec<String>, String> {
+    let state = state.lock().await;
+    state.clipboard_manager.get_source_apps()
+}
+
+/// 用内置规则和给定的自定义规则检测文本（设置页测试用，自定义规则无效时报错）
+#[tauri::command]
+async fn check_sensitive_content(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    text: String,
+    patterns: Option<Vec<String>>,
+) -> Result<Vec<sensitive::Finding>, String> {
+    let state = state.lock().await;
+    state.clipboard_manager.check_sensitive_content(&text, patterns).await
+}
+
+/// 标记下一次剪贴板变化来自应用内复制（点击条目复制时由前端在写剪贴板前调用）
+#[tauri::command]
+async fn mark_internal_copy(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+) -> Result<(), String> {
+    let state = state.lock().await;
+    state.clipboard_watcher.mark_internal_copy().await;
+    Ok(())
+}
+
 #[tauri::command]
 fn get_clipboard_history(
     state: tauri::State<'_, Arc<Mutex<AppState>>>,
@@ -125,7 +176,7 @@ fn search_clipboard_history(
 async fn search_clipboard_advanced(
     state: tauri::State<'_, Arc<Mutex<AppState>>>,
     request: AdvancedSearchRequest,
-) -> Result<Vec<ClipboardItem>, String> {
+) -> Result<Vec<SearchResult>, String> {
     let state = state.lock().await;
     state.clipboard_manager.search_history_advanced(request)
 }
@@ -148,6 +199,17 @@ fn clear_clipboard_history(
     state.clipboard_manager.clear_history(request)
 }
 
+/// 图片存储垃圾回收（删除孤立文件、修复缩略图、报告文件丢失的记录；遍历并解码图片，在阻塞线程中执行）
+#[tauri::command]
+async fn collect_image_garbage(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+) -> Result<StorageGcReport, String> {
+    let clipboard_manager = state.lock().await.clipboard_manager.clone();
+    tokio::task::spawn_blocking(move || clipboard_manager.collect_image_garbage())
+        .await
+        .map_err(|e| e.to_string())?
+}
+
 #[tauri::command]
 fn get_settings(state: tauri::State<'_, Arc<Mutex<AppState>>>) -> Result<AppSettings, String> {
     let state = state.blocking_lock();
@@ -248,6 +310,16 @@ async fn hide_clipboard_window(
     state.window_manager.hide_clipboard_window(&app).await
 }
 
+/// 将剪贴板窗口尺寸恢复为默认值
+#[tauri::command]
+async fn reset_clipboard_window_size(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    app: tauri::AppHandle,
+) -> Result<(), String> {
+    let state = state.lock().await;
+    state.window_manager.reset_window_size(&app).await
+}
+
 #[tauri::command]
 fn open_file(path: String) -> Result<(), String> {
     #[cfg(target_os = "windows")]
@@ -330,6 +402,65 @@ fn get_all_tags(
     state.clipboard_manager.get_all_tags()
 }
 
+/// 获取所有标签（含颜色和使用次数）
+#[tauri::command]
+fn get_tags(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+) -> Result<Vec<Tag>, String> {
+    let state = state.blocking_lock();
+    state.clipboard_manager.get_tags()
+}
+
+#[tauri::command]
+fn create_tag(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    name: String,
+    color: Option<String>,
+) -> Result<Tag, String> {
+    let state = state.blocking_lock();
+    state.clipboard_manager.create_tag(name, color)
+}
+
+#[tauri::command]
+fn rename_tag(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    id: i64,
+    name: String,
+) -> Result<Tag, String> {
+    let state = state.blocking_lock();
+    state.clipboard_manager.rename_tag(id, name)
+}
+
+#[tauri::command]
+fn recolor_tag(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    id: i64,
+    color: String,
+) -> Result<Tag, String> {
+    let state = state.blocking_lock();
+    state.clipboard_manager.recolor_tag(id, color)
+}
+
+/// 合并标签：source_ids 中的标签并入 target_id 后删除
+#[tauri::command]
+fn merge_tags(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    source_ids: Vec<i64>,
+    target_id: i64,
+) -> Result<Tag, String> {
+    let state = state.blocking_lock();
+    state.clipboard_manager.merge_tags(source_ids, target_id)
+}
+
+#[tauri::command]
+fn delete_tag(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    id: i64,
+) -> Result<(), String> {
+    let state = state.blocking_lock();
+    state.clipboard_manager.delete_tag(id)
+}
+
 #[tauri::command]
 fn validate_shortcut(hotkey: String) -> Result<(), String> {
     validate_hotkey(&hotkey)
@@ -407,6 +538,205 @@ fn import_clipboard_data(
     state.clipboard_manager.import_data(&json_data)
 }
 
+/// 导出为 zip 归档（含图片），path 由前端的保存对话框选择
+#[tauri::command]
+async fn export_archive(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    path: String,
+    request: Option<ExportRequest>,
+) -> Result<ExportReport, String> {
+    let clipboard_manager = state.lock().await.clipboard_manager.clone();
+    tokio::task::spawn_blocking(move || {
+        clipboard_manager.export_archive(&request.unwrap_or_default(), Path::new(&path))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// 导入备份文件（zip 归档或旧版 JSON）
+#[tauri::command]
+async fn import_archive(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    path: String,
+) -> Result<ImportReport, String> {
+    let clipboard_manager = state.lock().await.clipboard_manager.clone();
+    tokio::task::spawn_blocking(move || clipboard_manager.import_file(Path::new(&path)))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// 从其他剪贴板管理器（Ditto、CopyQ、Maccy）或纯文本片段导入
+#[tauri::command]
+async fn import_external(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    source: ImportSource,
+    path: String,
+) -> Result<ExternalImportReport, String> {
+    let clipboard_manager = state.lock().await.clipboard_manager.clone();
+    tokio::task::spawn_blocking(move || clipboard_manager.import_from(source, Path::new(&path)))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// 按加密配置打开数据库，返回数据库和图片加密密钥
+///
+/// 密钥文件模式直接读取密钥打开；口令模式返回锁定状态的数据库，等待前端调用 `unlock_database`。
+fn open_database(app_dir: &Path) -> Result<(Database, Option<EncryptionKey>), String> {
+    let Some(config) = EncryptionConfig::load(app_dir)? else {
+        return Database::new(app_dir.to_path_buf())
+            .map(|db| (db, None))
+            .map_err(|e| e.to_string());
+    };
+
+    match config.key_source {
+        KeySource::KeyFile => {
+            let key = KeyMaterial::KeyFile { path: None }.resolve_key(&config)?;
+            let database = Database::open(app_dir.to_path_buf(), Some(&key))
+                .map_err(|e| format!("打开加密数据库失败: {}", e))?;
+            Ok((database, Some(key)))
+        }
+        KeySource::Passphrase => Ok((Database::locked(app_dir.to_path_buf()), None)),
+    }
+}
+
+/// 获取静态加密状态
+#[tauri::command]
+async fn get_encryption_status(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+) -> Result<EncryptionStatus, String> {
+    let clipboard_manager = state.lock().await.clipboard_manager.clone();
+    clipboard_manager.encryption_status()
+}
+
+/// 启用静态加密（加密数据库和已有图片，耗时操作在阻塞线程中执行）
+#[tauri::command]
+async fn enable_encryption(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    material: KeyMaterial,
+) -> Result<EncryptionStatus, String> {
+    let clipboard_manager = state.lock().await.clipboard_manager.clone();
+    tauri::async_runtime::spawn_blocking(move || clipboard_manager.enable_encryption(&material))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// 关闭静态加密（需要当前口令或密钥文件）
+#[tauri::command]
+async fn disable_encryption(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    current: KeyMaterial,
+) -> Result<EncryptionStatus, String> {
+    let clipboard_manager = state.lock().await.clipboard_manager.clone();
+    tauri::async_runtime::spawn_blocking(move || clipboard_manager.disable_encryption(&current))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// 更换加密密钥（可在口令与密钥文件之间切换）
+#[tauri::command]
+async fn rotate_encryption(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    current: KeyMaterial,
+    new_material: KeyMaterial,
+) -> Result<EncryptionStatus, String> {
+    let clipboard_manager = state.lock().await.clipboard_manager.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        clipboard_manager.rotate_encryption(&current, &new_material)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// 输入口令解锁数据库
+#[tauri::command]
+async fn unlock_database(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    material: KeyMaterial,
+) -> Result<AppSettings, String> {
+    let clipboard_manager = state.lock().await.clipboard_manager.clone();
+    let settings = clipboard_manager.unlock(&material).await?;
+    println!("数据库已解锁");
+    let _ = app.emit("database-unlocked", &settings);
+    Ok(settings)
+}
+
+/// 将图片存储中的图片写入剪贴板（加密图片在后端解密）
+#[tauri::command]
+async fn write_image_to_clipboard(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    path: String,
+) -> Result<(), String> {
+    let state = state.lock().await;
+    let bytes = state.clipboard_manager.read_image(&path)?;
+    state.clipboard_watcher.write_image(&bytes)
+}
+
+/// 解码 URL 路径中的百分号转义
+fn percent_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%' && i + 2 < bytes.len())
+            .then(|| std::str::from_utf8(&bytes[i + 1..i + 3]).ok())
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+/// `clipimg://` 协议：向前端提供图片存储中的图片
+///
+/// 图片存储可能已加密，asset 协议无法直接读取，统一经由该协议解密后返回。
+/// 只允许访问图片存储目录中的文件。
+async fn serve_stored_image(
+    app: &tauri::AppHandle,
+    request: &tauri::http::Request<Vec<u8>>,
+) -> tauri::http::Response<Vec<u8>> {
+    use tauri::http::{header::CONTENT_TYPE, Response, StatusCode};
+
+    let path = percent_decode(request.uri().path().trim_start_matches('/'));
+    let result = match app.try_state::<Arc<Mutex<AppState>>>() {
+        Some(state) => {
+            let clipboard_manager = state.lock().await.clipboard_manager.clone();
+            if clipboard_manager.is_stored_image(&path) {
+                clipboard_manager.read_image(&path)
+            } else {
+                Err("不允许访问图片存储以外的文件".to_string())
+            }
+        }
+        None => Err("应用尚未初始化".to_string()),
+    };
+
+    match result {
+        Ok(bytes) => {
+            let mime = image::guess_format(&bytes)
+                .map(|format| format.to_mime_type())
+                .unwrap_or("application/octet-stream");
+            Response::builder()
+                .header(CONTENT_TYPE, mime)
+                .body(bytes)
+                .unwrap_or_default()
+        }
+        Err(e) => Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .header(CONTENT_TYPE, "text/plain; charset=utf-8")
+            .body(e.into_bytes())
+            .unwrap_or_default(),
+    }
+}
+
 #[tauri::command]
 fn get_app_version() -> Result<String, String> {
     Ok(env!("CARGO_PKG_VERSION").to_string())
@@ -517,43 +847,56 @@ fn get_storage_paths(app: tauri::AppHandle) -> Result<std::collections::HashMap<
 }
 
 // 执行实际粘贴操作的内部函数
-fn do_paste(paste_shortcut: &str) -> Result<(), String> {
+fn do_paste(keystroke: PasteKeystroke) -> Result<(), String> {
     use std::thread;
     
     #[cfg(target_os = "windows")]
     {
-        let use_shift_insert = paste_shortcut == "shift_insert";
         use winapi::um::winuser::{keybd_event, VK_SHIFT, VK_INSERT, KEYEVENTF_KEYUP, KEYEVENTF_SCANCODE, KEYEVENTF_EXTENDEDKEY};
 
+        const SCANCODE_SHIFT: u8 = 0x2A;
+        const SCANCODE_V: u8 = 0x2F;
+        const SCANCODE_CTRL: u8 = 0x1D;
+        const VK_CONTROL: u8 = 0xA3;
+
         unsafe {
-            if use_shift_insert {
-                // 使用 Shift+Insert
-                const SCANCODE_SHIFT: u8 = 0x2A;
-                keybd_event(VK_SHIFT as u8, SCANCODE_SHIFT, KEYEVENTF_SCANCODE, 0);
-                thread::sleep(std::time::Duration::from_millis(20));
-                keybd_event(VK_INSERT as u8, 0, KEYEVENTF_EXTENDEDKEY, 0);
-                thread::sleep(std::time::Duration::from_millis(50));
-                keybd_event(VK_INSERT as u8, 0, KEYEVENTF_EXTENDEDKEY | KEYEVENTF_KEYUP, 0);
-                thread::sleep(std::time::Duration::from_millis(20));
-                keybd_event(VK_SHIFT as u8, SCANCODE_SHIFT, KEYEVENTF_SCANCODE | KEYEVENTF_KEYUP, 0);
-            } else {
-                // 使用 Ctrl+V
-                const SCANCODE_V: u8 = 0x2F;
-                const SCANCODE_CTRL: u8 = 0x1D;
-                const VK_CONTROL: u8 = 0xA3;
-                keybd_event(VK_CONTROL as u8, SCANCODE_CTRL, KEYEVENTF_SCANCODE, 0);
-                thread::sleep(std::time::Duration::from_millis(20));
-                keybd_event(0x41 + 21, SCANCODE_V, KEYEVENTF_SCANCODE, 0);
-                thread::sleep(std::time::Duration::from_millis(50));
-                keybd_event(0x41 + 21, SCANCODE_V, KEYEVENTF_SCANCODE | KEYEVENTF_KEYUP, 0);
-                thread::sleep(std::time::Duration::from_millis(20));
-                keybd_event(VK_CONTROL as u8, SCANCODE_CTRL, KEYEVENTF_SCANCODE | KEYEVENTF_KEYUP, 0);
+            match keystroke {
+                PasteKeystroke::ShiftInsert => {
+                    // 使用 Shift+Insert
+                    keybd_event(VK_SHIFT as u8, SCANCODE_SHIFT, KEYEVENTF_SCANCODE, 0);
+                    thread::sleep(std::time::Duration::from_millis(20));
+                    keybd_event(VK_INSERT as u8, 0, KEYEVENTF_EXTENDEDKEY, 0);
+                    thread::sleep(std::time::Duration::from_millis(50));
+                    keybd_event(VK_INSERT as u8, 0, KEYEVENTF_EXTENDEDKEY | KEYEVENTF_KEYUP, 0);
+                    thread::sleep(std::time::Duration::from_millis(20));
+                    keybd_event(VK_SHIFT as u8, SCANCODE_SHIFT, KEYEVENTF_SCANCODE | KEYEVENTF_KEYUP, 0);
+                }
+                PasteKeystroke::CtrlV | PasteKeystroke::CtrlShiftV => {
+                    // 使用 Ctrl+V / Ctrl+Shift+V
+                    let with_shift = keystroke == PasteKeystroke::CtrlShiftV;
+                    keybd_event(VK_CONTROL, SCANCODE_CTRL, KEYEVENTF_SCANCODE, 0);
+                    if with_shift {
+                        keybd_event(VK_SHIFT as u8, SCANCODE_SHIFT, KEYEVENTF_SCANCODE, 0);
+                    }
+                    thread::sleep(std::time::Duration::from_millis(20));
+                    keybd_event(0x41 + 21, SCANCODE_V, KEYEVENTF_SCANCODE, 0);
+                    thread::sleep(std::time::Duration::from_millis(50));
+                    keybd_event(0x41 + 21, SCANCODE_V, KEYEVENTF_SCANCODE | KEYEVENTF_KEYUP, 0);
+                    thread::sleep(std::time::Duration::from_millis(20));
+                    if with_shift {
+                        keybd_event(VK_SHIFT as u8, SCANCODE_SHIFT, KEYEVENTF_SCANCODE | KEYEVENTF_KEYUP, 0);
+                    }
+                    keybd_event(VK_CONTROL, SCANCODE_CTRL, KEYEVENTF_SCANCODE | KEYEVENTF_KEYUP, 0);
+                }
+                PasteKeystroke::Typing => return Err("模拟输入不发送粘贴按键".to_string()),
             }
         }
     }
 
     #[cfg(target_os = "macos")]
     {
+        // macOS 的终端同样使用 Cmd+V，按键规则不影响 macOS
+        let _ = keystroke;
         use enigo::{Direction, Enigo, Key, Keyboard, Settings};
         let mut enigo = Enigo::new(&Settings::default()).map_err(|e| e.to_string())?;
         enigo.key(Key::Meta, Direction::Press).map_err(|e| e.to_string())?;
@@ -563,31 +906,300 @@ fn do_paste(paste_shortcut: &str) -> Result<(), String> {
 
     #[cfg(target_os = "linux")]
     {
-        let use_shift_insert = paste_shortcut == "shift_insert";
         use enigo::{Direction, Enigo, Key, Keyboard, Settings};
         let mut enigo = Enigo::new(&Settings::default()).map_err(|e| e.to_string())?;
         
-        if use_shift_insert {
-            enigo.key(Key::Shift, Direction::Press).map_err(|e| e.to_string())?;
-            enigo.key(Key::Insert, Direction::Click).map_err(|e| e.to_string())?;
-            enigo.key(Key::Shift, Direction::Release).map_err(|e| e.to_string())?;
-        } else {
-            enigo.key(Key::Control, Direction::Press).map_err(|e| e.to_string())?;
-            enigo.key(Key::Unicode('v'), Direction::Click).map_err(|e| e.to_string())?;
-            enigo.key(Key::Control, Direction::Release).map_err(|e| e.to_string())?;
+        match keystroke {
+            PasteKeystroke::ShiftInsert => {
+                enigo.key(Key::Shift, Direction::Press).map_err(|e| e.to_string())?;
+                enigo.key(Key::Insert, Direction::Click).map_err(|e| e.to_string())?;
+                enigo.key(Key::Shift, Direction::Release).map_err(|e| e.to_string())?;
+            }
+            PasteKeystroke::CtrlV => {
+                enigo.key(Key::Control, Direction::Press).map_err(|e| e.to_string())?;
+                enigo.key(Key::Unicode('v'), Direction::Click).map_err(|e| e.to_string())?;
+                enigo.key(Key::Control, Direction::Release).map_err(|e| e.to_string())?;
+            }
+            PasteKeystroke::CtrlShiftV => {
+                enigo.key(Key::Control, Direction::Press).map_err(|e| e.to_string())?;
+                enigo.key(Key::Shift, Direction::Press).map_err(|e| e.to_string())?;
+                enigo.key(Key::Unicode('v'), Direction::Click).map_err(|e| e.to_string())?;
+                enigo.key(Key::Shift, Direction::Release).map_err(|e| e.to_string())?;
+                enigo.key(Key::Control, Direction::Release).map_err(|e| e.to_string())?;
+            }
+            PasteKeystroke::Typing => return Err("模拟输入不发送粘贴按键".to_string()),
         }
     }
 
     Ok(())
 }
 
-#[tauri::command]
-async fn simulate_paste(
-    state: tauri::State<'_, Arc<Mutex<AppState>>>,
-    app: tauri::AppHandle,
-    paste_shortcut: String,
+// 粘贴后将光标向左移动指定字符数（用于片段的 {{cursor}} 占位符）
+fn move_cursor_left(count: usize) -> Result<(), String> {
+    use std::thread;
+
+    if count == 0 {
+        return Ok(());
+    }
+
+    // 等待目标程序处理完粘贴
+    thread::sleep(std::time::Duration::from_millis(50));
+
+    #[cfg(target_os = "windows")]
+    {
+        use winapi::um::winuser::{keybd_event, VK_LEFT, KEYEVENTF_KEYUP, KEYEVENTF_EXTENDEDKEY};
+
+        unsafe {
+            for _ in 0..count {
+                keybd_event(VK_LEFT as u8, 0, KEYEVENTF_EXTENDEDKEY, 0);
+                keybd_event(VK_LEFT as u8, 0, KEYEVENTF_EXTENDEDKEY | KEYEVENTF_KEYUP, 0);
+                thread::sleep(std::time::Duration::from_millis(2));
+            }
+        }
+    }
+
+    #[cfg(any(target_os = "macos", target_os = "linux"))]
+    {
+        use enigo::{Direction, Enigo, Key, Keyboard, Settings};
+        let mut enigo = Enigo::new(&Settings::default()).map_err(|e| e.to_string())?;
+        for _ in 0..count {
+            enigo.key(Key::LeftArrow, Direction::Click).map_err(|e| e.to_string())?;
+        }
+    }
+
+    Ok(())
+}
+
+// 输入粘贴队列项之间的分隔符
+fn type_separator(separator: QueueSeparator) -> Result<(), String> {
+    #[cfg(target_os = "windows")]
+    {
+        use winapi::um::winuser::{keybd_event, VK_RETURN, VK_TAB, KEYEVENTF_KEYUP};
+
+        let vk = match separator {
+            QueueSeparator::None => return Ok(()),
+            QueueSeparator::Newline => VK_RETURN,
+            QueueSeparator::Tab => VK_TAB,
+        };
+        unsafe {
+            keybd_event(vk as u8, 0, 0, 0);
+            std::thread::sleep(std::time::Duration::from_millis(20));
+            keybd_event(vk as u8, 0, KEYEVENTF_KEYUP, 0);
+        }
+    }
+
+    #[cfg(any(target_os = "macos", target_os = "linux"))]
+    {
+        use enigo::{Direction, Enigo, Key, Keyboard, Settings};
+
+        let key = match separator {
+            QueueSeparator::None => return Ok(()),
+            QueueSeparator::Newline => Key::Return,
+            QueueSeparator::Tab => Key::Tab,
+        };
+        let mut enigo = Enigo::new(&Settings::default()).map_err(|e| e.to_string())?;
+        enigo.key(key, Direction::Click).map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+// 通过 enigo 的文本输入模拟键盘输入（不依赖键盘布局，所有平台通用）
+struct EnigoTyper(enigo::Enigo);
+
+impl EnigoTyper {
+    fn new() -> Result<Self, String> {
+        enigo::Enigo::new(&enigo::Settings::default())
+            .map(Self)
+            .map_err(|e| e.to_string())
+    }
+}
+
+impl TypingBackend for EnigoTyper {
+    fn text(&mut self, text: &str) -> Result<(), String> {
+        use enigo::Keyboard;
+        self.0.text(text).map_err(|e| e.to_string())
+    }
+
+    fn newline(&mut self, mode: NewlineMode) -> Result<(), String> {
+        use enigo::{Direction, Key, Keyboard};
+
+        if mode == NewlineMode::ShiftEnter {
+            self.0.key(Key::Shift, Direction::Press).map_err(|e| e.to_string())?;
+        }
+        let result = self.0.key(Key::Return, Direction::Click).map_err(|e| e.to_string());
+        if mode == NewlineMode::ShiftEnter {
+            self.0.key(Key::Shift, Direction::Release).map_err(|e| e.to_string())?;
+        }
+        result
+    }
+}
+
+// 一次模拟输入：要输入的文本、输入参数和取消标记
+struct TypingJob {
+    text: String,
+    options: TypingOptions,
+    cancel: Arc<AtomicBool>,
+}
+
+// 读取输入设置并清除上一次的取消标记
+async fn prepare_typing(state: &Arc<Mutex<AppState>>, text: String) -> Result<TypingJob, String> {
+    let (clipboard_manager, cancel) = {
+        let state = state.lock().await;
+        (state.clipboard_manager.clone(), state.typing_cancel.clone())
+    };
+    let settings = clipboard_manager.get_settings()?;
+    cancel.store(false, Ordering::SeqCst);
+
+    Ok(TypingJob {
+        text,
+        options: TypingOptions::from_settings(settings.typing_char_delay_ms, &settings.typing_newline),
+        cancel,
+    })
+}
+
+fn typing_cancel_shortcut() -> tauri_plugin_global_shortcut::Shortcut {
+    tauri_plugin_global_shortcut::Shortcut::new(None, tauri_plugin_global_shortcut::Code::Escape)
+}
+
+// 模拟输入期间临时注册 Escape 全局快捷键用于取消，返回是否注册成功
+fn register_typing_cancel_shortcut(app: &tauri::AppHandle, cancel: Arc<AtomicBool>) -> bool {
+    use tauri_plugin_global_shortcut::ShortcutState;
+
+    let result = app
+        .global_shortcut()
+        .on_shortcut(typing_cancel_shortcut(), move |_app, _shortcut, event| {
+            if event.state == ShortcutState::Pressed {
+                cancel.store(true, Ordering::SeqCst);
+            }
+        });
+    match result {
+        Ok(()) => true,
+        Err(e) => {
+            eprintln!("注册 Escape 快捷键失败，本次模拟输入无法中途取消: {}", e);
+            false
+        }
+    }
+}
+
+// 在阻塞线程中执行粘贴（模拟输入可能持续较长时间），模拟输入期间可按 Escape 取消
+async fn run_paste<F>(app: &tauri::AppHandle, cancel: Option<Arc<AtomicBool>>, paste: F) -> Result<(), String>
+where
+    F: FnOnce() -> Result<(), String> + Send + 'static,
+{
+    let escape_registered = cancel
+        .map(|cancel| register_typing_cancel_shortcut(app, cancel))
+        .unwrap_or(false);
+
+    let result = tokio::task::spawn_blocking(paste)
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|result| result);
+
+    if escape_registered {
+        if let Err(e) = app.global_shortcut().unregister(typing_cancel_shortcut()) {
+            eprintln!("注销 Escape 快捷键失败: {}", e);
+        }
+    }
+    result
+}
+
+// 切换回显示剪贴板窗口之前的前台窗口，并等待它获得焦点
+// 平台无法记录前台窗口时（macOS、纯 Wayland）退回固定等待，让窗口管理器自行切换焦点
+async fn focus_paste_target(state: &Arc<Mutex<AppState>>) -> Result<(), String> {
+    let target = {
+        let state = state.lock().await;
+        state.window_manager.previous_window().await
+    };
+
+    let Some(target) = target else {
+        tokio::time::sleep(std::time::Duration::from_millis(PASTE_FOCUS_FALLBACK_MS)).await;
+        return Ok(());
+    };
+
+    let timeout = std::time::Duration::from_millis(PASTE_FOCUS_TIMEOUT_MS);
+    tokio::task::spawn_blocking(move || platform::focus_window(target, timeout))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| format!("无法切换回目标窗口，已取消粘贴: {}", e))
+}
+
+// 按目标窗口匹配粘贴按键规则，目标未知或没有规则命中时使用全局的粘贴快捷键
+async fn resolve_paste_plan(state: &Arc<Mutex<AppState>>, paste_shortcut: &str) -> PastePlan {
+    let default = PasteKeystroke::from_setting(paste_shortcut);
+    let (target, clipboard_manager) = {
+        let state = state.lock().await;
+        (state.window_manager.previous_window().await, state.clipboard_manager.clone())
+    };
+    let Some(target) = target else {
+        return PastePlan::new(default);
+    };
+
+    let rules = match clipboard_manager.compiled_paste_rules() {
+        Ok(rules) if !rules.is_empty() => rules,
+        Ok(_) => return PastePlan::new(default),
+        Err(e) => {
+            eprintln!("读取粘贴规则失败: {}", e);
+            return PastePlan::new(default);
+        }
+    };
+    match platform::get_window_info(target) {
+        Ok(info) => rules.resolve(Some(&info), default),
+        Err(e) => {
+            eprintln!("获取目标窗口信息失败: {}", e);
+            PastePlan::new(default)
+        }
+    }
+}
+
+// 把剪贴板内容粘贴到之前的窗口（钉住模式下临时隐藏窗口）
+// 粘贴前可选地输入分隔符，粘贴后可选地左移光标
+// typed_text 不为空时模拟输入该文本；粘贴规则选择模拟输入时输入剪贴板中的文本
+async fn paste_to_previous_window(
+    state: &Arc<Mutex<AppState>>,
+    app: &tauri::AppHandle,
+    paste_shortcut: &str,
+    separator: Option<QueueSeparator>,
+    cursor_left: usize,
+    typed_text: Option<String>,
 ) -> Result<(), String> {
-    use tokio::time::{sleep, Duration};
+    let plan = resolve_paste_plan(state, paste_shortcut).await;
+    let typed_text = match typed_text {
+        Some(text) => Some(text),
+        None if plan.keystroke == PasteKeystroke::Typing => {
+            let clipboard_watcher = state.lock().await.clipboard_watcher.clone();
+            let text = clipboard_watcher
+                .read_text()
+                .filter(|text| !text.is_empty())
+                .ok_or_else(|| "剪贴板中没有可输入的文本".to_string())?;
+            Some(text)
+        }
+        None => None,
+    };
+    let typing_job = match typed_text {
+        Some(text) => Some(prepare_typing(state, text).await?),
+        None => None,
+    };
+    let cancel = typing_job.as_ref().map(|job| job.cancel.clone());
+
+    let paste = move || -> Result<(), String> {
+        std::thread::sleep(plan.pre_delay);
+        if let Some(separator) = separator {
+            type_separator(separator)?;
+        }
+        match &typing_job {
+            Some(job) => {
+                let outcome = typing::type_text(&mut EnigoTyper::new()?, &job.text, job.options, &job.cancel)?;
+                if outcome.cancelled {
+                    println!("模拟输入已取消，已输入 {} 个字符", outcome.typed_chars);
+                    return Ok(());
+                }
+            }
+            None => do_paste(plan.keystroke)?,
+        }
+        std::thread::sleep(plan.post_delay);
+        move_cursor_left(cursor_left)
+    };
 
     // 只在读取 Pin 状态时短暂持有 AppState 锁，避免跨 await 长时间占用
     let is_pinned = {
@@ -604,18 +1216,18 @@ async fn simulate_paste(
             window.hide().map_err(|e| e.to_string())?;
         }
 
-        // 2. 等待焦点转移
-        sleep(Duration::from_millis(80)).await;
-
-        // 3. 执行粘贴操作
-        let paste_result = do_paste(&paste_shortcut);
+        // 2. 切换回目标窗口，确认获得焦点后再执行粘贴
+        let paste_result = match focus_paste_target(state).await {
+            Ok(()) => run_paste(app, cancel, paste).await,
+            Err(e) => Err(e),
+        };
 
-        // 4. 无论粘贴是否成功，都尽量恢复窗口，保持连续粘贴体验
+        // 3. 无论粘贴是否成功，都尽量恢复窗口，保持连续粘贴体验
         let restore_result = if let Some(window) = app.get_webview_window("clipboard") {
             window.show().map_err(|e| e.to_string())?;
             window.set_focus().map_err(|e| e.to_string())?;
 
-        // 5. 恢复置顶状态
+        // 4. 恢复置顶状态
             #[cfg(target_os = "windows")]
             {
                 use winapi::um::winuser::{SetWindowPos, HWND_TOPMOST, SWP_NOMOVE, SWP_NOACTIVATE, SWP_NOSIZE};
@@ -637,13 +1249,541 @@ async fn simulate_paste(
         return restore_result;
     }
 
-    // 默认模式：等待窗口隐藏
-    sleep(Duration::from_millis(80)).await;
-    do_paste(&paste_shortcut)
+    // 默认模式：窗口已隐藏，切换回目标窗口
+    focus_paste_target(state).await?;
+    run_paste(app, cancel, paste).await
 }
 
 #[tauri::command]
-fn open_external_link(url: String) -> Result<(), String> {
+async fn simulate_paste(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    app: tauri::AppHandle,
+    paste_shortcut: String,
+) -> Result<(), String> {
+    paste_to_previous_window(state.inner(), &app, &paste_shortcut, None, 0, None).await
+}
+
+// 开启"粘贴后恢复剪贴板"时，在写入要粘贴的内容之前保存当前剪贴板
+async fn snapshot_before_paste(state: &Arc<Mutex<AppState>>) -> Result<Option<ClipboardSnapshot>, String> {
+    let (clipboard_manager, clipboard_watcher) = {
+        let state = state.lock().await;
+        (state.clipboard_manager.clone(), state.clipboard_watcher.clone())
+    };
+
+    if !clipboard_manager.get_settings()?.restore_clipboard_after_paste {
+        return Ok(None);
+    }
+    match clipboard_watcher.snapshot() {
+        Ok(snapshot) => Ok(Some(snapshot)),
+        Err(e) => {
+            eprintln!("保存剪贴板内容失败，粘贴后不会恢复: {}", e);
+            Ok(None)
+        }
+    }
+}
+
+// 等待目标程序读完剪贴板后恢复粘贴前的内容（不记录到历史）
+async fn restore_after_paste(state: &Arc<Mutex<AppState>>, snapshot: Option<ClipboardSnapshot>) {
+    let Some(snapshot) = snapshot else {
+        return;
+    };
+    let (clipboard_manager, clipboard_watcher) = {
+        let state = state.lock().await;
+        (state.clipboard_manager.clone(), state.clipboard_watcher.clone())
+    };
+
+    let delay = clipboard_manager
+        .get_settings()
+        .map(|settings| settings.restore_clipboard_delay_ms)
+        .unwrap_or(300);
+    tokio::time::sleep(std::time::Duration::from_millis(delay)).await;
+
+    if let Err(e) = clipboard_watcher.restore_unrecorded(&snapshot).await {
+        eprintln!("恢复剪贴板失败: {}", e);
+    }
+}
+
+/// 将记录写入剪贴板并粘贴到之前的窗口，开启"粘贴后恢复剪贴板"时粘贴后恢复原内容
+#[tauri::command]
+async fn paste_item(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    app: tauri::AppHandle,
+    item: ClipboardItem,
+    paste_shortcut: String,
+    copy_as_plain_text: Option<bool>,
+) -> Result<(), String> {
+    paste_clipboard_item(state.inner(), &app, item, &paste_shortcut, copy_as_plain_text.unwrap_or(false)).await
+}
+
+async fn paste_clipboard_item(
+    state: &Arc<Mutex<AppState>>,
+    app: &tauri::AppHandle,
+    item: ClipboardItem,
+    paste_shortcut: &str,
+    copy_as_plain_text: bool,
+) -> Result<(), String> {
+    let clipboard_watcher = state.lock().await.clipboard_watcher.clone();
+
+    let item = match item.content_type {
+        ClipboardContentType::Html | ClipboardContentType::Rtf if copy_as_plain_text => {
+            let text = item
+                .text_content
+                .clone()
+                .filter(|text| !text.trim().is_empty())
+                .unwrap_or_else(|| item.content.clone());
+            ClipboardItem {
+                content_type: ClipboardContentType::Text,
+                content: text,
+                ..item
+            }
+        }
+        _ => item,
+    };
+
+    let snapshot = snapshot_before_paste(state).await?;
+    clipboard_watcher.write_item(&item).await?;
+    let result = paste_to_previous_window(state, app, paste_shortcut, None, 0, None).await;
+    restore_after_paste(state, snapshot).await;
+    result
+}
+
+// ===== 片段相关命令 =====
+
+#[tauri::command]
+fn create_snippet(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    body: String,
+    tags: Option<Vec<String>>,
+) -> Result<ClipboardItem, String> {
+    let state = state.blocking_lock();
+    state.clipboard_manager.create_snippet(body, tags)
+}
+
+#[tauri::command]
+fn update_snippet(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    id: i64,
+    body: String,
+) -> Result<ClipboardItem, String> {
+    let state = state.blocking_lock();
+    state.clipboard_manager.update_snippet(id, body)
+}
+
+/// 获取片段需要用户填写的 `{{input:Name}}` 名称
+#[tauri::command]
+fn get_snippet_inputs(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    id: i64,
+) -> Result<Vec<String>, TemplateError> {
+    let state = state.blocking_lock();
+    let snippet = state.clipboard_manager.get_snippet(id)?;
+    template::input_names(&snippet.content)
+}
+
+/// 展开片段，返回展开后的文本和 {{cursor}} 之后的字符数
+async fn expand_snippet(
+    state: &Arc<Mutex<AppState>>,
+    id: i64,
+    inputs: HashMap<String, String>,
+) -> Result<(String, usize), TemplateError> {
+    let (snippet, clipboard_watcher) = {
+        let state = state.lock().await;
+        (state.clipboard_manager.get_snippet(id)?, state.clipboard_watcher.clone())
+    };
+
+    let clipboard = if template::uses_clipboard(&snippet.content)? {
+        clipboard_watcher.read_text()
+    } else {
+        None
+    };
+    let context = TemplateContext {
+        now: chrono::Local::now(),
+        clipboard,
+        inputs,
+    };
+    let expanded = template::expand(&snippet.content, &context)?;
+    Ok((expanded.text, expanded.cursor_offset.unwrap_or(0)))
+}
+
+/// 展开片段并写入剪贴板（不记录到历史），返回 {{cursor}} 之后的字符数
+async fn write_expanded_snippet(
+    state: &Arc<Mutex<AppState>>,
+    id: i64,
+    inputs: HashMap<String, String>,
+) -> Result<usize, TemplateError> {
+    let (text, cursor_left) = expand_snippet(state, id, inputs).await?;
+    let clipboard_watcher = state.lock().await.clipboard_watcher.clone();
+    clipboard_watcher.write_text_unrecorded(&text).await?;
+    Ok(cursor_left)
+}
+
+/// 展开片段并复制到剪贴板
+#[tauri::command]
+async fn copy_snippet(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    id: i64,
+    inputs: Option<HashMap<String, String>>,
+) -> Result<(), TemplateError> {
+    write_expanded_snippet(state.inner(), id, inputs.unwrap_or_default()).await?;
+    Ok(())
+}
+
+/// 展开片段并粘贴到之前的窗口，光标停在 {{cursor}} 处
+#[tauri::command]
+async fn paste_snippet(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    app: tauri::AppHandle,
+    id: i64,
+    inputs: Option<HashMap<String, String>>,
+    paste_shortcut: String,
+) -> Result<(), TemplateError> {
+    paste_expanded_snippet(state.inner(), &app, id, inputs.unwrap_or_default(), &paste_shortcut).await
+}
+
+async fn paste_expanded_snippet(
+    state: &Arc<Mutex<AppState>>,
+    app: &tauri::AppHandle,
+    id: i64,
+    inputs: HashMap<String, String>,
+    paste_shortcut: &str,
+) -> Result<(), TemplateError> {
+    let snapshot = snapshot_before_paste(state).await?;
+    let cursor_left = write_expanded_snippet(state, id, inputs).await?;
+    let result = paste_to_previous_window(state, app, paste_shortcut, None, cursor_left, None).await;
+    restore_after_paste(state, snapshot).await;
+    result?;
+    Ok(())
+}
+
+// 记录中可供模拟输入的纯文本（HTML/RTF 取纯文本内容）
+fn typing_text(item: &ClipboardItem) -> Option<String> {
+    let text = match item.content_type {
+        ClipboardContentType::Text => item.text_content.clone().or_else(|| Some(item.content.clone())),
+        ClipboardContentType::Html | ClipboardContentType::Rtf => item.text_content.clone(),
+        _ => None,
+    };
+    text.filter(|text| !text.is_empty())
+}
+
+/// 模拟键盘逐字输入记录的文本到之前的窗口（用于屏蔽粘贴的程序），输入过程中按 Escape 取消
+/// 片段先展开占位符，输入完成后光标停在 {{cursor}} 处
+#[tauri::command]
+async fn simulate_typing(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    app: tauri::AppHandle,
+    id: i64,
+    inputs: Option<HashMap<String, String>>,
+) -> Result<(), String> {
+    let clipboard_manager = state.lock().await.clipboard_manager.clone();
+    let item = clipboard_manager
+        .get_item(id)?
+        .ok_or_else(|| format!("记录不存在: {}", id))?;
+
+    let (text, cursor_left) = if item.content_type == ClipboardContentType::Snippet {
+        expand_snippet(state.inner(), id, inputs.unwrap_or_default())
+            .await
+            .map_err(|e| e.to_string())?
+    } else {
+        let text = typing_text(&item).ok_or_else(|| "该记录没有可输入的文本".to_string())?;
+        (text, 0)
+    };
+
+    // 规则中的粘贴前后等待时间同样适用于模拟输入
+    let paste_shortcut = clipboard_manager.get_settings()?.paste_shortcut;
+    paste_to_previous_window(state.inner(), &app, &paste_shortcut, None, cursor_left, Some(text)).await
+}
+
+// ===== 粘贴按键规则相关命令 =====
+
+/// 获取所有粘贴按键规则（按匹配顺序）
+#[tauri::command]
+fn get_paste_rules(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+) -> Result<Vec<PasteRule>, String> {
+    let state = state.blocking_lock();
+    state.clipboard_manager.get_paste_rules()
+}
+
+#[tauri::command]
+fn create_paste_rule(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    rule: PasteRuleInput,
+) -> Result<PasteRule, String> {
+    let state = state.blocking_lock();
+    state.clipboard_manager.create_paste_rule(rule)
+}
+
+#[tauri::command]
+fn update_paste_rule(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    id: i64,
+    rule: PasteRuleInput,
+) -> Result<PasteRule, String> {
+    let state = state.blocking_lock();
+    state.clipboard_manager.update_paste_rule(id, rule)
+}
+
+#[tauri::command]
+fn delete_paste_rule(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    id: i64,
+) -> Result<(), String> {
+    let state = state.blocking_lock();
+    state.clipboard_manager.delete_paste_rule(id)
+}
+
+/// 按给定的 ID 顺序调整规则的匹配顺序
+#[tauri::command]
+fn reorder_paste_rules(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    ids: Vec<i64>,
+) -> Result<Vec<PasteRule>, String> {
+    let state = state.blocking_lock();
+    state.clipboard_manager.reorder_paste_rules(ids)
+}
+
+// ===== 粘贴队列相关命令 =====
+
+/// 当前粘贴队列（已删除的记录不会出现在结果中）
+async fn paste_queue_state(state: &Arc<Mutex<AppState>>) -> Result<PasteQueueState, String> {
+    let (paste_queue, clipboard_manager) = {
+        let state = state.lock().await;
+        (state.paste_queue.clone(), state.clipboard_manager.clone())
+    };
+
+    let mut items = Vec::new();
+    for id in paste_queue.item_ids().await {
+        if let Some(item) = clipboard_manager.get_item(id)? {
+            items.push(item);
+        }
+    }
+
+    Ok(PasteQueueState {
+        items,
+        separator: paste_queue.separator().await,
+    })
+}
+
+/// 通知前端粘贴队列已变化，并返回最新状态
+async fn emit_paste_queue_changed(
+    state: &Arc<Mutex<AppState>>,
+    app: &tauri::AppHandle,
+) -> Result<PasteQueueState, String> {
+    let queue_state = paste_queue_state(state).await?;
+    let _ = app.emit("paste-queue-changed", &queue_state);
+    Ok(queue_state)
+}
+
+/// 粘贴队首记录并前进一项，队列为空时返回 None
+async fn paste_next(
+    state: &Arc<Mutex<AppState>>,
+    app: &tauri::AppHandle,
+) -> Result<Option<ClipboardItem>, String> {
+    let (paste_queue, clipboard_manager, clipboard_watcher) = {
+        let state = state.lock().await;
+        (
+            state.paste_queue.clone(),
+            state.clipboard_manager.clone(),
+            state.clipboard_watcher.clone(),
+        )
+    };
+
+    // 按快捷键时的前台窗口是粘贴目标（用户可能已切换到其他应用）
+    state.lock().await.window_manager.remember_paste_target(app).await;
+
+    // 取队首到前进一项之间不能有另一次 "粘贴下一项"
+    let _paste = paste_queue.lock_paste().await;
+    paste_queue.wait_for_paste_interval().await;
+
+    let Some(id) = paste_queue.peek().await else {
+        return Ok(None);
+    };
+    let Some(item) = clipboard_manager.get_item(id)? else {
+        paste_queue.remove(id).await;
+        emit_paste_queue_changed(state, app).await?;
+        return Err(format!("队列中的记录已被删除: {}", id));
+    };
+
+    let snapshot = snapshot_before_paste(state).await?;
+
+    // 片段展开后粘贴（需要填写输入值的片段无法从队列粘贴）
+    let cursor_left = if item.content_type == ClipboardContentType::Snippet {
+        write_expanded_snippet(state, id, HashMap::new())
+            .await
+            .map_err(|e| e.to_string())?
+    } else {
+        clipboard_watcher.write_item(&item).await?;
+        0
+    };
+
+    let separator = paste_queue.pending_separator().await;
+    let paste_shortcut = clipboard_manager.get_settings()?.paste_shortcut;
+    let result = paste_to_previous_window(state, app, &paste_shortcut, separator, cursor_left, None).await;
+    restore_after_paste(state, snapshot).await;
+    result?;
+
+    paste_queue.advance(id).await;
+    emit_paste_queue_changed(state, app).await?;
+    Ok(Some(item))
+}
+
+#[tauri::command]
+async fn get_paste_queue(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+) -> Result<PasteQueueState, String> {
+    paste_queue_state(state.inner()).await
+}
+
+/// 下一项要粘贴的记录
+#[tauri::command]
+async fn peek_paste_queue(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+) -> Result<Option<ClipboardItem>, String> {
+    let (paste_queue, clipboard_manager) = {
+        let state = state.lock().await;
+        (state.paste_queue.clone(), state.clipboard_manager.clone())
+    };
+
+    match paste_queue.peek().await {
+        Some(id) => clipboard_manager.get_item(id),
+        None => Ok(None),
+    }
+}
+
+/// 追加记录到队尾（已在队列中的记录忽略）
+#[tauri::command]
+async fn push_paste_queue(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    app: tauri::AppHandle,
+    ids: Vec<i64>,
+) -> Result<PasteQueueState, String> {
+    let (paste_queue, clipboard_manager) = {
+        let state = state.lock().await;
+        (state.paste_queue.clone(), state.clipboard_manager.clone())
+    };
+
+    for &id in &ids {
+        if clipboard_manager.get_item(id)?.is_none() {
+            return Err(format!("记录不存在: {}", id));
+        }
+    }
+    paste_queue.push(&ids).await;
+    emit_paste_queue_changed(state.inner(), &app).await
+}
+
+#[tauri::command]
+async fn remove_from_paste_queue(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    app: tauri::AppHandle,
+    id: i64,
+) -> Result<PasteQueueState, String> {
+    let paste_queue = state.lock().await.paste_queue.clone();
+    paste_queue.remove(id).await;
+    emit_paste_queue_changed(state.inner(), &app).await
+}
+
+/// 调整队列顺序（ids 为调整后的完整队列）
+#[tauri::command]
+async fn reorder_paste_queue(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    app: tauri::AppHandle,
+    ids: Vec<i64>,
+) -> Result<PasteQueueState, String> {
+    let paste_queue = state.lock().await.paste_queue.clone();
+    paste_queue.reorder(ids).await?;
+    emit_paste_queue_changed(state.inner(), &app).await
+}
+
+#[tauri::command]
+async fn clear_paste_queue(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    app: tauri::AppHandle,
+) -> Result<PasteQueueState, String> {
+    let paste_queue = state.lock().await.paste_queue.clone();
+    paste_queue.clear().await;
+    emit_paste_queue_changed(state.inner(), &app).await
+}
+
+#[tauri::command]
+async fn set_paste_queue_separator(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    app: tauri::AppHandle,
+    separator: QueueSeparator,
+) -> Result<PasteQueueState, String> {
+    let paste_queue = state.lock().await.paste_queue.clone();
+    paste_queue.set_separator(separator).await;
+    emit_paste_queue_changed(state.inner(), &app).await
+}
+
+/// 粘贴队列中的下一项（与"粘贴下一项"快捷键相同）
+#[tauri::command]
+async fn paste_next_in_queue(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    app: tauri::AppHandle,
+) -> Result<Option<ClipboardItem>, String> {
+    paste_next(state.inner(), &app).await
+}
+
+/// 注册"粘贴下一项"快捷键（松开时触发，避免修饰键干扰模拟粘贴）
+fn register_paste_next_shortcut(
+    app: &tauri::AppHandle,
+    app_state: Arc<Mutex<AppState>>,
+    shortcut: &str,
+) -> Result<(), String> {
+    use tauri_plugin_global_shortcut::ShortcutState;
+
+    let (modifiers, code) = shortcut_manager::parse_shortcut(shortcut)?;
+    let shortcut = tauri_plugin_global_shortcut::Shortcut::new(Some(modifiers), code);
+
+    app.global_shortcut()
+        .on_shortcut(shortcut, move |app, _shortcut, event| {
+            if event.state == ShortcutState::Released {
+                let app_handle = app.clone();
+                let s = app_state.clone();
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = paste_next(&s, &app_handle).await {
+                        eprintln!("粘贴队列下一项失败: {}", e);
+                    }
+                });
+            }
+        })
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn update_paste_next_shortcut(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    app: tauri::AppHandle,
+    old_shortcut: String,
+    new_shortcut: String,
+) -> Result<(), String> {
+    if !new_shortcut.is_empty() {
+        shortcut_manager::validate_hotkey(&new_shortcut)?;
+    }
+
+    if let Ok((modifiers, code)) = shortcut_manager::parse_shortcut(&old_shortcut) {
+        let old = tauri_plugin_global_shortcut::Shortcut::new(Some(modifiers), code);
+        let _ = app.global_shortcut().unregister(old);
+    }
+
+    if new_shortcut.is_empty() {
+        println!("粘贴队列快捷键已关闭");
+        return Ok(());
+    }
+
+    if register_paste_next_shortcut(&app, state.inner().clone(), &new_shortcut).is_err() {
+        if !old_shortcut.is_empty() {
+            let _ = register_paste_next_shortcut(&app, state.inner().clone(), &old_shortcut);
+        }
+        return Err(format!("快捷键 \"{}\" 注册失败，可能已被其他程序占用", new_shortcut));
+    }
+
+    println!("粘贴队列快捷键已动态更新为 '{}'", new_shortcut);
+    Ok(())
+}
+
+#[tauri::command]
+fn open_external_link(url: String) -> Result<(), String> {
     #[cfg(target_os = "windows")]
     {
         std::process::Command::new("cmd")
@@ -678,13 +1818,287 @@ fn show_settings_window(app: tauri::AppHandle) -> Result<(), String> {
     Ok(())
 }
 
+/// 由外部程序（IPC、启动参数）触发的粘贴：剪贴板窗口未显示时粘贴到调用时的前台窗口
+async fn paste_from_outside(
+    state: &Arc<Mutex<AppState>>,
+    app: &tauri::AppHandle,
+    item: ClipboardItem,
+    plain_text: bool,
+    inputs: HashMap<String, String>,
+) -> Result<(), String> {
+    let paste_shortcut = state.lock().await.clipboard_manager.get_settings()?.paste_shortcut;
+
+    state.lock().await.window_manager.remember_paste_target(app).await;
+
+    if item.content_type == ClipboardContentType::Snippet {
+        paste_expanded_snippet(state, app, item.id, inputs, &paste_shortcut)
+            .await
+            .map_err(|e| e.to_string())
+    } else {
+        paste_clipboard_item(state, app, item, &paste_shortcut, plain_text).await
+    }
+}
+
+// ===== 本地 IPC =====
+
+/// IPC 请求的实际处理（方法列表见 docs/TECH_DESIGN.md 的「本地 IPC」一节）
+struct AppIpcHandler {
+    state: Arc<Mutex<AppState>>,
+    app: tauri::AppHandle,
+}
+
+/// IPC 默认返回条数
+const IPC_DEFAULT_LIMIT: i64 = 50;
+
+#[derive(serde::Deserialize)]
+struct IpcIdParams {
+    id: i64,
+}
+
+#[derive(serde::Deserialize)]
+struct IpcListParams {
+    limit: Option<i64>,
+    offset: Option<i64>,
+}
+
+#[derive(serde::Deserialize)]
+struct IpcAddParams {
+    text: String,
+    html: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+#[derive(serde::Deserialize)]
+struct IpcPasteParams {
+    id: i64,
+    #[serde(default)]
+    plain_text: bool,
+    /// 片段的 {{input:名称}} 取值
+    #[serde(default)]
+    inputs: HashMap<String, String>,
+}
+
+#[derive(serde::Deserialize)]
+struct IpcSetTagsParams {
+    id: i64,
+    tags: Vec<String>,
+}
+
+#[derive(serde::Deserialize)]
+struct IpcCreateTagParams {
+    name: String,
+    color: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+struct IpcRenameTagParams {
+    id: i64,
+    name: String,
+}
+
+impl AppIpcHandler {
+    async fn clipboard_manager(&self) -> Result<ClipboardManager, ipc::RpcError> {
+        let clipboard_manager = self.state.lock().await.clipboard_manager.clone();
+        if clipboard_manager.is_locked() {
+            return Err(ipc::RpcError::new(ipc::error_code::SERVER_ERROR, "数据库已加密，请先在应用中解锁"));
+        }
+        Ok(clipboard_manager)
+    }
+
+    async fn require_item(&self, id: i64) -> Result<ClipboardItem, ipc::RpcError> {
+        self.clipboard_manager()
+            .await?
+            .get_item(id)?
+            .ok_or_else(|| ipc::RpcError::not_found(format!("记录不存在: {}", id)))
+    }
+
+    // 历史记录或标签被外部修改后通知前端重新加载
+    fn notify_history_changed(&self) {
+        let _ = self.app.emit("clipboard-history-changed", ());
+    }
+
+    async fn dispatch(&self, method: &str, params: serde_json::Value) -> Result<serde_json::Value, ipc::RpcError> {
+        match method {
+            "history.list" => {
+                let params: IpcListParams = ipc::params(params)?;
+                let request = GetHistoryRequest {
+                    limit: Some(params.limit.unwrap_or(IPC_DEFAULT_LIMIT)),
+                    offset: params.offset,
+                };
+                ipc::to_value(self.clipboard_manager().await?.get_history(request)?)
+            }
+            "history.search" => {
+                let mut request: AdvancedSearchRequest = ipc::params(params)?;
+                request.limit = Some(request.limit.unwrap_or(IPC_DEFAULT_LIMIT));
+                ipc::to_value(self.clipboard_manager().await?.search_history_advanced(request)?)
+            }
+            "history.get" => {
+                let params: IpcIdParams = ipc::params(params)?;
+                ipc::to_value(self.require_item(params.id).await?)
+            }
+            "history.add" => {
+                let params: IpcAddParams = ipc::params(params)?;
+                if params.text.is_empty() {
+                    return Err(ipc::RpcError::new(ipc::error_code::INVALID_PARAMS, "text 不能为空"));
+                }
+                // 外部程序添加的内容没有来源应用
+                let clipboard_manager = self
+                    .clipboard_manager()
+                    .await?
+                    .with_source_app_provider(Arc::new(platform::NoSourceApp));
+                let outcome = clipboard_manager.handle_clipboard_change(params.text, params.html, false).await?;
+                let item = match outcome.item {
+                    Some(item) if !params.tags.is_empty() => Some(clipboard_manager.append_tags(item.id, &params.tags)?),
+                    item => item,
+                };
+                if let Some(item) = &item {
+                    let _ = self.app.emit("clipboard-item-added", item);
+                }
+                // 命中敏感内容规则被丢弃时返回 null
+                ipc::to_value(item)
+            }
+            "history.delete" => {
+                let params: IpcIdParams = ipc::params(params)?;
+                self.require_item(params.id).await?;
+                self.clipboard_manager().await?.delete_item(params.id)?;
+                self.notify_history_changed();
+                Ok(serde_json::Value::Null)
+            }
+            "history.copy" => {
+                let params: IpcIdParams = ipc::params(params)?;
+                let item = self.require_item(params.id).await?;
+                if item.content_type == ClipboardContentType::Snippet {
+                    write_expanded_snippet(&self.state, item.id, HashMap::new())
+                        .await
+                        .map_err(|e| e.to_string())?;
+                } else {
+                    let clipboard_watcher = self.state.lock().await.clipboard_watcher.clone();
+                    clipboard_watcher.write_item(&item).await?;
+                }
+                Ok(serde_json::Value::Null)
+            }
+            "history.paste" => {
+                let params: IpcPasteParams = ipc::params(params)?;
+                let item = self.require_item(params.id).await?;
+                paste_from_outside(&self.state, &self.app, item, params.plain_text, params.inputs).await?;
+                Ok(serde_json::Value::Null)
+            }
+            "window.show" => {
+                let state = self.state.lock().await;
+                state.window_manager.show_clipboard_window(&self.app).await?;
+                Ok(serde_json::Value::Null)
+            }
+            "window.hide" => {
+                let state = self.state.lock().await;
+                state.window_manager.hide_clipboard_window(&self.app).await?;
+                Ok(serde_json::Value::Null)
+            }
+            "window.toggle" => {
+                let state = self.state.lock().await;
+                // 返回切换后窗口是否可见
+                ipc::to_value(state.window_manager.toggle_clipboard_window(&self.app).await?)
+            }
+            "tags.list" => ipc::to_value(self.clipboard_manager().await?.get_tags()?),
+            "tags.set" => {
+                let params: IpcSetTagsParams = ipc::params(params)?;
+                self.require_item(params.id).await?;
+                let tags = (!params.tags.is_empty()).then_some(params.tags);
+                self.clipboard_manager().await?.update_tags(params.id, tags)?;
+                self.notify_history_changed();
+                ipc::to_value(self.require_item(params.id).await?)
+            }
+            "tags.add" => {
+                let params: IpcSetTagsParams = ipc::params(params)?;
+                self.require_item(params.id).await?;
+                let item = self.clipboard_manager().await?.append_tags(params.id, &params.tags)?;
+                self.notify_history_changed();
+                ipc::to_value(item)
+            }
+            "tags.create" => {
+                let params: IpcCreateTagParams = ipc::params(params)?;
+                let tag = self.clipboard_manager().await?.create_tag(params.name, params.color)?;
+                self.notify_history_changed();
+                ipc::to_value(tag)
+            }
+            "tags.rename" => {
+                let params: IpcRenameTagParams = ipc::params(params)?;
+                let tag = self.clipboard_manager().await?.rename_tag(params.id, params.name)?;
+                self.notify_history_changed();
+                ipc::to_value(tag)
+            }
+            "tags.delete" => {
+                let params: IpcIdParams = ipc::params(params)?;
+                self.clipboard_manager().await?.delete_tag(params.id)?;
+                self.notify_history_changed();
+                Ok(serde_json::Value::Null)
+            }
+            _ => Err(ipc::RpcError::method_not_found(method)),
+        }
+    }
+}
+
+impl ipc::Handler for AppIpcHandler {
+    async fn handle(&self, method: String, params: serde_json::Value) -> Result<serde_json::Value, ipc::RpcError> {
+        self.dispatch(&method, params).await
+    }
+}
+
+// ===== 启动参数 =====
+
+/// 执行启动参数中的操作；forwarded 表示参数来自再次启动的实例，此时没有任何参数也显示设置窗口
+fn handle_launch_args(app: &tauri::AppHandle, args: LaunchArgs, forwarded: bool) {
+    if forwarded && !args.hidden && !args.has_action() {
+        tray_manager::show_settings_window(app);
+        return;
+    }
+
+    if args.settings {
+        tray_manager::show_settings_window(app);
+    }
+
+    let Some(state) = app.try_state::<Arc<Mutex<AppState>>>() else {
+        return;
+    };
+    let state = state.inner().clone();
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if args.show {
+            let result = {
+                let state = state.lock().await;
+                state.window_manager.show_clipboard_window(&app).await
+            };
+            if let Err(e) = result {
+                eprintln!("显示剪贴板窗口失败: {}", e);
+            }
+        }
+
+        if let Some(id) = args.paste {
+            let clipboard_manager = state.lock().await.clipboard_manager.clone();
+            let result = match clipboard_manager.get_item(id) {
+                Ok(Some(item)) => paste_from_outside(&state, &app, item, false, HashMap::new()).await,
+                Ok(None) => Err(format!("记录不存在: {}", id)),
+                Err(e) => Err(e),
+            };
+            if let Err(e) = result {
+                eprintln!("粘贴记录 {} 失败: {}", id, e);
+            }
+        }
+    });
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     use tauri_plugin_global_shortcut::ShortcutState;
     use tauri_plugin_autostart::MacosLauncher;
 
     tauri::Builder::default()
+        // 单实例：再次启动时把参数转发给已运行的实例后退出（需要最先注册，先于打开数据库和注册快捷键）
+        .plugin(tauri_plugin_single_instance::init(|app, args, _cwd| {
+            handle_launch_args(app, LaunchArgs::parse(args), true);
+        }))
         .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_clipboard_x::init())
         .plugin(tauri_plugin_global_shortcut::Builder::new().build())
         .plugin(tauri_plugin_autostart::init(
@@ -696,29 +2110,61 @@ pub fn run() {
         // 禁用 webview 的默认行为（阻止系统菜单、快捷键等）
         // https://github.com/ferreira-tb/tauri-plugin-prevent-default
         .plugin(prevent_default::init())
+        .register_asynchronous_uri_scheme_protocol("clipimg", |ctx, request, responder| {
+            let app = ctx.app_handle().clone();
+            tauri::async_runtime::spawn(async move {
+                responder.respond(serve_stored_image(&app, &request).await);
+            });
+        })
         .setup(|app| {
             let app_dir = app
                 .path()
                 .app_local_data_dir()
                 .unwrap_or_else(|_| PathBuf::from("."));
 
-            let database = Arc::new(
-                Database::new(app_dir).expect("Failed to initialize database"),
-            );
+            let (database, image_key) = open_database(&app_dir).expect("Failed to initialize database");
+            let database = Arc::new(database);
+            let is_locked = database.is_locked();
+            if is_locked {
+                println!("数据库已加密，等待输入口令解锁");
+            }
 
             let settings = database
                 .get_settings()
                 .unwrap_or_else(|_| AppSettings::default());
             let settings = Arc::new(Mutex::new(settings));
 
-            // 在移动 database 之前先检查是否是首次运行
-            let is_first_run = database
+            // 在移动 database 之前先检查是否是首次运行（数据库锁定时无法判断，按非首次处理）
+            let is_first_run = !is_locked && database
                 .is_first_run()
                 .unwrap_or(true);
 
-            let app_state = Arc::new(Mutex::new(AppState::new(database.clone(), settings.clone())));
+            let ipc_socket = ipc::socket_path(&app_dir);
+            let app_state = Arc::new(Mutex::new(AppState::new(
+                database.clone(),
+                settings.clone(),
+                app_dir,
+                image_key,
+            )));
             app.manage(app_state.clone());
 
+            // 启动本地 IPC 服务，供外部程序查询和操作历史记录
+            let ipc_handler = Arc::new(AppIpcHandler {
+                state: app_state.clone(),
+                app: app.handle().clone(),
+            });
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = ipc::serve(ipc_socket, ipc_handler).await {
+                    eprintln!("IPC 服务启动失败: {}", e);
+                }
+            });
+
+            // 启动后端剪贴板监听（不依赖 webview）
+            app_state
+                .blocking_lock()
+                .clipboard_watcher
+                .start(app.handle().clone());
+
             // 启动时自动清理（保留有标签的记录）
             let app_state_for_cleanup = app_state.clone();
             tauri::async_runtime::spawn(async move {
@@ -737,6 +2183,30 @@ pub fn run() {
                         eprintln!("启动时自动清理失败: {}", e);
                     }
                 }
+
+                // 定期回收图片存储中的孤立文件
+                clipboard_manager.run_image_gc_schedule().await;
+            });
+
+            // 定期删除过期的敏感记录，并通知前端刷新列表
+            let app_state_for_expiry = app_state.clone();
+            let app_handle_for_expiry = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                let clipboard_manager = app_state_for_expiry.lock().await.clipboard_manager.clone();
+                let mut interval = tokio::time::interval(SENSITIVE_PURGE_INTERVAL);
+                loop {
+                    interval.tick().await;
+                    if clipboard_manager.is_locked() {
+                        continue;
+                    }
+                    match clipboard_manager.purge_expired_items() {
+                        Ok(ids) if !ids.is_empty() => {
+                            let _ = app_handle_for_expiry.emit("clipboard-items-expired", &ids);
+                        }
+                        Ok(_) => {}
+                        Err(e) => eprintln!("删除过期敏感记录失败: {}", e),
+                    }
+                }
             });
 
             // 尝试注册主快捷键
@@ -777,6 +2247,15 @@ pub fn run() {
                 }
             }
 
+            // 注册粘贴队列"粘贴下一项"快捷键（为空表示不启用）
+            let paste_next_shortcut = settings.blocking_lock().paste_next_shortcut.clone();
+            if !paste_next_shortcut.is_empty() {
+                match register_paste_next_shortcut(app.handle(), app_state.clone(), &paste_next_shortcut) {
+                    Ok(()) => println!("粘贴队列快捷键 '{}' 注册成功", paste_next_shortcut),
+                    Err(e) => eprintln!("警告: 粘贴队列快捷键 '{}' 注册失败: {}", paste_next_shortcut, e),
+                }
+            }
+
             // TODO: 钉住模式快捷键暂时禁用，待功能完善后恢复
             // let pin_shortcut = settings.blocking_lock().pin_shortcut.clone();
             // let app_handle_for_pin_shortcut = app.handle().clone();
@@ -844,6 +2323,14 @@ pub fn run() {
                     eprintln!("初始化开机自启状态失败: {}", e);
                 }
 
+                // 数据库等待解锁时显示设置窗口，由用户输入口令
+                if is_locked {
+                    if let Some(window) = app_handle.get_webview_window("main") {
+                        let _ = window.show();
+                        let _ = window.set_focus();
+                    }
+                }
+
                 // 如果是首次运行，显示设置窗口并标记已初始化
                 if is_first_run {
                     println!("首次运行，显示设置窗口");
@@ -858,31 +2345,76 @@ pub fn run() {
                 }
             });
 
+            handle_launch_args(app.handle(), LaunchArgs::parse(std::env::args()), false);
+
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             add_clipboard_item,
             add_clipboard_item_extended,
+            check_sensitive_content,
+            mark_internal_copy,
             get_clipboard_history,
             search_clipboard_history,
             search_clipboard_advanced,
+            get_source_apps,
+            get_paste_rules,
+            create_paste_rule,
+            update_paste_rule,
+            delete_paste_rule,
+            reorder_paste_rules,
             delete_clipboard_item,
             clear_clipboard_history,
+            collect_image_garbage,
             get_settings,
             save_settings,
             toggle_clipboard_window,
             hide_clipboard_window,
+            reset_clipboard_window_size,
             open_file,
             show_in_folder,
             get_file_size,
             update_tags,
             get_all_tags,
+            get_tags,
+            create_tag,
+            rename_tag,
+            recolor_tag,
+            merge_tags,
+            delete_tag,
             validate_shortcut,
             update_hotkey,
             export_clipboard_data,
+            export_archive,
+            import_archive,
+            import_external,
             import_clipboard_data,
             get_storage_paths,
+            // 静态加密相关命令
+            get_encryption_status,
+            enable_encryption,
+            disable_encryption,
+            rotate_encryption,
+            unlock_database,
+            write_image_to_clipboard,
             simulate_paste,
+            paste_item,
+            create_snippet,
+            update_snippet,
+            get_snippet_inputs,
+            copy_snippet,
+            paste_snippet,
+            simulate_typing,
+            // 粘贴队列相关命令
+            get_paste_queue,
+            peek_paste_queue,
+            push_paste_queue,
+            remove_from_paste_queue,
+            reorder_paste_queue,
+            clear_paste_queue,
+            set_paste_queue_separator,
+            paste_next_in_queue,
+            update_paste_next_shortcut,
             get_app_version,
             open_external_link,
             // 钉住模式相关命令
@@ -0,0 +1,607 @@
+//! 数据库结构迁移
+//! 通过 `PRAGMA user_version` 记录当前结构版本，启动时按顺序执行尚未应用的迁移。
+//! 所有待执行的迁移在同一个事务中完成，任一步失败都会整体回滚，数据库保持升级前的状态。
+
+use rusqlite::{params, Connection, Result, Transaction};
+
+use crate::search;
+
+/// 单个迁移步骤
+struct Migration {
+    /// 执行后的结构版本（从 1 开始连续递增）
+    version: i32,
+    description: &'static str,
+    up: fn(&Transaction) -> Result<()>,
+}
+
+/// 迁移列表（只能追加，不能修改已发布的步骤）
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "初始表结构",
+        up: initial_schema,
+    },
+    Migration {
+        version: 2,
+        description: "拼音索引列",
+        up: pinyin_columns,
+    },
+    Migration {
+        version: 3,
+        description: "FTS5 全文索引",
+        up: full_text_index,
+    },
+    Migration {
+        version: 4,
+        description: "标签表",
+        up: normalized_tags,
+    },
+    Migration {
+        version: 5,
+        description: "敏感内容标记",
+        up: sensitive_columns,
+    },
+    Migration {
+        version: 6,
+        description: "来源应用",
+        up: source_app_columns,
+    },
+    Migration {
+        version: 7,
+        description: "粘贴按键规则",
+        up: paste_rules_table,
+    },
+];
+
+/// 迁移列表中的最新结构版本
+fn latest_version(migrations: &[Migration]) -> i32 {
+    migrations.last().map(|m| m.version).unwrap_or(0)
+}
+
+/// 读取数据库当前结构版本
+fn current_version(conn: &Connection) -> Result<i32> {
+    conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+}
+
+/// 执行所有尚未应用的迁移
+///
+/// 返回升级前的版本。数据库版本高于程序支持的版本时（用旧版本程序打开新数据库）直接报错，
+/// 避免旧代码写坏新结构。
+pub fn migrate(conn: &mut Connection) -> Result<i32> {
+    apply(conn, MIGRATIONS)
+}
+
+fn apply(conn: &mut Connection, migrations: &[Migration]) -> Result<i32> {
+    let from_version = current_version(conn)?;
+    let latest = latest_version(migrations);
+
+    if from_version > latest {
+        return Err(rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_CANTOPEN),
+            Some(format!(
+                "数据库结构版本 {} 高于当前程序支持的版本 {}，请升级程序",
+                from_version, latest
+            )),
+        ));
+    }
+    if from_version == latest {
+        return Ok(from_version);
+    }
+
+    let tx = conn.transaction()?;
+    for migration in migrations.iter().filter(|m| m.version > from_version) {
+        (migration.up)(&tx).map_err(|e| {
+            eprintln!(
+                "数据库迁移失败 (v{} {}): {}，已回滚到 v{}",
+                migration.version, migration.description, e, from_version
+            );
+            e
+        })?;
+        // user_version 写在数据库头部，同样受事务保护
+        tx.pragma_update(None, "user_version", migration.version)?;
+    }
+    tx.commit()?;
+
+    eprintln!("数据库结构已从 v{} 升级到 v{}", from_version, latest);
+    Ok(from_version)
+}
+
+/// 表中是否存在某列
+fn has_column(tx: &Transaction, table: &str, column: &str) -> Result<bool> {
+    tx.query_row(
+        "SELECT EXISTS(SELECT 1 FROM pragma_table_info(?1) WHERE name = ?2)",
+        params![table, column],
+        |row| row.get(0),
+    )
+}
+
+/// 列不存在时添加（兼容引入版本号之前按列检查升级过的数据库）
+fn add_column_if_missing(tx: &Transaction, table: &str, column: &str, definition: &str) -> Result<()> {
+    if !has_column(tx, table, column)? {
+        tx.execute(
+            &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
+            [],
+        )?;
+    }
+    Ok(())
+}
+
+/// v1: 剪贴板历史表和设置表
+///
+/// 引入版本号之前的数据库版本都是 0，这里同时补齐旧版本逐步添加的列，
+/// 并把旧的 is_favorite 字段转换为"收藏"标签（只会执行一次）。
+fn initial_schema(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS clipboard_history (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            content_type TEXT NOT NULL,
+            content TEXT NOT NULL,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            content_hash TEXT NOT NULL UNIQUE,
+            text_content TEXT,
+            metadata TEXT,
+            file_paths TEXT,
+            thumbnail_path TEXT,
+            tags TEXT
+        );
+
+        CREATE TABLE IF NOT EXISTS settings (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        );",
+    )?;
+
+    add_column_if_missing(tx, "clipboard_history", "metadata", "TEXT")?;
+    add_column_if_missing(tx, "clipboard_history", "file_paths", "TEXT")?;
+    add_column_if_missing(tx, "clipboard_history", "thumbnail_path", "TEXT")?;
+    add_column_if_missing(tx, "clipboard_history", "tags", "TEXT")?;
+    add_column_if_missing(tx, "clipboard_history", "text_content", "TEXT")?;
+
+    if has_column(tx, "clipboard_history", "is_favorite")? {
+        tx.execute(
+            "UPDATE clipboard_history SET tags = '[\"收藏\"]' WHERE is_favorite = 1 AND tags IS NULL",
+            [],
+        )?;
+    }
+
+    Ok(())
+}
+
+/// v2: 预先计算的全拼 / 拼音首字母列，并为已有记录回填
+///
+/// NULL 表示尚未计算，不含汉字的记录存空字符串。拼音来源的取法固定在这一步里
+/// （不调用 `Database::compute_pinyin`），之后修改插入时的取法不会改变这一步的结果。
+fn pinyin_columns(tx: &Transaction) -> Result<()> {
+    add_column_if_missing(tx, "clipboard_history", "pinyin", "TEXT")?;
+    add_column_if_missing(tx, "clipboard_history", "pinyin_initials", "TEXT")?;
+
+    // (id, content_type, content, text_content, metadata)
+    type SourceRow = (i64, String, String, Option<String>, Option<String>);
+
+    let rows: Vec<SourceRow> = tx
+        .prepare(
+            "SELECT id, content_type, content, text_content, metadata
+             FROM clipboard_history
+             WHERE pinyin IS NULL OR pinyin_initials IS NULL",
+        )?
+        .query_map([], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?))
+        })?
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut stmt = tx.prepare(
+        "UPDATE clipboard_history SET pinyin = ?1, pinyin_initials = ?2 WHERE id = ?3",
+    )?;
+    for (id, content_type, content, text_content, metadata) in &rows {
+        // HTML 取纯文本，其他类型取 content，再接上 metadata 中的文件名 / 文件夹名
+        let mut source = match content_type.as_str() {
+            "html" => text_content.clone().unwrap_or_default(),
+            _ => content.clone(),
+        };
+        let metadata: Option<serde_json::Value> = metadata
+            .as_deref()
+            .and_then(|s| serde_json::from_str(s).ok());
+        for key in ["file_name", "folder_name"] {
+            if let Some(name) = metadata.as_ref().and_then(|m| m.get(key)).and_then(|v| v.as_str()) {
+                source.push(' ');
+                source.push_str(name);
+            }
+        }
+
+        let (pinyin, initials) = if search::contains_hanzi(&source) {
+            (search::to_pinyin_full(&source), search::to_pinyin_initials(&source))
+        } else {
+            (String::new(), String::new())
+        };
+        stmt.execute(params![pinyin, initials, id])?;
+    }
+
+    Ok(())
+}
+
+/// v3: FTS5 全文索引（trigram 分词，支持中文子串匹配）
+///
+/// - body: HTML 类型取 text_content，其他类型取 content
+/// - tags: 标签 JSON 数组原文
+/// - file_names: metadata 中的文件名 / 文件夹名
+/// - pinyin / initials: 插入时预先计算的全拼和拼音首字母
+///
+/// 引入版本号之前可能已经建过列不同的旧索引，先整体删除再重建。
+fn full_text_index(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        "DROP TRIGGER IF EXISTS clipboard_history_fts_insert;
+        DROP TRIGGER IF EXISTS clipboard_history_fts_delete;
+        DROP TRIGGER IF EXISTS clipboard_history_fts_update;
+        DROP TABLE IF EXISTS clipboard_fts;
+
+        CREATE VIRTUAL TABLE clipboard_fts USING fts5(
+            body, tags, file_names, pinyin, initials,
+            tokenize = 'trigram'
+        );
+
+        CREATE TRIGGER clipboard_history_fts_insert AFTER INSERT ON clipboard_history BEGIN
+            INSERT INTO clipboard_fts (rowid, body, tags, file_names, pinyin, initials) VALUES (
+                new.id,
+                CASE WHEN new.content_type = 'html' THEN COALESCE(new.text_content, '') ELSE new.content END,
+                COALESCE(new.tags, ''),
+                CASE WHEN json_valid(new.metadata)
+                    THEN TRIM(COALESCE(json_extract(new.metadata, '$.file_name'), '') || ' ' || COALESCE(json_extract(new.metadata, '$.folder_name'), ''))
+                    ELSE '' END,
+                COALESCE(new.pinyin, ''),
+                COALESCE(new.pinyin_initials, '')
+            );
+        END;
+
+        CREATE TRIGGER clipboard_history_fts_delete AFTER DELETE ON clipboard_history BEGIN
+            DELETE FROM clipboard_fts WHERE rowid = old.id;
+        END;
+
+        CREATE TRIGGER clipboard_history_fts_update
+        AFTER UPDATE OF content_type, content, text_content, tags, metadata, pinyin, pinyin_initials ON clipboard_history BEGIN
+            DELETE FROM clipboard_fts WHERE rowid = old.id;
+            INSERT INTO clipboard_fts (rowid, body, tags, file_names, pinyin, initials) VALUES (
+                new.id,
+                CASE WHEN new.content_type = 'html' THEN COALESCE(new.text_content, '') ELSE new.content END,
+                COALESCE(new.tags, ''),
+                CASE WHEN json_valid(new.metadata)
+                    THEN TRIM(COALESCE(json_extract(new.metadata, '$.file_name'), '') || ' ' || COALESCE(json_extract(new.metadata, '$.folder_name'), ''))
+                    ELSE '' END,
+                COALESCE(new.pinyin, ''),
+                COALESCE(new.pinyin_initials, '')
+            );
+        END;
+
+        INSERT INTO clipboard_fts (rowid, body, tags, file_names, pinyin, initials)
+        SELECT
+            id,
+            CASE WHEN content_type = 'html' THEN COALESCE(text_content, '') ELSE content END,
+            COALESCE(tags, ''),
+            CASE WHEN json_valid(metadata)
+                THEN TRIM(COALESCE(json_extract(metadata, '$.file_name'), '') || ' ' || COALESCE(json_extract(metadata, '$.folder_name'), ''))
+                ELSE '' END,
+            COALESCE(pinyin, ''),
+            COALESCE(pinyin_initials, '')
+        FROM clipboard_history;",
+    )
+}
+
+/// v4 创建标签时使用的颜色（与当时 `Database::default_tag_color` 的取法相同，固定在这一步里）
+const V4_TAG_COLORS: [&str; 12] = [
+    "#fa8c16", "#1890ff", "#52c41a", "#ff4d4f", "#722ed1", "#eb2f96",
+    "#13c2c2", "#faad14", "#2f54eb", "#595959", "#d48806", "#08979c",
+];
+
+/// v4: 标签拆分为 tags / item_tags 两张表
+///
+/// 旧的 tags 列（JSON 字符串数组）迁移到新表后删除；全文索引的 tags 列改为从 item_tags 汇总，
+/// 由 item_tags 的增删和标签改名触发更新。与 v2 一样，创建标签的 SQL 和颜色取法都固定在这一步里
+/// （不调用 `Database::ensure_tag`）。
+fn normalized_tags(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE tags (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+            color TEXT NOT NULL,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+
+        CREATE TABLE item_tags (
+            item_id INTEGER NOT NULL REFERENCES clipboard_history(id) ON DELETE CASCADE,
+            tag_id INTEGER NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+            PRIMARY KEY (item_id, tag_id)
+        );
+
+        CREATE INDEX idx_item_tags_tag_id ON item_tags(tag_id);",
+    )?;
+
+    // 迁移 JSON 标签（同名不同大小写的标签合并为一个）
+    let rows: Vec<(i64, String)> = tx
+        .prepare("SELECT id, tags FROM clipboard_history WHERE tags IS NOT NULL AND tags != ''")?
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut malformed = Vec::new();
+    for (item_id, tags_json) in rows {
+        let Ok(names) = serde_json::from_str::<Vec<String>>(&tags_json) else {
+            malformed.push(item_id);
+            continue;
+        };
+        for name in names.iter().map(|n| n.trim()).filter(|n| !n.is_empty()) {
+            tx.execute(
+                "INSERT OR IGNORE INTO tags (name, color) VALUES (?1, ?2)",
+                params![name, v4_tag_color(name)],
+            )?;
+            let tag_id: i64 = tx.query_row("SELECT id FROM tags WHERE name = ?1", params![name], |row| row.get(0))?;
+            tx.execute(
+                "INSERT OR IGNORE INTO item_tags (item_id, tag_id) VALUES (?1, ?2)",
+                params![item_id, tag_id],
+            )?;
+        }
+    }
+    // 之后会删除 tags 列，无法解析的标签会丢失，至少在日志中留下记录
+    if !malformed.is_empty() {
+        eprintln!(
+            "迁移标签时跳过 {} 条标签格式无效的记录: {:?}",
+            malformed.len(),
+            malformed
+        );
+    }
+
+    // 旧触发器引用了 tags 列，必须先删除才能删列
+    tx.execute_batch(
+        "DROP TRIGGER IF EXISTS clipboard_history_fts_insert;
+        DROP TRIGGER IF EXISTS clipboard_history_fts_update;
+
+        ALTER TABLE clipboard_history DROP COLUMN tags;
+
+        CREATE TRIGGER clipboard_history_fts_insert AFTER INSERT ON clipboard_history BEGIN
+            INSERT INTO clipboard_fts (rowid, body, tags, file_names, pinyin, initials) VALUES (
+                new.id,
+                CASE WHEN new.content_type = 'html' THEN COALESCE(new.text_content, '') ELSE new.content END,
+                COALESCE((SELECT group_concat(t.name, ' ') FROM item_tags it JOIN tags t ON t.id = it.tag_id WHERE it.item_id = new.id), ''),
+                CASE WHEN json_valid(new.metadata)
+                    THEN TRIM(COALESCE(json_extract(new.metadata, '$.file_name'), '') || ' ' || COALESCE(json_extract(new.metadata, '$.folder_name'), ''))
+                    ELSE '' END,
+                COALESCE(new.pinyin, ''),
+                COALESCE(new.pinyin_initials, '')
+            );
+        END;
+
+        CREATE TRIGGER clipboard_history_fts_update
+        AFTER UPDATE OF content_type, content, text_content, metadata, pinyin, pinyin_initials ON clipboard_history BEGIN
+            DELETE FROM clipboard_fts WHERE rowid = old.id;
+            INSERT INTO clipboard_fts (rowid, body, tags, file_names, pinyin, initials) VALUES (
+                new.id,
+                CASE WHEN new.content_type = 'html' THEN COALESCE(new.text_content, '') ELSE new.content END,
+                COALESCE((SELECT group_concat(t.name, ' ') FROM item_tags it JOIN tags t ON t.id = it.tag_id WHERE it.item_id = new.id), ''),
+                CASE WHEN json_valid(new.metadata)
+                    THEN TRIM(COALESCE(json_extract(new.metadata, '$.file_name'), '') || ' ' || COALESCE(json_extract(new.metadata, '$.folder_name'), ''))
+                    ELSE '' END,
+                COALESCE(new.pinyin, ''),
+                COALESCE(new.pinyin_initials, '')
+            );
+        END;
+
+        CREATE TRIGGER item_tags_fts_insert AFTER INSERT ON item_tags BEGIN
+            UPDATE clipboard_fts
+            SET tags = COALESCE((SELECT group_concat(t.name, ' ') FROM item_tags it JOIN tags t ON t.id = it.tag_id WHERE it.item_id = new.item_id), '')
+            WHERE rowid = new.item_id;
+        END;
+
+        CREATE TRIGGER item_tags_fts_delete AFTER DELETE ON item_tags BEGIN
+            UPDATE clipboard_fts
+            SET tags = COALESCE((SELECT group_concat(t.name, ' ') FROM item_tags it JOIN tags t ON t.id = it.tag_id WHERE it.item_id = old.item_id), '')
+            WHERE rowid = old.item_id;
+        END;
+
+        CREATE TRIGGER tags_fts_rename AFTER UPDATE OF name ON tags BEGIN
+            UPDATE clipboard_fts
+            SET tags = COALESCE((SELECT group_concat(t.name, ' ') FROM item_tags it JOIN tags t ON t.id = it.tag_id WHERE it.item_id = clipboard_fts.rowid), '')
+            WHERE rowid IN (SELECT item_id FROM item_tags WHERE tag_id = new.id);
+        END;
+
+        UPDATE clipboard_fts
+        SET tags = COALESCE((SELECT group_concat(t.name, ' ') FROM item_tags it JOIN tags t ON t.id = it.tag_id WHERE it.item_id = clipboard_fts.rowid), '');",
+    )
+}
+
+// 标签名的 UTF-16 哈希取模选色
+fn v4_tag_color(name: &str) -> &'static str {
+    let mut hash: i32 = 0;
+    for unit in name.encode_utf16() {
+        hash = hash.wrapping_shl(5).wrapping_sub(hash).wrapping_add(unit as i32);
+    }
+    V4_TAG_COLORS[(hash as i64).unsigned_abs() as usize % V4_TAG_COLORS.len()]
+}
+
+/// v5: 敏感内容标记和过期时间
+fn sensitive_columns(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        "ALTER TABLE clipboard_history ADD COLUMN is_sensitive INTEGER NOT NULL DEFAULT 0;
+        ALTER TABLE clipboard_history ADD COLUMN expires_at DATETIME;
+
+        CREATE INDEX idx_clipboard_history_expires_at ON clipboard_history(expires_at)
+            WHERE expires_at IS NOT NULL;",
+    )
+}
+
+/// v6: 来源应用（进程名和窗口标题）
+fn source_app_columns(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        "ALTER TABLE clipboard_history ADD COLUMN source_app TEXT;
+        ALTER TABLE clipboard_history ADD COLUMN source_window_title TEXT;
+
+        CREATE INDEX idx_clipboard_history_source_app ON clipboard_history(source_app COLLATE NOCASE)
+            WHERE source_app IS NOT NULL;",
+    )
+}
+
+/// v7: 按目标程序选择粘贴按键的规则表
+fn paste_rules_table(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE paste_rules (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            match_field TEXT NOT NULL DEFAULT 'process',
+            pattern TEXT NOT NULL,
+            keystroke TEXT NOT NULL DEFAULT 'ctrl_v',
+            pre_delay_ms INTEGER NOT NULL DEFAULT 0,
+            post_delay_ms INTEGER NOT NULL DEFAULT 0,
+            enabled INTEGER NOT NULL DEFAULT 1,
+            sort_order INTEGER NOT NULL DEFAULT 0,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 引入版本号之前（v0）的表结构，含更早版本的 is_favorite 列
+    fn baseline_connection() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE clipboard_history (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                content_type TEXT NOT NULL,
+                content TEXT NOT NULL,
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                content_hash TEXT NOT NULL UNIQUE,
+                text_content TEXT,
+                metadata TEXT,
+                file_paths TEXT,
+                thumbnail_path TEXT,
+                tags TEXT,
+                is_favorite INTEGER DEFAULT 0
+            );
+            CREATE TABLE settings (key TEXT PRIMARY KEY, value TEXT NOT NULL);
+
+            INSERT INTO clipboard_history (id, content_type, content, content_hash, text_content, tags, is_favorite)
+            VALUES
+                (1, 'text', '复制 world', 'h1', '复制 world', '[\"工作\", \"Rust\"]', 0),
+                (2, 'html', '<b>x</b>', 'h2', '中文', NULL, 1),
+                (3, 'text', 'plain', 'h3', 'plain', '[\"rust\"]', 0),
+                (4, 'text', 'broken', 'h4', 'broken', 'not json', 0);",
+        )
+        .unwrap();
+        conn
+    }
+
+    fn tag_names(conn: &Connection, item_id: i64) -> Vec<String> {
+        conn.prepare(
+            "SELECT t.name FROM item_tags it JOIN tags t ON t.id = it.tag_id
+             WHERE it.item_id = ?1 ORDER BY t.name",
+        )
+        .unwrap()
+        .query_map([item_id], |row| row.get(0))
+        .unwrap()
+        .collect::<Result<Vec<String>>>()
+        .unwrap()
+    }
+
+    #[test]
+    fn migrates_baseline_to_latest() {
+        let mut conn = baseline_connection();
+
+        assert_eq!(migrate(&mut conn).unwrap(), 0);
+        assert_eq!(current_version(&conn).unwrap(), latest_version(MIGRATIONS));
+
+        // v2 回填拼音：HTML 取纯文本，不含汉字的记录为空字符串
+        let pinyin = |id: i64| -> (String, String) {
+            conn.query_row(
+                "SELECT pinyin, pinyin_initials FROM clipboard_history WHERE id = ?1",
+                [id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap()
+        };
+        assert_eq!(pinyin(1), ("fuzhi world".to_string(), "fz world".to_string()));
+        assert_eq!(pinyin(2), ("zhongwen".to_string(), "zw".to_string()));
+        assert_eq!(pinyin(3), (String::new(), String::new()));
+
+        // 再次执行不做任何事
+        assert_eq!(migrate(&mut conn).unwrap(), latest_version(MIGRATIONS));
+    }
+
+    #[test]
+    fn v4_moves_json_tags_into_item_tags() {
+        let mut conn = baseline_connection();
+        migrate(&mut conn).unwrap();
+
+        let has_tags_column: bool = conn
+            .query_row(
+                "SELECT EXISTS(SELECT 1 FROM pragma_table_info('clipboard_history') WHERE name = 'tags')",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert!(!has_tags_column);
+
+        assert_eq!(tag_names(&conn, 1), vec!["Rust", "工作"]);
+        // 旧的 is_favorite 在 v1 转为“收藏”标签
+        assert_eq!(tag_names(&conn, 2), vec!["收藏"]);
+        // 只有大小写不同的标签合并为一个
+        assert_eq!(tag_names(&conn, 3), vec!["Rust"]);
+        let tag_count: i64 = conn.query_row("SELECT COUNT(*) FROM tags", [], |row| row.get(0)).unwrap();
+        assert_eq!(tag_count, 3);
+        // 无法解析的标签跳过（记录在日志中），不影响迁移
+        assert!(tag_names(&conn, 4).is_empty());
+
+        // 固定在迁移里的颜色取法与创建时相同
+        let color: String = conn
+            .query_row("SELECT color FROM tags WHERE name = '工作'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(color, crate::storage::Database::default_tag_color("工作"));
+
+        // 全文索引的 tags 列改为从 item_tags 汇总
+        let fts_tags: String = conn
+            .query_row("SELECT tags FROM clipboard_fts WHERE rowid = 1", [], |row| row.get(0))
+            .unwrap();
+        assert!(fts_tags.contains("工作") && fts_tags.contains("Rust"));
+    }
+
+    #[test]
+    fn failing_step_rolls_back_everything() {
+        fn fail(tx: &Transaction) -> Result<()> {
+            tx.execute_batch("CREATE TABLE half_done (id INTEGER); SELECT * FROM missing_table;")
+        }
+        let failing = [
+            Migration {
+                version: 1,
+                description: "初始表结构",
+                up: initial_schema,
+            },
+            Migration {
+                version: 2,
+                description: "失败的步骤",
+                up: fail,
+            },
+        ];
+
+        let mut conn = baseline_connection();
+        assert!(apply(&mut conn, &failing).is_err());
+
+        // 版本号、第一步补的列和失败步骤建的表都已回滚
+        assert_eq!(current_version(&conn).unwrap(), 0);
+        let objects: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM sqlite_master WHERE name = 'half_done'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(objects, 0);
+        let favorite_tags: Option<String> = conn
+            .query_row("SELECT tags FROM clipboard_history WHERE id = 2", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(favorite_tags, None);
+    }
+
+    #[test]
+    fn newer_database_is_rejected() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.pragma_update(None, "user_version", latest_version(MIGRATIONS) + 1)
+            .unwrap();
+        assert!(migrate(&mut conn).is_err());
+    }
+}
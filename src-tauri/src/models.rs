@@ -1,6 +1,9 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+use crate::encryption::KeySource;
+use crate::sensitive::SensitiveReport;
+
 /// 剪贴板内容类型
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -19,6 +22,8 @@ pub enum ClipboardContentType {
     Folder,
     /// 多个文件/文件夹
     Files,
+    /// 片段（模板，粘贴时展开占位符，不参与自动清理）
+    Snippet,
 }
 
 /// 剪贴板元数据
@@ -42,9 +47,12 @@ pub struct ClipboardMetadata {
 /// 标签定义
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Tag {
-    pub id: String,
+    pub id: i64,
     pub name: String,
+    /// 颜色 (#RRGGBB)
     pub color: String,
+    /// 使用该标签的记录数
+    pub item_count: i64,
 }
 
 /// 剪贴板历史记录项
@@ -75,6 +83,66 @@ pub struct ClipboardItem {
     /// 标签列表
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tags: Option<Vec<String>>,
+    /// 是否被识别为敏感内容（到期后自动删除）
+    #[serde(default)]
+    pub is_sensitive: bool,
+    /// 过期时间（敏感记录限时保存）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+    /// 来源应用（复制时的前台窗口）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_app: Option<SourceApp>,
+}
+
+/// 剪贴板内容的来源应用
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceApp {
+    /// 进程名（如 "firefox"、"Code.exe"）
+    pub process_name: String,
+    /// 窗口标题
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub window_title: Option<String>,
+}
+
+impl SourceApp {
+    /// 进程名是否与给定名称相同（不区分大小写，忽略 Windows 的 .exe 后缀）
+    pub fn matches(&self, name: &str) -> bool {
+        normalize_process_name(&self.process_name) == normalize_process_name(name)
+    }
+}
+
+/// 规范化进程名：去掉首尾空白和 .exe 后缀并转为小写
+pub fn normalize_process_name(name: &str) -> String {
+    let name = name.trim().to_lowercase();
+    match name.strip_suffix(".exe") {
+        Some(stem) => stem.to_string(),
+        None => name,
+    }
+}
+
+/// 窗口信息（粘贴前按目标窗口匹配粘贴按键规则）
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WindowInfo {
+    /// 进程名（可执行文件名）
+    pub process_name: Option<String>,
+    /// 窗口类名（X11 为 WM_CLASS 的类名部分，Windows 为窗口类）
+    pub window_class: Option<String>,
+}
+
+/// 写入剪贴板记录的结果
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CaptureOutcome {
+    /// 写入的记录（被忽略或因敏感内容被丢弃时为 null）
+    pub item: Option<ClipboardItem>,
+    /// 命中敏感内容时采取的处理
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sensitive: Option<SensitiveReport>,
+}
+
+impl From<Option<ClipboardItem>> for CaptureOutcome {
+    fn from(item: Option<ClipboardItem>) -> Self {
+        Self { item, sensitive: None }
+    }
 }
 
 /// 创建剪贴板项的请求
@@ -95,15 +163,43 @@ pub struct SearchRequest {
 #[derive(Debug, Deserialize)]
 pub struct AdvancedSearchRequest {
     /// 关键词列表（普通文本搜索词）
+    #[serde(default)]
     pub keywords: Vec<String>,
     /// 标签过滤（@标签）
+    #[serde(default)]
     pub tags: Vec<String>,
     /// 类型过滤（@类型）
+    #[serde(default)]
     pub types: Vec<ClipboardContentType>,
     /// 返回数量限制
     pub limit: Option<i64>,
     /// 分页偏移量
     pub offset: Option<i64>,
+    /// 来源应用过滤（进程名，多个之间是 OR 关系）
+    #[serde(default)]
+    pub source_apps: Vec<String>,
+}
+
+/// 匹配高亮区间（UTF-16 偏移，相对于 snippet）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MatchRange {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// 高级搜索结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchResult {
+    /// 命中的记录
+    pub item: ClipboardItem,
+    /// bm25 相关度分数（越小越相关，仅关键词走全文索引时有值）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub score: Option<f64>,
+    /// 匹配片段
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snippet: Option<String>,
+    /// 片段中的高亮区间
+    pub highlights: Vec<MatchRange>,
 }
 
 /// 获取历史记录的请求
@@ -122,6 +218,155 @@ pub struct ClearHistoryRequest {
     pub keep_days: Option<i64>,
 }
 
+/// 图片存储垃圾回收报告
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StorageGcReport {
+    /// 扫描的文件数
+    pub files_scanned: usize,
+    /// 删除的未引用文件数
+    pub files_deleted: usize,
+    /// 回收的字节数
+    pub bytes_reclaimed: u64,
+    /// 重新生成了缩略图的记录数
+    pub rows_repaired: usize,
+    /// 原图文件已丢失、无法修复的记录 ID
+    pub broken_item_ids: Vec<i64>,
+}
+
+/// 归档导出请求（各过滤条件之间是 AND 关系，均为空时导出全部记录）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExportRequest {
+    /// 标签过滤（带有任一标签的记录）
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// 类型过滤
+    #[serde(default)]
+    pub types: Vec<ClipboardContentType>,
+    /// 创建时间下限（含）
+    #[serde(default)]
+    pub from: Option<DateTime<Utc>>,
+    /// 创建时间上限（不含）
+    #[serde(default)]
+    pub to: Option<DateTime<Utc>>,
+    /// 是否导出敏感记录（默认不导出，归档不加密）
+    #[serde(default)]
+    pub include_sensitive: bool,
+}
+
+/// 归档导出结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExportReport {
+    /// 导出的记录数
+    pub items: usize,
+    /// 写入的图片文件数（相同图片只写一份）
+    pub images: usize,
+    /// 原图已丢失而跳过的图片记录数
+    pub missing_images: usize,
+}
+
+/// 导入结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ImportReport {
+    /// 导入的记录数（与已有记录重复的会合并）
+    pub imported: usize,
+    /// 恢复到图片存储的图片数
+    pub images: usize,
+    /// 无法解析或图片缺失而跳过的记录数
+    pub skipped: usize,
+}
+
+/// 可导入的其他剪贴板管理器数据
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportSource {
+    /// Ditto 的数据库（Ditto.db）
+    Ditto,
+    /// CopyQ 导出的标签页（.cpq）
+    Copyq,
+    /// Maccy 的数据库（Storage.sqlite）
+    Maccy,
+    /// 纯文本片段：目录下每个文件一条，或单个文件内以 `---` 行分隔
+    Snippets,
+}
+
+impl ImportSource {
+    /// 显示名称
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Ditto => "Ditto",
+            Self::Copyq => "CopyQ",
+            Self::Maccy => "Maccy",
+            Self::Snippets => "纯文本片段",
+        }
+    }
+}
+
+/// 从其他剪贴板管理器导入的结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExternalImportReport {
+    pub source: ImportSource,
+    /// 导入的记录数（与已有记录重复的会合并）
+    pub imported: usize,
+    /// 有意不导入的条目数（分组、空内容）
+    pub skipped: usize,
+    /// 格式都不支持而未导入的条目数
+    pub unsupported: usize,
+    /// 不支持的格式说明（去重，如 `不支持的格式：CF_ENHMETAFILE`）
+    pub unsupported_reasons: Vec<String>,
+    /// 数据损坏或写入失败的条目数
+    pub failed: usize,
+    /// 写入图片存储的图片数
+    pub images: usize,
+}
+
+impl ExternalImportReport {
+    pub fn new(source: ImportSource) -> Self {
+        Self {
+            source,
+            imported: 0,
+            skipped: 0,
+            unsupported: 0,
+            unsupported_reasons: Vec::new(),
+            failed: 0,
+            images: 0,
+        }
+    }
+}
+
+/// 粘贴队列项之间输入的分隔符
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QueueSeparator {
+    /// 不输入分隔符
+    #[default]
+    None,
+    /// 回车换行
+    Newline,
+    /// Tab（常用于逐格填写表格）
+    Tab,
+}
+
+/// 粘贴队列状态（`paste-queue-changed` 事件的内容）
+#[derive(Debug, Clone, Serialize)]
+pub struct PasteQueueState {
+    /// 按粘贴顺序排列的记录
+    pub items: Vec<ClipboardItem>,
+    pub separator: QueueSeparator,
+}
+
+/// 静态加密状态
+#[derive(Debug, Clone, Serialize)]
+pub struct EncryptionStatus {
+    /// 是否已启用加密
+    pub enabled: bool,
+    /// 密钥来源（未启用时为 null）
+    pub key_source: Option<KeySource>,
+    /// 密钥文件路径（密钥文件模式）
+    pub key_file: Option<String>,
+    /// 数据库是否等待解锁
+    pub locked: bool,
+}
+
 /// 应用设置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppSettings {
@@ -130,6 +375,8 @@ pub struct AppSettings {
     pub max_history_count: i64,
     /// 自动清理周期 (天, 0 表示不自动清理)
     pub auto_cleanup_days: i64,
+    /// 图片存储垃圾回收周期 (小时, 0 表示不定期回收)
+    pub image_gc_interval_hours: i64,
 
     // 窗口设置
     /// 窗口位置 (remember/center/cursor)
@@ -138,6 +385,10 @@ pub struct AppSettings {
     pub window_pos_x: Option<i32>,
     /// 窗口位置 Y 坐标 (remember 模式使用)
     pub window_pos_y: Option<i32>,
+    /// 窗口宽度（逻辑像素，None 表示默认尺寸）
+    pub window_width: Option<u32>,
+    /// 窗口高度（逻辑像素，None 表示默认尺寸）
+    pub window_height: Option<u32>,
 
     // 音效设置
     /// 复制音效
@@ -154,8 +405,16 @@ pub struct AppSettings {
     pub click_action: String,
     /// 双击动作 (copy/paste/none)
     pub double_click_action: String,
-    /// 粘贴快捷键 (ctrl_v/shift_insert)
+    /// 粘贴快捷键 (ctrl_v/shift_insert/ctrl_shift_v/typing)
     pub paste_shortcut: String,
+    /// 粘贴后恢复原剪贴板内容
+    pub restore_clipboard_after_paste: bool,
+    /// 粘贴后等待多久再恢复剪贴板 (毫秒，目标程序需要在此之前读完剪贴板)
+    pub restore_clipboard_delay_ms: u64,
+    /// 模拟输入时每个字符之间的间隔 (毫秒，0 表示整段输入)
+    pub typing_char_delay_ms: u64,
+    /// 模拟输入时换行使用的按键 (enter/shift_enter)
+    pub typing_newline: String,
     /// 复制后隐藏窗口
     pub hide_window_after_copy: bool,
     /// 图片OCR
@@ -182,19 +441,41 @@ pub struct AppSettings {
     // 钉住模式设置
     /// 钉住模式快捷键（默认 "Ctrl+Shift+P"）
     pub pin_shortcut: String,
+
+    // 粘贴队列设置
+    /// 粘贴队列下一项的快捷键（默认 "Alt+Shift+V"，为空表示不启用）
+    pub paste_next_shortcut: String,
+
+    // 敏感内容设置
+    /// 命中敏感内容时的处理 (off/drop/expire/mask)
+    pub sensitive_action: String,
+    /// 敏感记录保留的分钟数 (expire 模式)
+    pub sensitive_expire_minutes: i64,
+    /// 用户自定义的敏感内容正则（每项一个）
+    pub sensitive_patterns: Vec<String>,
+
+    // 来源应用设置
+    /// 不记录这些应用复制的内容（进程名，不区分大小写）
+    pub ignored_apps: Vec<String>,
 }
 
+/// 默认忽略的应用（常见密码管理器）
+pub const DEFAULT_IGNORED_APPS: &[&str] = &["keepassxc", "keepass", "1password", "bitwarden", "enpass"];
+
 impl Default for AppSettings {
     fn default() -> Self {
         Self {
             // 历史记录
             max_history_count: 5000,
             auto_cleanup_days: 30,
+            image_gc_interval_hours: 24,
 
             // 窗口
             window_position: "remember".to_string(),
             window_pos_x: None,
             window_pos_y: None,
+            window_width: None,
+            window_height: None,
 
             // 音效
             copy_sound: false,
@@ -207,6 +488,10 @@ impl Default for AppSettings {
             click_action: "copy".to_string(),
             double_click_action: "paste".to_string(),
             paste_shortcut: "ctrl_v".to_string(),
+            restore_clipboard_after_paste: false,
+            restore_clipboard_delay_ms: 300,
+            typing_char_delay_ms: 10,
+            typing_newline: "enter".to_string(),
             hide_window_after_copy: false,
             image_ocr: false,
             copy_as_plain_text: false,
@@ -223,6 +508,17 @@ impl Default for AppSettings {
 
             // 钉住模式设置
             pin_shortcut: "Ctrl+Shift+P".to_string(),
+
+            // 粘贴队列设置
+            paste_next_shortcut: "Alt+Shift+V".to_string(),
+
+            // 敏感内容设置
+            sensitive_action: "expire".to_string(),
+            sensitive_expire_minutes: 5,
+            sensitive_patterns: Vec::new(),
+
+            // 来源应用设置
+            ignored_apps: DEFAULT_IGNORED_APPS.iter().map(|s| s.to_string()).collect(),
         }
     }
 }
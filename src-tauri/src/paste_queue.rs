@@ -0,0 +1,229 @@
+//! 粘贴队列
+//! 按顺序保存待粘贴的记录 ID，"粘贴下一项" 每次粘贴队首记录后前进一项。
+//! 队列保存在后端，剪贴板窗口关闭后依然保留。
+
+use std::time::{Duration, Instant};
+use tokio::sync::{Mutex, MutexGuard};
+
+use crate::models::QueueSeparator;
+
+/// 连续粘贴的最小间隔（毫秒）
+const PASTE_INTERVAL_MS: u64 = 100;
+
+#[derive(Default)]
+struct QueueInner {
+    item_ids: Vec<i64>,
+    separator: QueueSeparator,
+    /// 本轮已粘贴的数量（大于 0 时，下一项粘贴前先输入分隔符）
+    pasted_count: usize,
+}
+
+/// 粘贴队列管理器（同时串行化处理快速连续粘贴）
+pub struct PasteQueue {
+    inner: Mutex<QueueInner>,
+    last_paste_time: Mutex<Instant>,
+    /// "粘贴下一项" 从取队首到前进一项期间持有，避免连按时同一项被粘贴两次
+    paste: Mutex<()>,
+}
+
+impl Default for PasteQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PasteQueue {
+    pub fn new() -> Self {
+        Self {
+            inner: Mutex::new(QueueInner::default()),
+            last_paste_time: Mutex::new(Instant::now() - Duration::from_secs(1)),
+            paste: Mutex::new(()),
+        }
+    }
+
+    /// 开始一次 "粘贴下一项"，返回的锁持有到 `advance` 之后
+    ///
+    /// 一次粘贴包括等待焦点和粘贴规则的延迟，耗时可能远超粘贴间隔，
+    /// 连按快捷键时后一次要等前一次前进之后再取队首。
+    pub async fn lock_paste(&self) -> MutexGuard<'_, ()> {
+        self.paste.lock().await
+    }
+
+    /// 等待合适的粘贴间隔（串行化处理 <100ms 的连续粘贴）
+    pub async fn wait_for_paste_interval(&self) {
+        let mut last_time = self.last_paste_time.lock().await;
+        let elapsed = last_time.elapsed().as_millis() as u64;
+
+        // 如果距离上次粘贴 <100ms，等待剩余时间
+        if elapsed < PASTE_INTERVAL_MS {
+            tokio::time::sleep(Duration::from_millis(PASTE_INTERVAL_MS - elapsed)).await;
+        }
+
+        // 更新上次粘贴时间
+        *last_time = Instant::now();
+    }
+
+    /// 队列中的记录 ID（按粘贴顺序）
+    pub async fn item_ids(&self) -> Vec<i64> {
+        self.inner.lock().await.item_ids.clone()
+    }
+
+    pub async fn separator(&self) -> QueueSeparator {
+        self.inner.lock().await.separator
+    }
+
+    pub async fn set_separator(&self, separator: QueueSeparator) {
+        self.inner.lock().await.separator = separator;
+    }
+
+    /// 下一项要粘贴的记录 ID
+    pub async fn peek(&self) -> Option<i64> {
+        self.inner.lock().await.item_ids.first().copied()
+    }
+
+    /// 追加到队尾（已在队列中的记录忽略）
+    pub async fn push(&self, ids: &[i64]) {
+        let mut inner = self.inner.lock().await;
+        if inner.item_ids.is_empty() {
+            inner.pasted_count = 0;
+        }
+        for &id in ids {
+            if !inner.item_ids.contains(&id) {
+                inner.item_ids.push(id);
+            }
+        }
+    }
+
+    /// 从队列中移除
+    pub async fn remove(&self, id: i64) {
+        self.inner.lock().await.item_ids.retain(|&item_id| item_id != id);
+    }
+
+    /// 调整顺序（ids 必须与当前队列包含相同的记录）
+    pub async fn reorder(&self, ids: Vec<i64>) -> Result<(), String> {
+        let mut inner = self.inner.lock().await;
+
+        let mut current = inner.item_ids.clone();
+        let mut requested = ids.clone();
+        current.sort_unstable();
+        requested.sort_unstable();
+        if current != requested {
+            return Err("队列已变化，请刷新后重试".to_string());
+        }
+
+        inner.item_ids = ids;
+        Ok(())
+    }
+
+    pub async fn clear(&self) {
+        let mut inner = self.inner.lock().await;
+        inner.item_ids.clear();
+        inner.pasted_count = 0;
+    }
+
+    /// 粘贴下一项之前需要输入的分隔符（本轮第一项不需要）
+    pub async fn pending_separator(&self) -> Option<QueueSeparator> {
+        let inner = self.inner.lock().await;
+        (inner.pasted_count > 0 && inner.separator != QueueSeparator::None).then_some(inner.separator)
+    }
+
+    /// 队首记录粘贴完成后前进一项
+    ///
+    /// 粘贴过程中队列可能被修改，只有队首仍是 id 时才移除。
+    pub async fn advance(&self, id: i64) {
+        let mut inner = self.inner.lock().await;
+        if inner.item_ids.first() == Some(&id) {
+            inner.item_ids.remove(0);
+            inner.pasted_count += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run<F: std::future::Future>(future: F) -> F::Output {
+        tauri::async_runtime::block_on(future)
+    }
+
+    #[test]
+    fn push_ignores_duplicates() {
+        run(async {
+            let queue = PasteQueue::new();
+            queue.push(&[1, 2]).await;
+            queue.push(&[2, 3, 1, 3]).await;
+            assert_eq!(queue.item_ids().await, vec![1, 2, 3]);
+            assert_eq!(queue.peek().await, Some(1));
+        });
+    }
+
+    #[test]
+    fn reorder_requires_the_same_items() {
+        run(async {
+            let queue = PasteQueue::new();
+            queue.push(&[1, 2, 3]).await;
+            assert!(queue.reorder(vec![3, 1]).await.is_err());
+            assert!(queue.reorder(vec![3, 1, 2, 4]).await.is_err());
+            assert!(queue.reorder(vec![3, 1, 1]).await.is_err());
+            assert_eq!(queue.item_ids().await, vec![1, 2, 3]);
+
+            queue.reorder(vec![3, 1, 2]).await.unwrap();
+            assert_eq!(queue.item_ids().await, vec![3, 1, 2]);
+        });
+    }
+
+    #[test]
+    fn advance_only_removes_the_head() {
+        run(async {
+            let queue = PasteQueue::new();
+            queue.push(&[1, 2, 3]).await;
+            // 粘贴期间队首被移除或调整，不能误删其他记录
+            queue.advance(2).await;
+            assert_eq!(queue.item_ids().await, vec![1, 2, 3]);
+
+            queue.advance(1).await;
+            assert_eq!(queue.item_ids().await, vec![2, 3]);
+            queue.remove(3).await;
+            assert_eq!(queue.item_ids().await, vec![2]);
+        });
+    }
+
+    #[test]
+    fn separator_is_typed_between_pastes() {
+        run(async {
+            let queue = PasteQueue::new();
+            queue.set_separator(QueueSeparator::Tab).await;
+            queue.push(&[1, 2]).await;
+            assert_eq!(queue.pending_separator().await, None);
+
+            queue.advance(1).await;
+            assert_eq!(queue.pending_separator().await, Some(QueueSeparator::Tab));
+            queue.set_separator(QueueSeparator::None).await;
+            assert_eq!(queue.pending_separator().await, None);
+            queue.set_separator(QueueSeparator::Newline).await;
+
+            // 队列清空后重新开始一轮，第一项之前不输入分隔符
+            queue.advance(2).await;
+            queue.push(&[3]).await;
+            assert_eq!(queue.pending_separator().await, None);
+            queue.advance(3).await;
+            queue.clear().await;
+            queue.push(&[4]).await;
+            assert_eq!(queue.pending_separator().await, None);
+        });
+    }
+
+    #[test]
+    fn pastes_are_serialized() {
+        run(async {
+            let queue = PasteQueue::new();
+            let first = queue.lock_paste().await;
+            let second = tokio::time::timeout(Duration::from_millis(50), queue.lock_paste()).await;
+            assert!(second.is_err(), "前一次粘贴完成前不能开始下一次");
+            drop(first);
+            let second = tokio::time::timeout(Duration::from_millis(50), queue.lock_paste()).await;
+            assert!(second.is_ok());
+        });
+    }
+}
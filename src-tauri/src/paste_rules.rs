@@ -0,0 +1,323 @@
+//! 按目标程序选择粘贴按键
+//! 粘贴前根据目标窗口的进程名或窗口类名匹配规则表（按顺序，第一条命中的规则生效），
+//! 决定发送的按键组合以及粘贴前后的等待时间；没有规则命中时使用全局的粘贴快捷键设置。
+
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::models::WindowInfo;
+
+/// 粘贴前后等待时间的上限（毫秒）
+pub const MAX_DELAY_MS: u64 = 2000;
+
+/// 粘贴按键组合
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PasteKeystroke {
+    CtrlV,
+    ShiftInsert,
+    /// 终端常用的 Ctrl+Shift+V
+    CtrlShiftV,
+    /// 不发送粘贴按键，模拟键盘逐字输入剪贴板中的文本（用于屏蔽粘贴的程序）
+    Typing,
+}
+
+impl PasteKeystroke {
+    /// 解析设置/数据库中的值（未知值按 Ctrl+V 处理）
+    pub fn from_setting(value: &str) -> Self {
+        match value {
+            "shift_insert" => Self::ShiftInsert,
+            "ctrl_shift_v" => Self::CtrlShiftV,
+            "typing" => Self::Typing,
+            _ => Self::CtrlV,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::CtrlV => "ctrl_v",
+            Self::ShiftInsert => "shift_insert",
+            Self::CtrlShiftV => "ctrl_shift_v",
+            Self::Typing => "typing",
+        }
+    }
+}
+
+/// 规则匹配的字段
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PasteRuleField {
+    /// 进程名（可执行文件名）
+    Process,
+    /// 窗口类名（X11 的 WM_CLASS，Windows 的窗口类）
+    WindowClass,
+}
+
+impl PasteRuleField {
+    /// 解析数据库中的值（未知值按进程名处理）
+    pub fn from_setting(value: &str) -> Self {
+        match value {
+            "window_class" => Self::WindowClass,
+            _ => Self::Process,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Process => "process",
+            Self::WindowClass => "window_class",
+        }
+    }
+}
+
+/// 粘贴按键规则
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PasteRule {
+    pub id: i64,
+    pub field: PasteRuleField,
+    /// 正则表达式（不区分大小写，匹配字段中的任意位置，需要整体匹配时用 ^...$）
+    pub pattern: String,
+    pub keystroke: PasteKeystroke,
+    /// 粘贴前等待（毫秒）
+    pub pre_delay_ms: u64,
+    /// 粘贴后等待（毫秒）
+    pub post_delay_ms: u64,
+    pub enabled: bool,
+    /// 排列顺序（越小越先匹配）
+    pub sort_order: i64,
+}
+
+/// 新建/修改规则时提交的内容
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PasteRuleInput {
+    pub field: PasteRuleField,
+    pub pattern: String,
+    pub keystroke: PasteKeystroke,
+    #[serde(default)]
+    pub pre_delay_ms: u64,
+    #[serde(default)]
+    pub post_delay_ms: u64,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl PasteRuleInput {
+    /// 检查正则和等待时间，返回去掉首尾空白后的内容
+    pub fn validate(self) -> Result<Self, String> {
+        let pattern = self.pattern.trim().to_string();
+        if pattern.is_empty() {
+            return Err("匹配规则不能为空".to_string());
+        }
+        build_regex(&pattern).map_err(|e| format!("无效的正则表达式 \"{}\": {}", pattern, e))?;
+        if self.pre_delay_ms > MAX_DELAY_MS || self.post_delay_ms > MAX_DELAY_MS {
+            return Err(format!("等待时间不能超过 {} 毫秒", MAX_DELAY_MS));
+        }
+        Ok(Self { pattern, ..self })
+    }
+}
+
+/// 一次粘贴使用的按键和等待时间
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PastePlan {
+    pub keystroke: PasteKeystroke,
+    pub pre_delay: Duration,
+    pub post_delay: Duration,
+}
+
+impl PastePlan {
+    /// 不匹配任何规则时的默认方案
+    pub fn new(keystroke: PasteKeystroke) -> Self {
+        Self {
+            keystroke,
+            pre_delay: Duration::ZERO,
+            post_delay: Duration::ZERO,
+        }
+    }
+}
+
+fn build_regex(pattern: &str) -> Result<Regex, regex::Error> {
+    RegexBuilder::new(pattern).case_insensitive(true).build()
+}
+
+/// 预编译的规则表
+///
+/// 读取规则时编译一次正则，之后每次粘贴直接匹配；已停用和正则无效的规则在编译时就被剔除。
+#[derive(Debug, Default)]
+pub struct CompiledRules {
+    rules: Vec<(PasteRule, Regex)>,
+}
+
+impl CompiledRules {
+    /// 编译规则（规则需已按 sort_order 排序）
+    pub fn new(rules: Vec<PasteRule>) -> Self {
+        let rules = rules
+            .into_iter()
+            .filter(|rule| rule.enabled)
+            .filter_map(|rule| match build_regex(&rule.pattern) {
+                Ok(regex) => Some((rule, regex)),
+                Err(e) => {
+                    eprintln!("粘贴规则 {} 的正则无效，已跳过: {}", rule.id, e);
+                    None
+                }
+            })
+            .collect();
+        Self { rules }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// 找到第一条匹配目标窗口的规则
+    pub fn find_rule(&self, target: &WindowInfo) -> Option<&PasteRule> {
+        self.rules
+            .iter()
+            .find(|(rule, regex)| {
+                let value = match rule.field {
+                    PasteRuleField::Process => target.process_name.as_deref(),
+                    PasteRuleField::WindowClass => target.window_class.as_deref(),
+                };
+                value.is_some_and(|value| regex.is_match(value))
+            })
+            .map(|(rule, _)| rule)
+    }
+
+    /// 根据目标窗口决定粘贴方案（目标未知或没有规则命中时使用默认按键）
+    pub fn resolve(&self, target: Option<&WindowInfo>, default: PasteKeystroke) -> PastePlan {
+        match target.and_then(|target| self.find_rule(target)) {
+            Some(rule) => PastePlan {
+                keystroke: rule.keystroke,
+                pre_delay: Duration::from_millis(rule.pre_delay_ms.min(MAX_DELAY_MS)),
+                post_delay: Duration::from_millis(rule.post_delay_ms.min(MAX_DELAY_MS)),
+            },
+            None => PastePlan::new(default),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(id: i64, field: PasteRuleField, pattern: &str, keystroke: PasteKeystroke, enabled: bool) -> PasteRule {
+        PasteRule {
+            id,
+            field,
+            pattern: pattern.to_string(),
+            keystroke,
+            pre_delay_ms: 0,
+            post_delay_ms: 0,
+            enabled,
+            sort_order: id,
+        }
+    }
+
+    fn input(pattern: &str, pre_delay_ms: u64, post_delay_ms: u64) -> PasteRuleInput {
+        PasteRuleInput {
+            field: PasteRuleField::Process,
+            pattern: pattern.to_string(),
+            keystroke: PasteKeystroke::ShiftInsert,
+            pre_delay_ms,
+            post_delay_ms,
+            enabled: true,
+        }
+    }
+
+    fn window(process_name: Option<&str>, window_class: Option<&str>) -> WindowInfo {
+        WindowInfo {
+            process_name: process_name.map(str::to_string),
+            window_class: window_class.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn first_enabled_match_wins() {
+        let rules = CompiledRules::new(vec![
+            rule(1, PasteRuleField::Process, "term", PasteKeystroke::Typing, false),
+            rule(2, PasteRuleField::Process, "(", PasteKeystroke::CtrlV, true),
+            rule(3, PasteRuleField::Process, "^xterm$", PasteKeystroke::ShiftInsert, true),
+            rule(4, PasteRuleField::Process, "term", PasteKeystroke::CtrlShiftV, true),
+        ]);
+
+        let target = window(Some("XTerm"), None);
+        assert_eq!(rules.find_rule(&target).map(|rule| rule.id), Some(3));
+        let target = window(Some("gnome-terminal-server"), None);
+        assert_eq!(rules.find_rule(&target).map(|rule| rule.id), Some(4));
+        assert!(rules.find_rule(&window(Some("firefox"), None)).is_none());
+    }
+
+    #[test]
+    fn disabled_and_invalid_rules_are_dropped() {
+        let rules = CompiledRules::new(vec![
+            rule(1, PasteRuleField::Process, "a", PasteKeystroke::Typing, false),
+            rule(2, PasteRuleField::Process, "[", PasteKeystroke::Typing, true),
+        ]);
+        assert!(rules.is_empty());
+        assert!(CompiledRules::default().is_empty());
+    }
+
+    #[test]
+    fn window_class_rules_need_a_class() {
+        let rules = CompiledRules::new(vec![rule(1, PasteRuleField::WindowClass, "kitty", PasteKeystroke::CtrlShiftV, true)]);
+
+        assert!(rules.find_rule(&window(Some("kitty"), None)).is_none());
+        assert_eq!(rules.find_rule(&window(None, Some("KITTY"))).map(|rule| rule.id), Some(1));
+    }
+
+    #[test]
+    fn resolve_clamps_delays_and_falls_back() {
+        let mut slow = rule(1, PasteRuleField::Process, "slow", PasteKeystroke::ShiftInsert, true);
+        slow.pre_delay_ms = 150;
+        slow.post_delay_ms = MAX_DELAY_MS + 5000;
+        let rules = CompiledRules::new(vec![slow]);
+
+        assert_eq!(
+            rules.resolve(Some(&window(Some("slow.exe"), None)), PasteKeystroke::CtrlV),
+            PastePlan {
+                keystroke: PasteKeystroke::ShiftInsert,
+                pre_delay: Duration::from_millis(150),
+                post_delay: Duration::from_millis(MAX_DELAY_MS),
+            }
+        );
+        assert_eq!(
+            rules.resolve(Some(&window(Some("fast"), None)), PasteKeystroke::CtrlShiftV),
+            PastePlan::new(PasteKeystroke::CtrlShiftV)
+        );
+        assert_eq!(rules.resolve(None, PasteKeystroke::CtrlV), PastePlan::new(PasteKeystroke::CtrlV));
+    }
+
+    #[test]
+    fn validate_trims_and_checks_input() {
+        let valid = input("  ^code$ ", 0, MAX_DELAY_MS).validate().unwrap();
+        assert_eq!(valid.pattern, "^code$");
+        assert_eq!(valid.post_delay_ms, MAX_DELAY_MS);
+
+        assert_eq!(input("   ", 0, 0).validate().unwrap_err(), "匹配规则不能为空");
+        assert!(input("(unclosed", 0, 0).validate().unwrap_err().starts_with("无效的正则表达式 \"(unclosed\""));
+        assert_eq!(
+            input("code", MAX_DELAY_MS + 1, 0).validate().unwrap_err(),
+            format!("等待时间不能超过 {} 毫秒", MAX_DELAY_MS)
+        );
+        assert!(input("code", 0, MAX_DELAY_MS + 1).validate().is_err());
+    }
+
+    #[test]
+    fn settings_round_trip() {
+        for keystroke in [
+            PasteKeystroke::CtrlV,
+            PasteKeystroke::ShiftInsert,
+            PasteKeystroke::CtrlShiftV,
+            PasteKeystroke::Typing,
+        ] {
+            assert_eq!(PasteKeystroke::from_setting(keystroke.as_str()), keystroke);
+        }
+        assert_eq!(PasteKeystroke::from_setting("unknown"), PasteKeystroke::CtrlV);
+        assert_eq!(PasteRuleField::from_setting("window_class"), PasteRuleField::WindowClass);
+        assert_eq!(PasteRuleField::from_setting("other"), PasteRuleField::Process);
+    }
+}
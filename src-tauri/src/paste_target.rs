@@ -0,0 +1,107 @@
+//! 粘贴目标窗口
+//!
+//! 粘贴前切换回的窗口：打开剪贴板窗口前记录当时的前台窗口；不经过剪贴板窗口直接粘贴
+//! （粘贴队列快捷键、IPC、启动参数）时，粘贴目标是此刻的前台窗口。
+
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::platform::ForegroundWindowProvider;
+
+pub struct PasteTarget {
+    provider: Arc<dyn ForegroundWindowProvider>,
+    window: Mutex<Option<u64>>,
+}
+
+impl PasteTarget {
+    pub fn new(provider: Arc<dyn ForegroundWindowProvider>) -> Self {
+        Self {
+            provider,
+            window: Mutex::new(None),
+        }
+    }
+
+    pub async fn window(&self) -> Option<u64> {
+        *self.window.lock().await
+    }
+
+    /// 记录当前前台窗口（查询失败时清空，粘贴到当前焦点）
+    pub async fn remember_foreground(&self) {
+        let window = self.provider.foreground_window().unwrap_or_else(|e| {
+            eprintln!("获取前台窗口失败: {}", e);
+            None
+        });
+        *self.window.lock().await = window;
+    }
+
+    /// 剪贴板窗口未显示时记录当前前台窗口；显示时保留打开前记录的窗口
+    /// （此刻的前台窗口就是剪贴板窗口本身）
+    pub async fn remember_if_hidden(&self, clipboard_visible: bool) {
+        if !clipboard_visible {
+            self.remember_foreground().await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex as StdMutex;
+
+    // 返回可随时修改的前台窗口
+    struct FakeForeground(StdMutex<Result<Option<u64>, String>>);
+
+    impl FakeForeground {
+        fn set(&self, window: Result<Option<u64>, String>) {
+            *self.0.lock().unwrap() = window;
+        }
+    }
+
+    impl ForegroundWindowProvider for FakeForeground {
+        fn foreground_window(&self) -> Result<Option<u64>, String> {
+            self.0.lock().unwrap().clone()
+        }
+    }
+
+    fn target() -> (Arc<FakeForeground>, PasteTarget) {
+        let provider = Arc::new(FakeForeground(StdMutex::new(Ok(Some(1)))));
+        (provider.clone(), PasteTarget::new(provider))
+    }
+
+    #[test]
+    fn hidden_window_follows_foreground_switch() {
+        let (provider, target) = target();
+        tauri::async_runtime::block_on(async {
+            target.remember_if_hidden(false).await;
+            assert_eq!(target.window().await, Some(1));
+
+            // 用户切换到另一个应用后按“粘贴下一条”
+            provider.set(Ok(Some(2)));
+            target.remember_if_hidden(false).await;
+            assert_eq!(target.window().await, Some(2));
+        });
+    }
+
+    #[test]
+    fn visible_window_keeps_target() {
+        let (provider, target) = target();
+        tauri::async_runtime::block_on(async {
+            target.remember_foreground().await;
+            // 剪贴板窗口获得焦点后前台窗口是它自己，不能覆盖粘贴目标
+            provider.set(Ok(Some(99)));
+            target.remember_if_hidden(true).await;
+            assert_eq!(target.window().await, Some(1));
+        });
+    }
+
+    #[test]
+    fn query_failure_clears_target() {
+        let (provider, target) = target();
+        tauri::async_runtime::block_on(async {
+            target.remember_foreground().await;
+            provider.set(Err("no display".to_string()));
+            target.remember_if_hidden(false).await;
+            assert_eq!(target.window().await, None);
+        });
+    }
+}
@@ -1,11 +1,249 @@
 //! Linux 平台特定实现
 
-use tauri::WebviewWindow;
+use std::ffi::OsString;
+use x11rb::connection::Connection;
+use x11rb::protocol::xproto::{
+    Atom, AtomEnum, ClientMessageEvent, ConnectionExt, EventMask, GetPropertyReply, Window,
+};
+use x11rb::rust_connection::RustConnection;
 
-/// 获取鼠标光标位置（Linux 实现）
-/// 注意：当前为简化实现，实际需要 X11 支持
+use crate::models::{SourceApp, WindowInfo};
+
+/// 等待剪贴板被其他程序接管时的轮询间隔
+const CLIPBOARD_OWNER_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(200);
+
+/// 连接 X 服务器，返回连接和默认屏幕的根窗口
+///
+/// 纯 Wayland 会话（没有 DISPLAY）时报错；XWayland 下可以正常连接。
+fn connect() -> Result<(RustConnection, Window), String> {
+    connect_to(std::env::var_os("DISPLAY"))
+}
+
+/// 连接指定的 X 显示（DISPLAY 的值，None 表示没有 DISPLAY）
+fn connect_to(display: Option<OsString>) -> Result<(RustConnection, Window), String> {
+    let Some(display) = display else {
+        return Err("未检测到 X11 显示服务（纯 Wayland 会话）".to_string());
+    };
+    let (conn, screen_num) =
+        x11rb::connect(Some(&display.to_string_lossy())).map_err(|e| format!("连接 X 服务器失败: {}", e))?;
+    let root = conn.setup().roots[screen_num].root;
+    Ok((conn, root))
+}
+
+/// 获取鼠标光标位置（Linux 实现，X11 XQueryPointer）
+///
+/// 返回根窗口坐标（物理像素）。纯 Wayland 会话无法查询全局光标位置，返回错误，
+/// 由调用方回退到居中显示；XWayland 下只有光标经过 X 窗口时位置才会更新。
 pub fn get_cursor_position() -> Result<(f64, f64), String> {
-    // Linux: 可以尝试读取 X11 或使用 xdotool
-    // 实际实现需要链接 X11 库
-    Ok((400.0, 300.0))
+    cursor_position(std::env::var_os("DISPLAY"))
+}
+
+fn cursor_position(display: Option<OsString>) -> Result<(f64, f64), String> {
+    let (conn, root) = connect_to(display)?;
+    let pointer = conn
+        .query_pointer(root)
+        .map_err(|e| e.to_string())?
+        .reply()
+        .map_err(|e| format!("查询光标位置失败: {}", e))?;
+    if !pointer.same_screen {
+        return Err("光标不在默认屏幕上".to_string());
+    }
+    Ok((pointer.root_x as f64, pointer.root_y as f64))
+}
+
+/// 获取前台应用（X11 实现）
+///
+/// 通过根窗口的 `_NET_ACTIVE_WINDOW` 找到活动窗口，进程名优先取 `_NET_WM_PID`
+/// 对应的可执行文件名，取不到时退回 `WM_CLASS`。没有 X 服务器（纯 Wayland 会话）时返回 None。
+pub fn get_foreground_app() -> Result<Option<SourceApp>, String> {
+    if std::env::var_os("DISPLAY").is_none() {
+        return Ok(None);
+    }
+    let (conn, root) = connect()?;
+
+    let Some(window) = active_window(&conn, root)? else {
+        return Ok(None);
+    };
+
+    let process_name = match window_pid(&conn, window)? {
+        Some(pid) => process_name(pid),
+        None => None,
+    };
+    let Some(process_name) = process_name.or(wm_class(&conn, window)?.0) else {
+        return Ok(None);
+    };
+
+    Ok(Some(SourceApp {
+        process_name,
+        window_title: window_title(&conn, window)?,
+    }))
+}
+
+/// 获取当前活动窗口（X11 实现，读取根窗口的 `_NET_ACTIVE_WINDOW`）
+///
+/// 没有 X 服务器（纯 Wayland 会话）时返回 None。
+pub fn get_foreground_window() -> Result<Option<u64>, String> {
+    if std::env::var_os("DISPLAY").is_none() {
+        return Ok(None);
+    }
+    let (conn, root) = connect()?;
+    Ok(active_window(&conn, root)?.map(u64::from))
+}
+
+/// 获取窗口的进程名和窗口类名（X11 实现）
+pub fn get_window_info(window: u64) -> Result<WindowInfo, String> {
+    let window = Window::try_from(window).map_err(|_| format!("无效的 X11 窗口: {}", window))?;
+    let (conn, _) = connect()?;
+    let process_name = window_pid(&conn, window)?.and_then(process_name);
+    let (_, window_class) = wm_class(&conn, window)?;
+    Ok(WindowInfo {
+        process_name,
+        window_class,
+    })
+}
+
+/// 激活窗口（X11 实现）
+///
+/// 按 EWMH 向根窗口发送 `_NET_ACTIVE_WINDOW` 客户端消息，由窗口管理器切换焦点
+/// （来源标记为 2，即"分页器"发起的请求，窗口管理器不会因为焦点抢占保护而忽略）。
+pub fn activate_window(window: u64) -> Result<(), String> {
+    let window = Window::try_from(window).map_err(|_| format!("无效的 X11 窗口: {}", window))?;
+    let (conn, root) = connect()?;
+    let active_atom = intern_atom(&conn, "_NET_ACTIVE_WINDOW")?;
+
+    let event = ClientMessageEvent::new(32, window, active_atom, [2, x11rb::CURRENT_TIME, 0, 0, 0]);
+    conn.send_event(
+        false,
+        root,
+        EventMask::SUBSTRUCTURE_REDIRECT | EventMask::SUBSTRUCTURE_NOTIFY,
+        event,
+    )
+    .map_err(|e| format!("激活窗口失败: {}", e))?;
+    conn.flush().map_err(|e| format!("激活窗口失败: {}", e))
+}
+
+/// 等待其他程序取得 CLIPBOARD 选区（轮询选区所有者，所有者变化或选区无人持有时返回）
+pub fn hold_clipboard_until_replaced() -> Result<(), String> {
+    let (conn, _) = connect()?;
+    let clipboard = intern_atom(&conn, "CLIPBOARD")?;
+    let owner = || -> Result<Window, String> {
+        Ok(conn
+            .get_selection_owner(clipboard)
+            .map_err(|e| e.to_string())?
+            .reply()
+            .map_err(|e| e.to_string())?
+            .owner)
+    };
+
+    let initial = owner()?;
+    if initial == x11rb::NONE {
+        return Ok(());
+    }
+    while owner()? == initial {
+        std::thread::sleep(CLIPBOARD_OWNER_POLL_INTERVAL);
+    }
+    Ok(())
+}
+
+/// 根窗口 `_NET_ACTIVE_WINDOW` 指向的窗口（0 表示没有活动窗口）
+fn active_window(conn: &RustConnection, root: Window) -> Result<Option<Window>, String> {
+    let active_atom = intern_atom(conn, "_NET_ACTIVE_WINDOW")?;
+    Ok(get_property(conn, root, active_atom, AtomEnum::WINDOW.into())?
+        .value32()
+        .and_then(|mut values| values.next())
+        .filter(|&window| window != 0))
+}
+
+fn intern_atom(conn: &RustConnection, name: &str) -> Result<Atom, String> {
+    Ok(conn
+        .intern_atom(false, name.as_bytes())
+        .map_err(|e| e.to_string())?
+        .reply()
+        .map_err(|e| e.to_string())?
+        .atom)
+}
+
+fn get_property(conn: &RustConnection, window: Window, property: Atom, type_: Atom) -> Result<GetPropertyReply, String> {
+    conn.get_property(false, window, property, type_, 0, u32::MAX / 4)
+        .map_err(|e| e.to_string())?
+        .reply()
+        .map_err(|e| format!("读取窗口属性失败: {}", e))
+}
+
+/// 窗口所属进程 ID（`_NET_WM_PID`，部分窗口不提供）
+fn window_pid(conn: &RustConnection, window: Window) -> Result<Option<u32>, String> {
+    let pid_atom = intern_atom(conn, "_NET_WM_PID")?;
+    Ok(get_property(conn, window, pid_atom, AtomEnum::CARDINAL.into())?
+        .value32()
+        .and_then(|mut values| values.next())
+        .filter(|&pid| pid != 0))
+}
+
+/// 进程名：可执行文件名，读取不到时（其他用户的进程）用 /proc/<pid>/comm
+fn process_name(pid: u32) -> Option<String> {
+    let exe = std::fs::read_link(format!("/proc/{}/exe", pid))
+        .ok()
+        .and_then(|path| path.file_name().map(|name| name.to_string_lossy().to_string()));
+    exe.or_else(|| {
+        std::fs::read_to_string(format!("/proc/{}/comm", pid))
+            .ok()
+            .map(|comm| comm.trim().to_string())
+    })
+    .filter(|name| !name.is_empty())
+}
+
+/// `WM_CLASS` 的实例名和类名（"instance\0class\0"）
+fn wm_class(conn: &RustConnection, window: Window) -> Result<(Option<String>, Option<String>), String> {
+    let reply = get_property(conn, window, AtomEnum::WM_CLASS.into(), AtomEnum::STRING.into())?;
+    let mut parts = reply
+        .value
+        .split(|&b| b == 0)
+        .map(|part| (!part.is_empty()).then(|| String::from_utf8_lossy(part).to_string()));
+    Ok((parts.next().flatten(), parts.next().flatten()))
+}
+
+/// 窗口标题：优先 `_NET_WM_NAME`（UTF-8），否则 `WM_NAME`
+fn window_title(conn: &RustConnection, window: Window) -> Result<Option<String>, String> {
+    let net_wm_name = intern_atom(conn, "_NET_WM_NAME")?;
+    let utf8_string = intern_atom(conn, "UTF8_STRING")?;
+    let mut title = get_property(conn, window, net_wm_name, utf8_string)?.value;
+    if title.is_empty() {
+        title = get_property(conn, window, AtomEnum::WM_NAME.into(), AtomEnum::ANY.into())?.value;
+    }
+    Ok((!title.is_empty()).then(|| String::from_utf8_lossy(&title).to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cursor_position_without_display() {
+        let error = cursor_position(None).unwrap_err();
+        assert!(error.contains("Wayland"), "{}", error);
+    }
+
+    #[test]
+    #[ignore = "需要 X 服务器（DISPLAY），例如 xvfb-run cargo test -- --ignored"]
+    fn cursor_position_follows_pointer_warp() {
+        let Some(display) = std::env::var_os("DISPLAY") else {
+            return;
+        };
+        let (conn, root) = connect_to(Some(display.clone())).unwrap();
+        let screen = &conn.setup().roots[0];
+        let target = (
+            (screen.width_in_pixels / 3) as i16,
+            (screen.height_in_pixels / 2) as i16,
+        );
+        // check() 等待服务器处理完请求
+        conn.warp_pointer(x11rb::NONE, root, 0, 0, 0, 0, target.0, target.1)
+            .unwrap()
+            .check()
+            .unwrap();
+
+        assert_eq!(
+            cursor_position(Some(display)).unwrap(),
+            (target.0 as f64, target.1 as f64)
+        );
+    }
 }
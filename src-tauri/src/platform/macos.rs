@@ -1,9 +1,15 @@
 //! macOS 平台特定实现
 
+use crate::models::SourceApp;
+
 /// 获取鼠标光标位置（macOS 实现）
-/// 注意：当前为简化实现，实际需要 NSEvent 支持
+/// 注意：需要 NSEvent 支持，当前未实现，返回错误由调用方回退到居中
 pub fn get_cursor_position() -> Result<(f64, f64), String> {
-    // macOS: 使用 NSEvent 获取鼠标位置
-    // 注意：这里需要链接 Cocoa 框架，简化实现返回屏幕中心
-    Ok((400.0, 300.0))
+    Err("macOS 暂不支持获取光标位置".to_string())
+}
+
+/// 获取前台应用（macOS 实现）
+/// 注意：需要 NSWorkspace 支持，当前未实现，不记录来源
+pub fn get_foreground_app() -> Result<Option<SourceApp>, String> {
+    Ok(None)
 }
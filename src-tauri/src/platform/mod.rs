@@ -1,7 +1,16 @@
 //! 平台抽象模块
-//! 提供跨平台的窗口功能抽象，包括光标位置获取
+//! 提供跨平台的窗口功能抽象，包括光标位置获取、前台应用查询和窗口激活
 
-/// 获取当前鼠标光标位置
+use std::time::{Duration, Instant};
+
+use crate::models::{SourceApp, WindowInfo};
+
+/// 等待窗口获得焦点时的轮询间隔
+const FOCUS_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// 获取当前鼠标光标位置（屏幕物理像素坐标）
+///
+/// 无法获取时返回错误（不返回假坐标），调用方应回退到居中显示。
 pub fn get_cursor_position() -> Result<(f64, f64), String> {
     #[cfg(target_os = "windows")]
     {
@@ -20,7 +29,163 @@ pub fn get_cursor_position() -> Result<(f64, f64), String> {
 
     #[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
     {
-        Ok((400.0, 300.0))
+        Err("当前平台不支持获取光标位置".to_string())
+    }
+}
+
+/// 获取当前前台窗口所属的应用（剪贴板内容的来源）
+///
+/// 无法判断时（如 Wayland 会话、没有前台窗口）返回 Ok(None)。
+pub fn get_foreground_app() -> Result<Option<SourceApp>, String> {
+    #[cfg(target_os = "windows")]
+    {
+        windows::get_foreground_app()
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        macos::get_foreground_app()
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        linux::get_foreground_app()
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+    {
+        Ok(None)
+    }
+}
+
+/// 获取当前前台窗口（Windows 为 HWND，X11 为窗口 ID）
+///
+/// 无法判断时（如 Wayland 会话、macOS）返回 Ok(None)。
+pub fn get_foreground_window() -> Result<Option<u64>, String> {
+    #[cfg(target_os = "windows")]
+    {
+        windows::get_foreground_window()
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        linux::get_foreground_window()
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "linux")))]
+    {
+        Ok(None)
+    }
+}
+
+/// 获取窗口所属进程名和窗口类名（`get_foreground_window` 返回的窗口）
+pub fn get_window_info(window: u64) -> Result<WindowInfo, String> {
+    #[cfg(target_os = "windows")]
+    {
+        windows::get_window_info(window)
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        linux::get_window_info(window)
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "linux")))]
+    {
+        let _ = window;
+        Err("当前平台不支持查询窗口信息".to_string())
+    }
+}
+
+/// 请求激活指定窗口（不等待激活完成）
+pub fn activate_window(window: u64) -> Result<(), String> {
+    #[cfg(target_os = "windows")]
+    {
+        windows::activate_window(window)
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        linux::activate_window(window)
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "linux")))]
+    {
+        let _ = window;
+        Err("当前平台不支持激活窗口".to_string())
+    }
+}
+
+/// 激活指定窗口，并等待它成为前台窗口（超时返回错误）
+pub fn focus_window(window: u64, timeout: Duration) -> Result<(), String> {
+    if get_foreground_window()? == Some(window) {
+        return Ok(());
+    }
+    activate_window(window)?;
+
+    let deadline = Instant::now() + timeout;
+    loop {
+        std::thread::sleep(FOCUS_POLL_INTERVAL);
+        if get_foreground_window()? == Some(window) {
+            return Ok(());
+        }
+        if Instant::now() >= deadline {
+            return Err(format!("等待目标窗口获得焦点超时（{} ms）", timeout.as_millis()));
+        }
+    }
+}
+
+/// 写入剪贴板后保持运行，直到其他程序取得剪贴板
+///
+/// X11 下剪贴板内容由写入的进程提供，进程退出后内容随之失效（与 xclip 相同），
+/// 命令行工具写入后需要等待剪贴板管理器或其他程序接管；Windows、macOS 写入后由系统保存，直接返回。
+pub fn hold_clipboard_until_replaced() -> Result<(), String> {
+    #[cfg(target_os = "linux")]
+    {
+        linux::hold_clipboard_until_replaced()
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        Ok(())
+    }
+}
+
+/// 来源应用查询接口（ClipboardManager 通过它获取来源，便于替换为模拟实现）
+pub trait SourceAppProvider: Send + Sync {
+    fn foreground_app(&self) -> Result<Option<SourceApp>, String>;
+}
+
+/// 基于 `get_foreground_app` 的系统实现
+pub struct SystemSourceAppProvider;
+
+impl SourceAppProvider for SystemSourceAppProvider {
+    fn foreground_app(&self) -> Result<Option<SourceApp>, String> {
+        get_foreground_app()
+    }
+}
+
+/// 不查询来源的实现：命令行、IPC 等外部程序添加的内容没有来源应用
+/// （不查询前台窗口，避免把终端当作来源或命中忽略列表）
+pub struct NoSourceApp;
+
+impl SourceAppProvider for NoSourceApp {
+    fn foreground_app(&self) -> Result<Option<SourceApp>, String> {
+        Ok(None)
+    }
+}
+
+/// 前台窗口查询接口（粘贴目标通过它记录窗口，便于替换为模拟实现）
+pub trait ForegroundWindowProvider: Send + Sync {
+    fn foreground_window(&self) -> Result<Option<u64>, String>;
+}
+
+/// 基于 `get_foreground_window` 的系统实现
+pub struct SystemForegroundWindow;
+
+impl ForegroundWindowProvider for SystemForegroundWindow {
+    fn foreground_window(&self) -> Result<Option<u64>, String> {
+        get_foreground_window()
     }
 }
 
@@ -1,7 +1,19 @@
 //! Windows 平台特定实现
 
-use winapi::um::winuser::GetCursorPos;
-use winapi::shared::windef::POINT;
+use std::path::Path;
+
+use winapi::shared::minwindef::FALSE;
+use winapi::shared::windef::{HWND, POINT};
+use winapi::um::handleapi::CloseHandle;
+use winapi::um::processthreadsapi::OpenProcess;
+use winapi::um::winbase::QueryFullProcessImageNameW;
+use winapi::um::winnt::PROCESS_QUERY_LIMITED_INFORMATION;
+use winapi::um::winuser::{
+    GetClassNameW, GetCursorPos, GetForegroundWindow, GetWindowTextW, GetWindowThreadProcessId, IsIconic,
+    IsWindow, SetForegroundWindow, ShowWindow, SW_RESTORE,
+};
+
+use crate::models::{SourceApp, WindowInfo};
 
 /// 获取鼠标光标位置（Windows 实现）
 pub fn get_cursor_position() -> Result<(f64, f64), String> {
@@ -14,3 +26,97 @@ pub fn get_cursor_position() -> Result<(f64, f64), String> {
         }
     }
 }
+
+/// 获取前台应用（Windows 实现，进程名取可执行文件名）
+pub fn get_foreground_app() -> Result<Option<SourceApp>, String> {
+    unsafe {
+        let hwnd = GetForegroundWindow();
+        if hwnd.is_null() {
+            return Ok(None);
+        }
+
+        let mut title = [0u16; 512];
+        let len = GetWindowTextW(hwnd, title.as_mut_ptr(), title.len() as i32);
+        let window_title = (len > 0).then(|| String::from_utf16_lossy(&title[..len as usize]));
+
+        let Some(process_name) = window_process_name(hwnd)? else {
+            return Ok(None);
+        };
+        Ok(Some(SourceApp {
+            process_name,
+            window_title,
+        }))
+    }
+}
+
+/// 获取窗口的进程名和窗口类名（Windows 实现）
+pub fn get_window_info(window: u64) -> Result<WindowInfo, String> {
+    let hwnd = window as usize as HWND;
+    unsafe {
+        if IsWindow(hwnd) == 0 {
+            return Err("目标窗口已关闭".to_string());
+        }
+
+        let mut class = [0u16; 256];
+        let len = GetClassNameW(hwnd, class.as_mut_ptr(), class.len() as i32);
+        let window_class = (len > 0).then(|| String::from_utf16_lossy(&class[..len as usize]));
+
+        Ok(WindowInfo {
+            process_name: window_process_name(hwnd)?,
+            window_class,
+        })
+    }
+}
+
+/// 窗口所属进程的可执行文件名
+unsafe fn window_process_name(hwnd: HWND) -> Result<Option<String>, String> {
+    let mut pid = 0u32;
+    GetWindowThreadProcessId(hwnd, &mut pid);
+    if pid == 0 {
+        return Ok(None);
+    }
+
+    let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, FALSE, pid);
+    if process.is_null() {
+        return Err(format!("无法打开进程 {}", pid));
+    }
+    let mut path = [0u16; 1024];
+    let mut size = path.len() as u32;
+    let ok = QueryFullProcessImageNameW(process, 0, path.as_mut_ptr(), &mut size);
+    CloseHandle(process);
+    if ok == 0 {
+        return Err(format!("无法获取进程 {} 的路径", pid));
+    }
+
+    let path = String::from_utf16_lossy(&path[..size as usize]);
+    let process_name = Path::new(&path)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or(path);
+    Ok(Some(process_name))
+}
+
+/// 获取当前前台窗口（Windows 实现，返回 HWND）
+pub fn get_foreground_window() -> Result<Option<u64>, String> {
+    let hwnd = unsafe { GetForegroundWindow() };
+    Ok((!hwnd.is_null()).then_some(hwnd as usize as u64))
+}
+
+/// 激活窗口（Windows 实现）
+///
+/// 剪贴板窗口刚刚隐藏，本进程仍持有前台权限，SetForegroundWindow 可以生效；最小化的窗口先还原。
+pub fn activate_window(window: u64) -> Result<(), String> {
+    let hwnd = window as usize as HWND;
+    unsafe {
+        if IsWindow(hwnd) == 0 {
+            return Err("目标窗口已关闭".to_string());
+        }
+        if IsIconic(hwnd) != 0 {
+            ShowWindow(hwnd, SW_RESTORE);
+        }
+        if SetForegroundWindow(hwnd) == 0 {
+            return Err("无法将目标窗口切换到前台".to_string());
+        }
+    }
+    Ok(())
+}
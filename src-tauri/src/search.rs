@@ -0,0 +1,359 @@
+//! 搜索辅助模块
+//! 负责构建 FTS5 查询表达式、拼音转换、生成匹配片段与高亮区间
+
+use pinyin::ToPinyin;
+
+use crate::models::MatchRange;
+
+/// trigram 分词器要求关键词至少 3 个字符才能走 MATCH，更短的关键词退化为 LIKE
+pub const FTS_MIN_KEYWORD_CHARS: usize = 3;
+
+/// 片段中命中位置前保留的字符数
+const SNIPPET_LEADING_CHARS: usize = 20;
+/// 片段最大字符数
+const SNIPPET_MAX_CHARS: usize = 120;
+const SNIPPET_ELLIPSIS: char = '…';
+
+/// 正文类索引列（FTS5 列过滤语法）
+const TEXT_COLUMNS: &[&str] = &["body", "tags", "file_names"];
+/// 全拼索引列
+const PINYIN_COLUMNS: &[&str] = &["pinyin"];
+/// 拼音首字母索引列
+const INITIALS_COLUMNS: &[&str] = &["initials"];
+
+/// 拼音索引最多处理的字符数（避免大段文本让索引膨胀）
+const PINYIN_MAX_CHARS: usize = 2000;
+
+/// 关键词是否足够长，可以交给 FTS5 MATCH 处理
+pub fn is_fts_keyword(keyword: &str) -> bool {
+    keyword.chars().count() >= FTS_MIN_KEYWORD_CHARS
+}
+
+/// 将关键词转义为 FTS5 短语（双引号包裹，内部双引号加倍）
+///
+/// 这样关键词里的 `-`、`:`、`*`、`AND` 等都不会被当作 FTS5 语法解析。
+pub fn fts_phrase(keyword: &str) -> String {
+    format!("\"{}\"", keyword.replace('"', "\"\""))
+}
+
+/// 文本是否包含汉字
+pub fn contains_hanzi(text: &str) -> bool {
+    text.chars().any(|c| c.to_pinyin().is_some())
+}
+
+/// 转换为全拼（汉字替换为不带声调的拼音，其他字符转小写保留）
+///
+/// 例如 "复制 Text" -> "fuzhi text"
+pub fn to_pinyin_full(text: &str) -> String {
+    let mut result = String::with_capacity(text.len() * 2);
+    for c in text.chars().take(PINYIN_MAX_CHARS) {
+        match c.to_pinyin() {
+            Some(pinyin) => result.push_str(pinyin.plain()),
+            None => result.extend(c.to_lowercase()),
+        }
+    }
+    result
+}
+
+/// 转换为拼音首字母（汉字替换为首字母，其他字符转小写保留）
+///
+/// 例如 "复制 Text" -> "fz text"
+pub fn to_pinyin_initials(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for c in text.chars().take(PINYIN_MAX_CHARS) {
+        match c.to_pinyin() {
+            Some(pinyin) => result.push_str(pinyin.first_letter()),
+            None => result.extend(c.to_lowercase()),
+        }
+    }
+    result
+}
+
+/// 关键词查询计划
+///
+/// 每个关键词会展开为若干候选（原文、全拼、首字母），候选之间是 OR 关系，关键词之间是 AND 关系。
+/// 所有候选都不少于 3 个字符时走 FTS5 MATCH，否则整个关键词退化为 LIKE。
+#[derive(Debug, Default)]
+pub struct KeywordPlan {
+    /// FTS5 MATCH 表达式
+    pub match_expr: Option<String>,
+    /// LIKE 条件组：组内为 (列名, LIKE 模式)，组内 OR、组间 AND
+    pub like_groups: Vec<Vec<(&'static str, String)>>,
+}
+
+/// 展开关键词候选
+///
+/// 只有包含英文字母的关键词才做拼音匹配，纯中文关键词不会误命中同音字。
+/// 混合输入（如 "fu制"）会先整体转成拼音再匹配。
+fn keyword_alternatives(keyword: &str) -> Vec<(&'static [&'static str], String)> {
+    let mut alternatives: Vec<(&'static [&'static str], String)> = vec![(TEXT_COLUMNS, keyword.to_string())];
+
+    if keyword.chars().any(|c| c.is_ascii_alphabetic()) {
+        alternatives.push((PINYIN_COLUMNS, to_pinyin_full(keyword)));
+        alternatives.push((INITIALS_COLUMNS, to_pinyin_initials(keyword)));
+    }
+
+    alternatives
+}
+
+/// 构建关键词查询计划
+pub fn plan_keywords(keywords: &[String]) -> KeywordPlan {
+    let mut match_terms: Vec<String> = Vec::new();
+    let mut like_groups = Vec::new();
+
+    for keyword in keywords.iter().map(|k| k.trim()).filter(|k| !k.is_empty()) {
+        let alternatives = keyword_alternatives(keyword);
+
+        if alternatives.iter().all(|(_, needle)| is_fts_keyword(needle)) {
+            let term = alternatives
+                .iter()
+                .map(|(columns, needle)| format!("{{{}}} : {}", columns.join(" "), fts_phrase(needle)))
+                .collect::<Vec<_>>()
+                .join(" OR ");
+            match_terms.push(format!("({})", term));
+        } else {
+            let group = alternatives
+                .iter()
+                .flat_map(|(columns, needle)| {
+                    let pattern = format!("%{}%", escape_like(needle));
+                    columns.iter().map(move |column| (*column, pattern.clone()))
+                })
+                .collect();
+            like_groups.push(group);
+        }
+    }
+
+    KeywordPlan {
+        match_expr: if match_terms.is_empty() {
+            None
+        } else {
+            Some(match_terms.join(" AND "))
+        },
+        like_groups,
+    }
+}
+
+/// 转义 LIKE 通配符（配合 `ESCAPE '\'` 使用）
+pub fn escape_like(keyword: &str) -> String {
+    let mut escaped = String::with_capacity(keyword.len());
+    for c in keyword.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// 单字符小写（保持字符数量不变，方便换算偏移）
+fn fold_char(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+/// 在 `haystack` 中查找 `needle` 的所有出现位置（字符下标，不区分大小写）
+fn find_all(haystack: &[char], needle: &[char]) -> Vec<(usize, usize)> {
+    let mut ranges = Vec::new();
+    if needle.is_empty() || needle.len() > haystack.len() {
+        return ranges;
+    }
+
+    let mut i = 0;
+    while i + needle.len() <= haystack.len() {
+        if haystack[i..i + needle.len()] == *needle {
+            ranges.push((i, i + needle.len()));
+            i += needle.len();
+        } else {
+            i += 1;
+        }
+    }
+    ranges
+}
+
+/// 合并重叠的区间
+fn merge_ranges(mut ranges: Vec<(usize, usize)>) -> Vec<(usize, usize)> {
+    ranges.sort_unstable();
+    let mut merged: Vec<(usize, usize)> = Vec::with_capacity(ranges.len());
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+/// 字符下标转换为 UTF-16 偏移
+fn utf16_offset(chars: &[char], char_index: usize) -> usize {
+    chars[..char_index].iter().map(|c| c.len_utf16()).sum()
+}
+
+/// 生成匹配片段与高亮区间
+///
+/// 片段以第一个命中位置为中心截取，换行替换为空格；
+/// 高亮区间是片段内的 UTF-16 偏移，前端可以直接 `slice`。
+pub fn build_snippet(text: &str, keywords: &[String]) -> Option<(String, Vec<MatchRange>)> {
+    let original: Vec<char> = text
+        .chars()
+        .map(|c| if c == '\n' || c == '\r' || c == '\t' { ' ' } else { c })
+        .collect();
+    let folded: Vec<char> = original.iter().map(|c| fold_char(*c)).collect();
+
+    let mut ranges = Vec::new();
+    for keyword in keywords {
+        let needle: Vec<char> = keyword.trim().chars().map(fold_char).collect();
+        ranges.extend(find_all(&folded, &needle));
+    }
+    let ranges = merge_ranges(ranges);
+    let first = ranges.first()?.0;
+
+    let start = first.saturating_sub(SNIPPET_LEADING_CHARS);
+    let end = (start + SNIPPET_MAX_CHARS).min(original.len());
+
+    let mut snippet: Vec<char> = Vec::with_capacity(end - start + 2);
+    if start > 0 {
+        snippet.push(SNIPPET_ELLIPSIS);
+    }
+    let shift = snippet.len();
+    snippet.extend_from_slice(&original[start..end]);
+    if end < original.len() {
+        snippet.push(SNIPPET_ELLIPSIS);
+    }
+
+    let highlights = ranges
+        .into_iter()
+        .filter(|(s, _)| *s >= start && *s < end)
+        .map(|(s, e)| {
+            let s = s - start + shift;
+            let e = e.min(end) - start + shift;
+            MatchRange {
+                start: utf16_offset(&snippet, s),
+                end: utf16_offset(&snippet, e),
+            }
+        })
+        .collect();
+
+    Some((snippet.into_iter().collect(), highlights))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keywords(list: &[&str]) -> Vec<String> {
+        list.iter().map(|k| k.to_string()).collect()
+    }
+
+    fn ranges(highlights: &[MatchRange]) -> Vec<(usize, usize)> {
+        highlights.iter().map(|r| (r.start, r.end)).collect()
+    }
+
+    #[test]
+    fn short_keywords_fall_back_to_like() {
+        let plan = plan_keywords(&keywords(&["%_"]));
+        assert!(plan.match_expr.is_none());
+        assert_eq!(
+            plan.like_groups,
+            vec![vec![
+                ("body", "%\\%\\_%".to_string()),
+                ("tags", "%\\%\\_%".to_string()),
+                ("file_names", "%\\%\\_%".to_string()),
+            ]]
+        );
+        assert_eq!(escape_like("a\\b"), "a\\\\b");
+    }
+
+    #[test]
+    fn fts_syntax_in_keywords_is_quoted() {
+        let plan = plan_keywords(&keywords(&["1-2:\"3\"*"]));
+        assert_eq!(plan.match_expr.as_deref(), Some("({body tags file_names} : \"1-2:\"\"3\"\"*\")"));
+        assert!(plan.like_groups.is_empty());
+
+        let plan = plan_keywords(&keywords(&["NOT OR"]));
+        assert_eq!(
+            plan.match_expr.as_deref(),
+            Some("({body tags file_names} : \"NOT OR\" OR {pinyin} : \"not or\" OR {initials} : \"not or\")")
+        );
+    }
+
+    #[test]
+    fn mixed_keywords_combine_match_and_like() {
+        let plan = plan_keywords(&keywords(&["123", " ", "45", "6789"]));
+        assert_eq!(
+            plan.match_expr.as_deref(),
+            Some("({body tags file_names} : \"123\") AND ({body tags file_names} : \"6789\")")
+        );
+        assert_eq!(plan.like_groups.len(), 1);
+        assert!(plan.like_groups[0].iter().all(|(_, pattern)| pattern == "%45%"));
+
+        let empty = plan_keywords(&keywords(&["", "  "]));
+        assert!(empty.match_expr.is_none() && empty.like_groups.is_empty());
+    }
+
+    #[test]
+    fn snippet_highlights_use_utf16_offsets() {
+        let (snippet, highlights) = build_snippet("😀复制粘贴 Hello", &keywords(&["hello", "粘贴"])).unwrap();
+        assert_eq!(snippet, "😀复制粘贴 Hello");
+        assert_eq!(ranges(&highlights), vec![(4, 6), (7, 12)]);
+
+        let utf16: Vec<u16> = snippet.encode_utf16().collect();
+        assert_eq!(String::from_utf16(&utf16[7..12]).unwrap(), "Hello");
+    }
+
+    #[test]
+    fn snippet_is_trimmed_around_first_match() {
+        let text = format!("{}😀复制{}", "x".repeat(30), "y".repeat(200));
+        let (snippet, highlights) = build_snippet(&text, &keywords(&["复制"])).unwrap();
+        assert!(snippet.starts_with('…') && snippet.ends_with('…'));
+        assert_eq!(snippet.chars().count(), SNIPPET_MAX_CHARS + 2);
+        // 省略号 1 + 19 个 x + emoji 2 个 UTF-16 单元
+        assert_eq!(ranges(&highlights), vec![(22, 24)]);
+
+        let utf16: Vec<u16> = snippet.encode_utf16().collect();
+        assert_eq!(String::from_utf16(&utf16[22..24]).unwrap(), "复制");
+    }
+
+    #[test]
+    fn snippet_flattens_whitespace_and_ignores_case() {
+        let (snippet, highlights) = build_snippet("Line\r\nTEXT", &keywords(&["text"])).unwrap();
+        assert_eq!(snippet, "Line  TEXT");
+        assert_eq!(ranges(&highlights), vec![(6, 10)]);
+
+        assert!(build_snippet("nothing here", &keywords(&["absent"])).is_none());
+    }
+
+    #[test]
+    fn pinyin_conversion() {
+        assert_eq!(to_pinyin_full("复制 Text"), "fuzhi text");
+        assert_eq!(to_pinyin_initials("复制 Text"), "fz text");
+        assert!(contains_hanzi("粘贴a"));
+        assert!(!contains_hanzi("abc 123"));
+    }
+
+    #[test]
+    fn only_keywords_with_letters_get_pinyin_alternatives() {
+        assert_eq!(keyword_alternatives("复制"), vec![(TEXT_COLUMNS, "复制".to_string())]);
+        assert_eq!(
+            keyword_alternatives("fu制"),
+            vec![
+                (TEXT_COLUMNS, "fu制".to_string()),
+                (PINYIN_COLUMNS, "fuzhi".to_string()),
+                (INITIALS_COLUMNS, "fuz".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn pinyin_keywords_plan() {
+        let plan = plan_keywords(&keywords(&["fuzhi"]));
+        assert_eq!(
+            plan.match_expr.as_deref(),
+            Some("({body tags file_names} : \"fuzhi\" OR {pinyin} : \"fuzhi\" OR {initials} : \"fuzhi\")")
+        );
+
+        // 首字母通常很短，走 LIKE 并覆盖全部列
+        let plan = plan_keywords(&keywords(&["fz"]));
+        assert!(plan.match_expr.is_none());
+        let columns: Vec<&str> = plan.like_groups[0].iter().map(|(column, _)| *column).collect();
+        assert_eq!(columns, vec!["body", "tags", "file_names", "pinyin", "initials"]);
+    }
+}
@@ -1,134 +1,472 @@
-use rusqlite::{params, Connection, OptionalExtension, Result};
+use rusqlite::{ffi, params, Connection, OptionalExtension, Result};
 use std::collections::HashSet;
-use std::io::ErrorKind;
-use std::path::PathBuf;
-use std::sync::Mutex;
+use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
 
 use crate::models::{
-    AdvancedSearchRequest, AppSettings, ClearHistoryRequest, ClipboardContentType, ClipboardItem,
-    ClipboardMetadata,
+    normalize_process_name, AdvancedSearchRequest, AppSettings, ClearHistoryRequest, ClipboardContentType,
+    ClipboardItem, ClipboardMetadata, ExportRequest, SearchResult, SourceApp, Tag,
 };
+use crate::encryption::EncryptionKey;
+use crate::image_handler::ImageStore;
+use crate::migrations;
+use crate::paste_rules::{PasteKeystroke, PasteRule, PasteRuleField, PasteRuleInput};
+use crate::search;
+use crate::window_geometry::MonitorPositions;
+
+/// 记录查询的标准列（别名 h），tags 为该记录的标签名 JSON 数组
+const ITEM_COLUMNS_SQL: &str = "h.id, h.content_type, h.content, h.created_at, h.content_hash, h.text_content, h.metadata, h.file_paths, h.thumbnail_path,
+    (SELECT json_group_array(t.name) FROM item_tags it JOIN tags t ON t.id = it.tag_id WHERE it.item_id = h.id) AS tags,
+    h.is_sensitive, h.expires_at, h.source_app, h.source_window_title";
+
+/// ITEM_COLUMNS_SQL 的列数（之后的列由调用方自行读取）
+const ITEM_COLUMN_COUNT: usize = 14;
+
+/// 非片段记录（片段是用户维护的模板，任何清理都不会删除）
+const NOT_SNIPPET_SQL: &str = "content_type != 'snippet'";
+
+/// 没有任何标签的非片段记录（自动清理只处理这些记录）
+const UNTAGGED_SQL: &str = "content_type != 'snippet' AND NOT EXISTS (SELECT 1 FROM item_tags WHERE item_tags.item_id = clipboard_history.id)";
+
+/// 标签默认颜色，与前端 utils/tagColors.ts 的调色板保持一致
+const TAG_COLOR_PALETTE: &[&str] = &[
+    "#fa8c16", "#1890ff", "#52c41a", "#ff4d4f", "#722ed1", "#eb2f96",
+    "#13c2c2", "#faad14", "#2f54eb", "#595959", "#d48806", "#08979c",
+];
+
+/// 引用了本地图片文件的记录 (id, content_type, content, thumbnail_path)
+pub type ImageReference = (i64, String, String, Option<String>);
+
+/// 数据库文件名
+pub(crate) const DB_FILE: &str = "clipboard.db";
+/// 数据库被其他进程（GUI 与命令行工具）占用时等待的最长时间
+const BUSY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
 
 /// 数据库管理器
 pub struct Database {
-    conn: Mutex<Connection>,
+    app_dir: PathBuf,
+    /// 加密数据库解锁前为 None
+    conn: Mutex<Option<Connection>>,
+}
+
+/// 已打开的数据库连接（持有连接锁）
+struct ConnGuard<'a>(MutexGuard<'a, Option<Connection>>);
+
+impl Deref for ConnGuard<'_> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        self.0.as_ref().expect("ConnGuard 只在连接已打开时创建")
+    }
+}
+
+impl DerefMut for ConnGuard<'_> {
+    fn deref_mut(&mut self) -> &mut Connection {
+        self.0.as_mut().expect("ConnGuard 只在连接已打开时创建")
+    }
 }
 
 impl Database {
-    fn normalize_image_path(thumbnail_path: Option<String>, content: String) -> Option<String> {
-        let path = thumbnail_path.unwrap_or(content);
-        if path.trim().is_empty() {
-            return None;
+    fn content_type_to_str(content_type: ClipboardContentType) -> &'static str {
+        match content_type {
+            ClipboardContentType::Text => "text",
+            ClipboardContentType::Html => "html",
+            ClipboardContentType::Rtf => "rtf",
+            ClipboardContentType::Image => "image",
+            ClipboardContentType::File => "file",
+            ClipboardContentType::Folder => "folder",
+            ClipboardContentType::Files => "files",
+            ClipboardContentType::Snippet => "snippet",
+        }
+    }
+
+    pub(crate) fn content_type_from_str(value: &str) -> ClipboardContentType {
+        match value {
+            "text" => ClipboardContentType::Text,
+            "html" => ClipboardContentType::Html,
+            "rtf" => ClipboardContentType::Rtf,
+            "image" => ClipboardContentType::Image,
+            "file" => ClipboardContentType::File,
+            "folder" => ClipboardContentType::Folder,
+            "files" => ClipboardContentType::Files,
+            "snippet" => ClipboardContentType::Snippet,
+            _ => ClipboardContentType::Text,
+        }
+    }
+
+    /// 将查询行映射为 ClipboardItem
+    ///
+    /// 要求前 14 列依次为：id, content_type, content, created_at, content_hash,
+    /// text_content, metadata, file_paths, thumbnail_path, tags, is_sensitive, expires_at,
+    /// source_app, source_window_title（见 ITEM_COLUMNS_SQL）
+    fn map_item_row(row: &rusqlite::Row) -> Result<ClipboardItem> {
+        let content_type_str: String = row.get(1)?;
+
+        let created_at_str: String = row.get(3)?;
+        let created_at = created_at_str
+            .parse::<chrono::DateTime<chrono::Utc>>()
+            .unwrap_or_else(|_| chrono::Utc::now());
+
+        let metadata: Option<ClipboardMetadata> = row
+            .get::<_, Option<String>>(6)?
+            .and_then(|s| serde_json::from_str(&s).ok());
+        let file_paths: Option<Vec<String>> = row
+            .get::<_, Option<String>>(7)?
+            .and_then(|s| serde_json::from_str(&s).ok());
+        let tags: Option<Vec<String>> = row
+            .get::<_, Option<String>>(9)?
+            .and_then(|s| serde_json::from_str::<Vec<String>>(&s).ok())
+            .filter(|tags| !tags.is_empty());
+        let expires_at = row
+            .get::<_, Option<String>>(11)?
+            .and_then(|s| s.parse::<chrono::DateTime<chrono::Utc>>().ok());
+        let source_app = row
+            .get::<_, Option<String>>(12)?
+            .map(|process_name| -> Result<SourceApp> {
+                Ok(SourceApp {
+                    process_name,
+                    window_title: row.get(13)?,
+                })
+            })
+            .transpose()?;
+
+        Ok(ClipboardItem {
+            id: row.get(0)?,
+            content_type: Self::content_type_from_str(&content_type_str),
+            content: row.get(2)?,
+            created_at,
+            content_hash: row.get(4)?,
+            text_content: row.get(5)?,
+            metadata,
+            file_paths,
+            thumbnail_path: row.get(8)?,
+            tags,
+            is_sensitive: row.get(10)?,
+            expires_at,
+            source_app,
+        })
+    }
+
+    /// 计算记录的拼音索引（全拼, 首字母）
+    ///
+    /// 拼音来源与全文索引 body 列一致，另外附加文件名；
+    /// 不含汉字时返回空字符串，NULL 专门用来表示"尚未计算"。
+    pub(crate) fn compute_pinyin(
+        content_type: ClipboardContentType,
+        content: &str,
+        text_content: Option<&str>,
+        metadata: Option<&ClipboardMetadata>,
+    ) -> (String, String) {
+        let body = match content_type {
+            ClipboardContentType::Html => text_content.unwrap_or(""),
+            _ => content,
+        };
+        let mut source = body.to_string();
+        if let Some(metadata) = metadata {
+            for name in [&metadata.file_name, &metadata.folder_name].into_iter().flatten() {
+                source.push(' ');
+                source.push_str(name);
+            }
+        }
+
+        if !search::contains_hanzi(&source) {
+            return (String::new(), String::new());
+        }
+        (
+            search::to_pinyin_full(&source),
+            search::to_pinyin_initials(&source),
+        )
+    }
+
+    /// 标签默认颜色（与前端按标签名哈希取色的算法一致，迁移后标签颜色保持不变）
+    pub(crate) fn default_tag_color(name: &str) -> String {
+        let mut hash: i32 = 0;
+        for unit in name.encode_utf16() {
+            hash = hash.wrapping_shl(5).wrapping_sub(hash).wrapping_add(unit as i32);
+        }
+        let index = (hash as i64).unsigned_abs() as usize % TAG_COLOR_PALETTE.len();
+        TAG_COLOR_PALETTE[index].to_string()
+    }
+
+    /// 获取标签 ID，不存在时以默认颜色创建（名称不区分大小写）
+    pub(crate) fn ensure_tag(conn: &Connection, name: &str) -> Result<i64> {
+        conn.execute(
+            "INSERT OR IGNORE INTO tags (name, color) VALUES (?1, ?2)",
+            params![name, Self::default_tag_color(name)],
+        )?;
+        conn.query_row("SELECT id FROM tags WHERE name = ?1", params![name], |row| row.get(0))
+    }
+
+    /// 为记录添加标签（已有的标签保留）
+    fn add_item_tags(conn: &Connection, item_id: i64, names: &[String]) -> Result<()> {
+        for name in names.iter().map(|n| n.trim()).filter(|n| !n.is_empty()) {
+            let tag_id = Self::ensure_tag(conn, name)?;
+            conn.execute(
+                "INSERT OR IGNORE INTO item_tags (item_id, tag_id) VALUES (?1, ?2)",
+                params![item_id, tag_id],
+            )?;
+        }
+        Ok(())
+    }
+
+    fn map_tag_row(row: &rusqlite::Row) -> Result<Tag> {
+        Ok(Tag {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            color: row.get(2)?,
+            item_count: row.get(3)?,
+        })
+    }
+
+    fn map_paste_rule_row(row: &rusqlite::Row) -> Result<PasteRule> {
+        Ok(PasteRule {
+            id: row.get(0)?,
+            field: PasteRuleField::from_setting(&row.get::<_, String>(1)?),
+            pattern: row.get(2)?,
+            keystroke: PasteKeystroke::from_setting(&row.get::<_, String>(3)?),
+            pre_delay_ms: row.get::<_, i64>(4)?.max(0) as u64,
+            post_delay_ms: row.get::<_, i64>(5)?.max(0) as u64,
+            enabled: row.get(6)?,
+            sort_order: row.get(7)?,
+        })
+    }
+
+    /// 收集满足条件的图片记录引用的本地文件（原图和缩略图）
+    fn collect_image_files<P: rusqlite::Params>(
+        conn: &Connection,
+        condition: &str,
+        params: P,
+    ) -> Result<HashSet<String>> {
+        let sql = format!(
+            "SELECT content, thumbnail_path FROM clipboard_history
+             WHERE content_type = 'image' AND ({})",
+            condition
+        );
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map(params, |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?))
+        })?;
+
+        let mut paths = HashSet::new();
+        for row in rows {
+            let (content, thumbnail_path) = row?;
+            for path in std::iter::once(content).chain(thumbnail_path) {
+                if !path.trim().is_empty() {
+                    paths.insert(path);
+                }
+            }
         }
-        Some(path)
+        Ok(paths)
     }
 
+    /// 过滤出已不再被任何图片记录引用的文件（引用计数为 0 才能删除）
+    ///
+    /// 内容寻址存储中多条记录可能共享同一个文件，必须在删除记录之后调用。
+    fn unreferenced_image_files(conn: &Connection, paths: HashSet<String>) -> Result<HashSet<String>> {
+        let mut stmt = conn.prepare(
+            "SELECT COUNT(*) FROM clipboard_history
+             WHERE content_type = 'image' AND (content = ?1 OR thumbnail_path = ?1)",
+        )?;
+
+        let mut unreferenced = HashSet::new();
+        for path in paths {
+            let references: i64 = stmt.query_row(params![&path], |row| row.get(0))?;
+            if references == 0 {
+                unreferenced.insert(path);
+            }
+        }
+        Ok(unreferenced)
+    }
+
+    /// 删除不再被引用的图片文件
+    ///
+    /// 保存图片（复用或写入文件）与写入记录之间不持有数据库锁，宽限期内刚保存或复用过的文件
+    /// 可能属于正在写入的记录，跳过并留给图片存储垃圾回收。
     fn delete_local_image_files(paths: HashSet<String>) {
         for path in paths {
-            if let Err(error) = std::fs::remove_file(&path) {
-                if error.kind() != ErrorKind::NotFound {
-                    eprintln!("自动清理删除图片本地文件失败 ({}): {}", path, error);
-                }
+            if let Err(error) = ImageStore::remove_unreferenced_file(Path::new(&path)) {
+                eprintln!("删除图片本地文件失败 ({}): {}", path, error);
             }
         }
     }
 
-    /// 初始化数据库连接
+    /// 初始化数据库连接（未加密）
     pub fn new(app_dir: PathBuf) -> Result<Self> {
-        let db_path = app_dir.join("clipboard.db");
-        let conn = Connection::open(&db_path)?;
+        Self::open(app_dir, None)
+    }
+
+    /// 打开数据库（key 为 SQLCipher 密钥，None 表示明文数据库）
+    pub fn open(app_dir: PathBuf, key: Option<&EncryptionKey>) -> Result<Self> {
+        let conn = Self::open_connection(&app_dir.join(DB_FILE), key)?;
 
         let db = Self {
-            conn: Mutex::new(conn),
+            app_dir,
+            conn: Mutex::new(Some(conn)),
         };
 
         db.init_tables()?;
         Ok(db)
     }
 
-    /// 创建必要的表
-    fn init_tables(&self) -> Result<()> {
-        let conn = self.conn.lock().unwrap();
+    /// 创建处于锁定状态的数据库（口令加密时启动使用，需调用 `unlock` 后才能访问）
+    pub fn locked(app_dir: PathBuf) -> Self {
+        Self {
+            app_dir,
+            conn: Mutex::new(None),
+        }
+    }
 
-        // 剪贴板历史表
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS clipboard_history (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                content_type TEXT NOT NULL,
-                content TEXT NOT NULL,
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-                content_hash TEXT NOT NULL UNIQUE,
-                text_content TEXT,
-                metadata TEXT,
-                file_paths TEXT,
-                thumbnail_path TEXT,
-                tags TEXT
-            )",
-            [],
-        )?;
+    /// 应用数据目录
+    pub fn app_dir(&self) -> &Path {
+        &self.app_dir
+    }
 
-        // 数据库迁移：为旧表添加新字段
-        let columns: Vec<String> = conn
-            .prepare("PRAGMA table_info(clipboard_history)")?
-            .query_map([], |row| row.get::<_, String>(1))?
-            .filter_map(|r| r.ok())
-            .collect();
+    fn db_path(&self) -> PathBuf {
+        self.app_dir.join(DB_FILE)
+    }
 
-        if !columns.contains(&"metadata".to_string()) {
-            conn.execute("ALTER TABLE clipboard_history ADD COLUMN metadata TEXT", [])?;
-        }
-        if !columns.contains(&"file_paths".to_string()) {
-            conn.execute(
-                "ALTER TABLE clipboard_history ADD COLUMN file_paths TEXT",
-                [],
-            )?;
-        }
-        if !columns.contains(&"thumbnail_path".to_string()) {
-            conn.execute(
-                "ALTER TABLE clipboard_history ADD COLUMN thumbnail_path TEXT",
-                [],
-            )?;
+    /// 打开连接并校验密钥
+    fn open_connection(db_path: &Path, key: Option<&EncryptionKey>) -> Result<Connection> {
+        let conn = Connection::open(db_path)?;
+        if let Some(key) = key {
+            // 密钥只包含十六进制字符，可以直接拼入语句
+            conn.execute_batch(&format!("PRAGMA key = \"{}\";", key.sqlcipher_key()))?;
         }
-        if !columns.contains(&"tags".to_string()) {
-            conn.execute("ALTER TABLE clipboard_history ADD COLUMN tags TEXT", [])?;
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+        // SQLCipher 在首次读取时才校验密钥，密钥错误会在这里报 SQLITE_NOTADB
+        conn.query_row("SELECT count(*) FROM sqlite_master", [], |_| Ok(()))?;
+        // WAL 让命令行工具和 GUI 可以同时读写；内存数据库会保持 memory 模式
+        conn.query_row("PRAGMA journal_mode = WAL", [], |_| Ok(()))?;
+        // item_tags 依赖外键级联删除
+        conn.pragma_update(None, "foreign_keys", true)?;
+        Ok(conn)
+    }
+
+    /// 获取连接，数据库锁定时返回错误
+    fn lock_conn(&self) -> Result<ConnGuard<'_>> {
+        let guard = self.conn.lock().unwrap();
+        if guard.is_none() {
+            return Err(rusqlite::Error::SqliteFailure(
+                ffi::Error::new(ffi::SQLITE_AUTH),
+                Some("数据库已加密，请先解锁".to_string()),
+            ));
         }
-        if !columns.contains(&"text_content".to_string()) {
-            conn.execute(
-                "ALTER TABLE clipboard_history ADD COLUMN text_content TEXT",
-                [],
-            )?;
+        Ok(ConnGuard(guard))
+    }
+
+    /// 数据库是否处于锁定状态
+    pub fn is_locked(&self) -> bool {
+        self.conn.lock().unwrap().is_none()
+    }
+
+    /// 使用密钥解锁加密数据库
+    pub fn unlock(&self, key: &EncryptionKey) -> Result<()> {
+        let conn = Self::open_connection(&self.db_path(), Some(key))?;
+        *self.conn.lock().unwrap() = Some(conn);
+        self.init_tables()
+    }
+
+    /// 校验密钥能否打开当前数据库文件（不影响已打开的连接）
+    pub fn verify_key(&self, key: &EncryptionKey) -> Result<()> {
+        Self::open_connection(&self.db_path(), Some(key)).map(|_| ())
+    }
+
+    /// 将明文数据库加密
+    pub fn encrypt(&self, key: &EncryptionKey) -> Result<()> {
+        self.export_and_replace(&key.sqlcipher_key(), None, Some(key))
+    }
+
+    /// 将加密数据库解密为明文（current 为当前密钥）
+    pub fn decrypt(&self, current: &EncryptionKey) -> Result<()> {
+        self.export_and_replace("", Some(current), None)
+    }
+
+    /// 更换加密数据库的密钥（SQLCipher 会重写所有页）
+    pub fn rekey(&self, key: &EncryptionKey) -> Result<()> {
+        let conn = self.lock_conn()?;
+        conn.execute_batch(&format!("PRAGMA rekey = \"{}\";", key.sqlcipher_key()))?;
+        Ok(())
+    }
+
+    /// 通过 `sqlcipher_export` 把整库导出到新文件，再替换原文件并重新打开
+    ///
+    /// `export_key` 为空字符串时导出为明文数据库。导出或替换失败时恢复原文件，
+    /// 并用 `current_key` 重新打开。
+    fn export_and_replace(
+        &self,
+        export_key: &str,
+        current_key: Option<&EncryptionKey>,
+        new_key: Option<&EncryptionKey>,
+    ) -> Result<()> {
+        let mut guard = self.lock_conn()?;
+        let db_path = self.db_path();
+        let export_path = db_path.with_extension("db.export");
+        let backup_path = db_path.with_extension("db.bak");
+        let _ = std::fs::remove_file(&export_path);
+
+        guard.execute(
+            "ATTACH DATABASE ?1 AS export KEY ?2",
+            params![export_path.to_string_lossy(), export_key],
+        )?;
+        // sqlcipher_export 不会复制 user_version，需要手动同步结构版本
+        let exported = guard
+            .query_row("SELECT sqlcipher_export('export')", [], |_| Ok(()))
+            .and_then(|_| guard.query_row("PRAGMA main.user_version", [], |row| row.get::<_, i32>(0)))
+            .and_then(|version| {
+                guard.execute_batch(&format!("PRAGMA export.user_version = {};", version))
+            })
+            .and_then(|_| guard.execute_batch("DETACH DATABASE export;"));
+        if let Err(error) = exported {
+            let _ = guard.execute_batch("DETACH DATABASE export;");
+            let _ = std::fs::remove_file(&export_path);
+            return Err(error);
         }
 
-        // 数据迁移：将 is_favorite 转换为标签（如果存在旧字段）
-        if columns.contains(&"is_favorite".to_string()) {
-            conn.execute(
-                "UPDATE clipboard_history SET tags = '[\"收藏\"]' WHERE is_favorite = 1 AND tags IS NULL",
-                [],
-            )?;
+        // 先把 WAL 写回主文件，关闭旧连接后替换文件，替换失败时恢复原文件
+        guard.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))?;
+        *guard.0 = None;
+        let io_error = |e: std::io::Error| {
+            rusqlite::Error::SqliteFailure(ffi::Error::new(ffi::SQLITE_IOERR), Some(e.to_string()))
+        };
+        std::fs::rename(&db_path, &backup_path).map_err(io_error)?;
+        if let Err(error) = std::fs::rename(&export_path, &db_path) {
+            let _ = std::fs::rename(&backup_path, &db_path);
+            *guard.0 = Some(Self::open_connection(&db_path, current_key)?);
+            return Err(io_error(error));
         }
 
-        // 设置表
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS settings (
-                key TEXT PRIMARY KEY,
-                value TEXT NOT NULL
-            )",
-            [],
-        )?;
+        *guard.0 = Some(Self::open_connection(&db_path, new_key)?);
+        let _ = std::fs::remove_file(&backup_path);
+        Ok(())
+    }
+
+    /// 执行结构迁移并写入默认设置
+    fn init_tables(&self) -> Result<()> {
+        let mut conn = self.lock_conn()?;
+
+        migrations::migrate(&mut conn)?;
 
         // 初始化默认设置
         let defaults = vec![
             ("max_history_count", "5000"),
             ("auto_cleanup_days", "30"),
+            ("image_gc_interval_hours", "24"),
             ("window_position", "remember"),
             ("window_pos_x", ""),
             ("window_pos_y", ""),
+            ("window_width", ""),
+            ("window_height", ""),
             ("copy_sound", "false"),
             ("search_position", "bottom"),
             ("focus_search_on_activate", "false"),
             ("click_action", "copy"),
             ("double_click_action", "paste"),
             ("paste_shortcut", "ctrl_v"),
+            ("restore_clipboard_after_paste", "false"),
+            ("restore_clipboard_delay_ms", "300"),
+            ("typing_char_delay_ms", "10"),
+            ("typing_newline", "enter"),
             ("hide_window_after_copy", "false"),
             ("image_ocr", "false"),
             ("copy_as_plain_text", "false"),
@@ -139,6 +477,11 @@ impl Database {
             ("auto_start", "false"),
             ("number_key_shortcut", "ctrl"),
             ("pin_shortcut", "Ctrl+Shift+P"),
+            ("paste_next_shortcut", "Alt+Shift+V"),
+            ("sensitive_action", "expire"),
+            ("sensitive_expire_minutes", "5"),
+            ("sensitive_patterns", "[]"),
+            ("ignored_apps", r#"["keepassxc","keepass","1password","bitwarden","enpass"]"#),
             ("app_initialized", "false"),
         ];
 
@@ -154,7 +497,7 @@ impl Database {
 
     /// 检查是否是首次运行
     pub fn is_first_run(&self) -> Result<bool> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.lock_conn()?;
         let result: Option<String> = conn
             .query_row(
                 "SELECT value FROM settings WHERE key = 'app_initialized'",
@@ -171,7 +514,7 @@ impl Database {
 
     /// 标记应用已初始化（非首次运行）
     pub fn mark_app_initialized(&self) -> Result<()> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.lock_conn()?;
         conn.execute(
             "INSERT OR REPLACE INTO settings (key, value) VALUES ('app_initialized', 'true')",
             [],
@@ -179,6 +522,34 @@ impl Database {
         Ok(())
     }
 
+    /// 读取每个显示器上记住的窗口位置
+    ///
+    /// 这部分由后端维护，不放在 AppSettings 中，避免被设置页保存的旧值覆盖。
+    pub fn get_window_positions(&self) -> Result<MonitorPositions> {
+        let conn = self.lock_conn()?;
+        let value: Option<String> = conn
+            .query_row(
+                "SELECT value FROM settings WHERE key = 'window_monitor_positions'",
+                [],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(value
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default())
+    }
+
+    /// 保存每个显示器上记住的窗口位置
+    pub fn save_window_positions(&self, positions: &MonitorPositions) -> Result<()> {
+        let conn = self.lock_conn()?;
+        let json = serde_json::to_string(positions).unwrap_or_else(|_| "{}".to_string());
+        conn.execute(
+            "INSERT OR REPLACE INTO settings (key, value) VALUES ('window_monitor_positions', ?1)",
+            params![json],
+        )?;
+        Ok(())
+    }
+
     /// 添加剪贴板记录
     pub fn add_clipboard_item(
         &self,
@@ -186,8 +557,64 @@ impl Database {
         auto_sort: bool,
         is_internal_copy: bool,
     ) -> Result<i64> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.lock_conn()?;
+        Self::insert_item(&conn, item, auto_sort, is_internal_copy)
+    }
+
+    /// 在一个事务中批量导入记录，返回每条记录的结果；单条失败不影响其他记录
+    ///
+    /// 与已有记录重复时只合并、不降级：时间取两者中较晚的，已有的敏感标记保留，
+    /// 过期时间取两者中较早的（导入的通常是旧数据，不应让已有的敏感记录变成永久保存）
+    pub fn import_items(&self, items: &[ClipboardItem]) -> Result<Vec<Result<i64>>> {
+        let conn = self.lock_conn()?;
+        let tx = conn.unchecked_transaction()?;
+        let results = items
+            .iter()
+            .map(|item| {
+                Self::insert_with_conflict(
+                    &tx,
+                    item,
+                    "ON CONFLICT(content_hash) DO UPDATE SET
+                        created_at = MAX(created_at, excluded.created_at),
+                        is_sensitive = MAX(is_sensitive, excluded.is_sensitive),
+                        expires_at = CASE
+                            WHEN expires_at IS NULL THEN excluded.expires_at
+                            WHEN excluded.expires_at IS NULL THEN expires_at
+                            ELSE MIN(expires_at, excluded.expires_at)
+                        END,
+                        source_window_title = CASE WHEN source_app IS NULL THEN excluded.source_window_title ELSE source_window_title END,
+                        source_app = COALESCE(source_app, excluded.source_app)",
+                )
+            })
+            .collect();
+        tx.commit()?;
+        Ok(results)
+    }
+
+    fn insert_item(conn: &Connection, item: &ClipboardItem, auto_sort: bool, is_internal_copy: bool) -> Result<i64> {
+        let should_update_timestamp = auto_sort || !is_internal_copy;
+
+        // 敏感标记总是以最新一次检测为准（重新复制同一密钥会延长过期时间）；
+        // 来源应用取最近一次已知的来源（应用内复制不覆盖）
+        let conflict_sql = if should_update_timestamp {
+            "ON CONFLICT(content_hash) DO UPDATE SET
+                created_at = excluded.created_at,
+                is_sensitive = excluded.is_sensitive,
+                expires_at = excluded.expires_at,
+                source_window_title = CASE WHEN excluded.source_app IS NULL THEN source_window_title ELSE excluded.source_window_title END,
+                source_app = COALESCE(excluded.source_app, source_app)"
+        } else {
+            "ON CONFLICT(content_hash) DO UPDATE SET
+                is_sensitive = excluded.is_sensitive,
+                expires_at = excluded.expires_at,
+                source_window_title = CASE WHEN excluded.source_app IS NULL THEN source_window_title ELSE excluded.source_window_title END,
+                source_app = COALESCE(excluded.source_app, source_app)"
+        };
+        Self::insert_with_conflict(conn, item, conflict_sql)
+    }
 
+    /// 插入记录，内容哈希重复时按 conflict_sql 合并到已有记录，返回记录 ID
+    fn insert_with_conflict(conn: &Connection, item: &ClipboardItem, conflict_sql: &str) -> Result<i64> {
         let metadata_json = item
             .metadata
             .as_ref()
@@ -198,35 +625,19 @@ impl Database {
             .as_ref()
             .map(|p| serde_json::to_string(p).ok())
             .flatten();
-        let tags_json = item
-            .tags
-            .as_ref()
-            .map(|t| serde_json::to_string(t).ok())
-            .flatten();
-
-        let should_update_timestamp = auto_sort || !is_internal_copy;
-
-        let conflict_sql = if should_update_timestamp {
-            "ON CONFLICT(content_hash) DO UPDATE SET
-                created_at = excluded.created_at"
-        } else {
-            "ON CONFLICT(content_hash) DO NOTHING"
-        };
+        let (pinyin, pinyin_initials) = Self::compute_pinyin(
+            item.content_type,
+            &item.content,
+            item.text_content.as_deref(),
+            item.metadata.as_ref(),
+        );
 
         conn.execute(
-            &format!("INSERT INTO clipboard_history (content_type, content, created_at, content_hash, text_content, metadata, file_paths, thumbnail_path, tags)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+            &format!("INSERT INTO clipboard_history (content_type, content, created_at, content_hash, text_content, metadata, file_paths, thumbnail_path, pinyin, pinyin_initials, is_sensitive, expires_at, source_app, source_window_title)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)
              {}", conflict_sql),
             params![
-                match item.content_type {
-                    ClipboardContentType::Text => "text",
-                    ClipboardContentType::Html => "html",
-                    ClipboardContentType::Rtf => "rtf",
-                    ClipboardContentType::Image => "image",
-                    ClipboardContentType::File => "file",
-                    ClipboardContentType::Folder => "folder",
-                    ClipboardContentType::Files => "files",
-                },
+                Self::content_type_to_str(item.content_type),
                 item.content,
                 item.created_at.to_rfc3339(),
                 item.content_hash,
@@ -234,7 +645,12 @@ impl Database {
                 metadata_json,
                 file_paths_json,
                 item.thumbnail_path,
-                tags_json,
+                pinyin,
+                pinyin_initials,
+                item.is_sensitive,
+                item.expires_at.map(Self::expiry_to_sql),
+                item.source_app.as_ref().map(|app| &app.process_name),
+                item.source_app.as_ref().and_then(|app| app.window_title.as_ref()),
             ],
         )?;
 
@@ -244,122 +660,108 @@ impl Database {
             |row| row.get(0),
         )?;
 
+        if let Some(ref tags) = item.tags {
+            Self::add_item_tags(conn, id, tags)?;
+        }
+
         Ok(id)
     }
 
     /// 获取历史记录
     pub fn get_history(&self, limit: i64, offset: i64) -> Result<Vec<ClipboardItem>> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.lock_conn()?;
 
-        let mut stmt = conn.prepare(
-            "SELECT id, content_type, content, created_at, content_hash, text_content, metadata, file_paths, thumbnail_path, tags
-             FROM clipboard_history
-             ORDER BY created_at DESC
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {}
+             FROM clipboard_history h
+             ORDER BY h.created_at DESC
              LIMIT ?1 OFFSET ?2",
-        )?;
+            ITEM_COLUMNS_SQL
+        ))?;
 
         let items = stmt
-            .query_map(params![limit, offset], |row| {
-                let content_type_str: String = row.get(1)?;
-                let content_type = match content_type_str.as_str() {
-                    "text" => ClipboardContentType::Text,
-                    "html" => ClipboardContentType::Html,
-                    "rtf" => ClipboardContentType::Rtf,
-                    "image" => ClipboardContentType::Image,
-                    "file" => ClipboardContentType::File,
-                    "folder" => ClipboardContentType::Folder,
-                    "files" => ClipboardContentType::Files,
-                    _ => ClipboardContentType::Text,
-                };
-
-                let created_at_str: String = row.get(3)?;
-                let created_at = created_at_str
-                    .parse::<chrono::DateTime<chrono::Utc>>()
-                    .unwrap_or_else(|_| chrono::Utc::now());
-
-                let text_content: Option<String> = row.get(5)?;
-                let metadata: Option<ClipboardMetadata> = row
-                    .get::<_, Option<String>>(6)?
-                    .and_then(|s| serde_json::from_str(&s).ok());
-                let file_paths: Option<Vec<String>> = row
-                    .get::<_, Option<String>>(7)?
-                    .and_then(|s| serde_json::from_str(&s).ok());
-                let tags: Option<Vec<String>> = row
-                    .get::<_, Option<String>>(9)?
-                    .and_then(|s| serde_json::from_str(&s).ok());
-
-                Ok(ClipboardItem {
-                    id: row.get(0)?,
-                    content_type,
-                    content: row.get(2)?,
-                    created_at,
-                    content_hash: row.get(4)?,
-                    text_content,
-                    metadata,
-                    file_paths,
-                    thumbnail_path: row.get(8)?,
-                    tags,
-                })
-            })?
+            .query_map(params![limit, offset], Self::map_item_row)?
             .collect::<Result<Vec<_>>>()?;
 
         Ok(items)
     }
 
+    /// 按导出条件分批读取记录（按 ID 升序，返回 ID 大于 after_id 的最多 limit 条）
+    ///
+    /// 导出时逐批调用，每批之间释放连接锁，避免长时间阻塞剪贴板写入。
+    pub fn get_export_batch(&self, request: &ExportRequest, after_id: i64, limit: i64) -> Result<Vec<ClipboardItem>> {
+        let conn = self.lock_conn()?;
+
+        let mut conditions = vec!["h.id > ?".to_string()];
+        let mut sql_params: Vec<Box<dyn rusqlite::ToSql>> = vec![Box::new(after_id)];
+
+        if !request.types.is_empty() {
+            let placeholders = vec!["?"; request.types.len()].join(", ");
+            conditions.push(format!("h.content_type IN ({})", placeholders));
+            for t in &request.types {
+                sql_params.push(Box::new(Self::content_type_to_str(*t)));
+            }
+        }
+
+        // 标签过滤：带有任一标签即可
+        if !request.tags.is_empty() {
+            let placeholders = vec!["?"; request.tags.len()].join(", ");
+            conditions.push(format!(
+                "EXISTS (SELECT 1 FROM item_tags it JOIN tags t ON t.id = it.tag_id WHERE it.item_id = h.id AND t.name IN ({}))",
+                placeholders
+            ));
+            for tag in &request.tags {
+                sql_params.push(Box::new(tag.clone()));
+            }
+        }
+
+        // created_at 统一以 UTC 的 RFC 3339 保存，可直接按字符串比较
+        if let Some(from) = request.from {
+            conditions.push("h.created_at >= ?".to_string());
+            sql_params.push(Box::new(from.to_rfc3339()));
+        }
+        if let Some(to) = request.to {
+            conditions.push("h.created_at < ?".to_string());
+            sql_params.push(Box::new(to.to_rfc3339()));
+        }
+
+        if !request.include_sensitive {
+            conditions.push("h.is_sensitive = 0".to_string());
+        }
+
+        let sql = format!(
+            "SELECT {}
+             FROM clipboard_history h
+             WHERE {}
+             ORDER BY h.id ASC
+             LIMIT ?",
+            ITEM_COLUMNS_SQL,
+            conditions.join(" AND "),
+        );
+        sql_params.push(Box::new(limit));
+
+        let mut stmt = conn.prepare(&sql)?;
+        let items = stmt
+            .query_map(rusqlite::params_from_iter(sql_params.iter()), Self::map_item_row)?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(items)
+    }
+
     /// 搜索历史记录
     pub fn search_history(&self, query: &str, limit: i64) -> Result<Vec<ClipboardItem>> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.lock_conn()?;
         let query_lower = query.to_lowercase();
 
-        let mut stmt = conn.prepare(
-            "SELECT id, content_type, content, created_at, content_hash, text_content, metadata, file_paths, thumbnail_path, tags
-             FROM clipboard_history
-             ORDER BY created_at DESC
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {}
+             FROM clipboard_history h
+             ORDER BY h.created_at DESC
              LIMIT 1000",
-        )?;
+            ITEM_COLUMNS_SQL
+        ))?;
 
         let items = stmt
-            .query_map([], |row| {
-                let content_type_str: String = row.get(1)?;
-                let content_type = match content_type_str.as_str() {
-                    "text" => ClipboardContentType::Text,
-                    "html" => ClipboardContentType::Html,
-                    "rtf" => ClipboardContentType::Rtf,
-                    "image" => ClipboardContentType::Image,
-                    "file" => ClipboardContentType::File,
-                    "folder" => ClipboardContentType::Folder,
-                    "files" => ClipboardContentType::Files,
-                    _ => ClipboardContentType::Text,
-                };
-
-                let created_at_str: String = row.get(3)?;
-                let created_at = created_at_str
-                    .parse::<chrono::DateTime<chrono::Utc>>()
-                    .unwrap_or_else(|_| chrono::Utc::now());
-
-                let text_content: Option<String> = row.get(5)?;
-                let metadata_str: Option<String> = row.get(6)?;
-                let metadata = metadata_str.and_then(|s| serde_json::from_str(&s).ok());
-
-                let file_paths_str: Option<String> = row.get(7)?;
-                let file_paths = file_paths_str.and_then(|s| serde_json::from_str(&s).ok());
-
-                let tags_str: Option<String> = row.get(9)?;
-                let tags = tags_str.and_then(|s| serde_json::from_str(&s).ok());
-
-                Ok(ClipboardItem {
-                    id: row.get(0)?,
-                    content_type,
-                    content: row.get(2)?,
-                    created_at,
-                    content_hash: row.get(4)?,
-                    text_content,
-                    metadata,
-                    file_paths,
-                    thumbnail_path: row.get(8)?,
-                    tags,
-                })
-            })?
+            .query_map([], Self::map_item_row)?
             .filter_map(|item| item.ok())
             .filter(|item| {
                 let content_lower = item.content.to_lowercase();
@@ -376,321 +778,528 @@ impl Database {
     }
 
     /// 高级搜索历史记录（支持标签和类型过滤）
-    /// 
+    ///
     /// 优化策略：
-    /// 1. 标签过滤：使用 SQL LIKE 在数据库层面过滤（JSON 数组格式: ["tag1","tag2"]）
-    /// 2. 内容过滤：使用 SQL LIKE 在数据库层面过滤 content 和 text_content
-    /// 3. 只返回匹配的记录，避免大量数据传输
-    pub fn search_history_advanced(&self, request: &AdvancedSearchRequest) -> Result<Vec<ClipboardItem>> {
-        let conn = self.conn.lock().unwrap();
+    /// 1. 关键词通过 FTS5 全文索引（clipboard_fts）匹配，按 bm25 相关度排序
+    /// 2. 含英文字母的关键词同时匹配全拼和拼音首字母（如 "fuzhi"、"fz" 都能搜到"复制"）
+    /// 3. 不足 3 个字符的关键词 trigram 无法 MATCH，退化为在索引表上 LIKE
+    /// 4. 标签过滤：通过 item_tags / tags 关联过滤；来源应用按进程名过滤
+    /// 5. 返回匹配片段和高亮区间，前端无需再次扫描内容
+    pub fn search_history_advanced(&self, request: &AdvancedSearchRequest) -> Result<Vec<SearchResult>> {
+        let conn = self.lock_conn()?;
 
         let target_limit = request.limit.unwrap_or(50);
+        let offset = request.offset.unwrap_or(0);
+
+        let keywords: Vec<String> = request
+            .keywords
+            .iter()
+            .map(|k| k.trim().to_string())
+            .filter(|k| !k.is_empty())
+            .collect();
+        let plan = search::plan_keywords(&keywords);
 
         // 构建 SQL 条件
         let mut conditions: Vec<String> = vec!["1=1".to_string()];
         let mut sql_params: Vec<Box<dyn rusqlite::ToSql>> = vec![];
 
+        // 关键词过滤（不区分大小写，多个关键词之间是 AND 关系）
+        if let Some(ref match_expr) = plan.match_expr {
+            conditions.push("clipboard_fts MATCH ?".to_string());
+            sql_params.push(Box::new(match_expr.clone()));
+        }
+        for group in &plan.like_groups {
+            let alternatives: Vec<String> = group
+                .iter()
+                .map(|(column, _)| format!("clipboard_fts.{} LIKE ? ESCAPE '\\'", column))
+                .collect();
+            conditions.push(format!("({})", alternatives.join(" OR ")));
+            for (_, pattern) in group {
+                sql_params.push(Box::new(pattern.clone()));
+            }
+        }
+
         // 类型过滤
         if !request.types.is_empty() {
-            let placeholders: Vec<String> = (0..request.types.len())
-                .map(|i| format!("?{}", i + 1))
-                .collect();
-            conditions.push(format!("content_type IN ({})", placeholders.join(", ")));
+            let placeholders = vec!["?"; request.types.len()].join(", ");
+            conditions.push(format!("h.content_type IN ({})", placeholders));
             for t in &request.types {
-                let type_str = format!("{:?}", t).to_lowercase();
-                sql_params.push(Box::new(type_str));
+                sql_params.push(Box::new(Self::content_type_to_str(*t)));
             }
         }
 
-        // 标签过滤：使用 LIKE 匹配 JSON 数组格式（不区分大小写）
-        // 例如: LOWER(tags) LIKE '%"nekotick-api"%' 匹配 {"nekotick-api"}
+        // 标签过滤：通过 item_tags 关联（标签名不区分大小写，多个标签之间是 AND 关系）
         for tag in &request.tags {
-            let tag_pattern = format!("%\"{}\"%", tag.to_lowercase());
-            conditions.push("LOWER(tags) LIKE ?".to_string());
-            sql_params.push(Box::new(tag_pattern));
-        }
-
-        // 关键词过滤（不区分大小写）：
-        // - HTML类型：只用 text_content（纯文本提取内容）过滤
-        // - 其他类型：用 content 过滤
-        // - 多个关键词之间是 AND 关系（必须同时匹配所有关键词）
-        if !request.keywords.is_empty() {
-            let mut keyword_groups: Vec<String> = vec![];
-
-            for keyword in &request.keywords {
-                let keyword_lower = keyword.to_lowercase();
-                let keyword_pattern = format!("%{}%", keyword_lower);
-
-                // 每个关键词作为一个组：(HTML条件 OR 非HTML条件)
-                // 组内是 OR（匹配 HTML 内容或非 HTML 内容）
-                // 组间是 AND（所有关键词都必须匹配）
-                let group = "(content_type = 'html' AND LOWER(text_content) LIKE ?) OR (content_type != 'html' AND LOWER(content) LIKE ?)".to_string();
-                keyword_groups.push(format!("({})", group));
-                sql_params.push(Box::new(keyword_pattern.clone()));
-                sql_params.push(Box::new(keyword_pattern));
-            }
+            conditions.push(
+                "EXISTS (SELECT 1 FROM item_tags it JOIN tags t ON t.id = it.tag_id WHERE it.item_id = h.id AND t.name = ?)"
+                    .to_string(),
+            );
+            sql_params.push(Box::new(tag.clone()));
+        }
 
-            conditions.push(format!("({})", keyword_groups.join(" AND ")));
+        // 来源应用过滤（进程名不区分大小写，忽略 .exe 后缀，多个应用之间是 OR 关系）
+        let source_apps: Vec<String> = request
+            .source_apps
+            .iter()
+            .map(|name| normalize_process_name(name))
+            .filter(|name| !name.is_empty())
+            .collect();
+        if !source_apps.is_empty() {
+            let alternatives = vec!["LOWER(h.source_app) IN (?, ?)"; source_apps.len()].join(" OR ");
+            conditions.push(format!("({})", alternatives));
+            for name in source_apps {
+                sql_params.push(Box::new(format!("{}.exe", name)));
+                sql_params.push(Box::new(name));
+            }
         }
 
+        let (join_sql, score_sql, order_sql) = if plan.match_expr.is_some() {
+            (
+                "JOIN clipboard_fts ON clipboard_fts.rowid = h.id",
+                "bm25(clipboard_fts)",
+                "score ASC, h.created_at DESC",
+            )
+        } else if !keywords.is_empty() {
+            ("JOIN clipboard_fts ON clipboard_fts.rowid = h.id", "NULL", "h.created_at DESC")
+        } else {
+            ("", "NULL", "h.created_at DESC")
+        };
+
         let sql = format!(
-            "SELECT id, content_type, content, created_at, content_hash, text_content, metadata, file_paths, thumbnail_path, tags
-             FROM clipboard_history
+            "SELECT {},
+                    {} AS score
+             FROM clipboard_history h
+             {}
              WHERE {}
-             ORDER BY created_at DESC
-             LIMIT {}",
+             ORDER BY {}
+             LIMIT ? OFFSET ?",
+            ITEM_COLUMNS_SQL,
+            score_sql,
+            join_sql,
             conditions.join(" AND "),
-            target_limit
+            order_sql,
         );
+        sql_params.push(Box::new(target_limit));
+        sql_params.push(Box::new(offset));
 
         let mut stmt = conn.prepare(&sql)?;
 
-        let items: Vec<ClipboardItem> = stmt
+        let results: Vec<SearchResult> = stmt
             .query_map(rusqlite::params_from_iter(sql_params.iter()), |row| {
-                let content_type_str: String = row.get(1)?;
-                let content_type = match content_type_str.as_str() {
-                    "text" => ClipboardContentType::Text,
-                    "html" => ClipboardContentType::Html,
-                    "rtf" => ClipboardContentType::Rtf,
-                    "image" => ClipboardContentType::Image,
-                    "file" => ClipboardContentType::File,
-                    "folder" => ClipboardContentType::Folder,
-                    "files" => ClipboardContentType::Files,
-                    _ => ClipboardContentType::Text,
-                };
-
-                let created_at_str: String = row.get(3)?;
-                let created_at = created_at_str
-                    .parse::<chrono::DateTime<chrono::Utc>>()
-                    .unwrap_or_else(|_| chrono::Utc::now());
-
-                let text_content: Option<String> = row.get(5)?;
-                let metadata_str: Option<String> = row.get(6)?;
-                let metadata = metadata_str.and_then(|s| serde_json::from_str(&s).ok());
-
-                let file_paths_str: Option<String> = row.get(7)?;
-                let file_paths = file_paths_str.and_then(|s| serde_json::from_str(&s).ok());
-
-                let tags_str: Option<String> = row.get(9)?;
-                let tags = tags_str.and_then(|s| serde_json::from_str(&s).ok());
-
-                Ok(ClipboardItem {
-                    id: row.get(0)?,
-                    content_type,
-                    content: row.get(2)?,
-                    created_at,
-                    content_hash: row.get(4)?,
-                    text_content,
-                    metadata,
-                    file_paths,
-                    thumbnail_path: row.get(8)?,
-                    tags,
-                })
+                let item = Self::map_item_row(row)?;
+                let score: Option<f64> = row.get(ITEM_COLUMN_COUNT)?;
+                Ok((item, score))
             })?
-            .filter_map(|item| item.ok())
+            .filter_map(|row| row.ok())
+            .map(|(item, score)| {
+                let body = match item.content_type {
+                    ClipboardContentType::Html => item.text_content.as_deref().unwrap_or(""),
+                    _ => item.content.as_str(),
+                };
+                let (snippet, highlights) = match search::build_snippet(body, &keywords) {
+                    Some((snippet, highlights)) => (Some(snippet), highlights),
+                    None => (None, Vec::new()),
+                };
+                SearchResult {
+                    item,
+                    score,
+                    snippet,
+                    highlights,
+                }
+            })
             .collect();
 
-        Ok(items)
+        Ok(results)
     }
 
-    /// 删除单条记录
+    /// 历史记录中出现过的来源应用（进程名），最近使用的在前
+    pub fn get_source_apps(&self) -> Result<Vec<String>> {
+        let conn = self.lock_conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT source_app FROM clipboard_history
+             WHERE source_app IS NOT NULL
+             GROUP BY source_app COLLATE NOCASE
+             ORDER BY MAX(created_at) DESC",
+        )?;
+        let apps = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<Vec<String>>>()?;
+        Ok(apps)
+    }
+
+    /// 删除单条记录（图片文件不再被引用时一并删除）
     pub fn delete_item(&self, id: i64) -> Result<()> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.lock_conn()?;
+        let image_files = Self::collect_image_files(&conn, "id = ?1", params![id])?;
+
         conn.execute("DELETE FROM clipboard_history WHERE id = ?1", params![id])?;
+
+        let image_files = Self::unreferenced_image_files(&conn, image_files)?;
+        drop(conn);
+        Self::delete_local_image_files(image_files);
         Ok(())
     }
 
-    /// 获取图片记录对应的本地文件路径
-    pub fn get_image_path_by_id(&self, id: i64) -> Result<Option<String>> {
-        let conn = self.conn.lock().unwrap();
-        let row: Option<(String, Option<String>, String)> = conn
-            .query_row(
-                "SELECT content_type, thumbnail_path, content FROM clipboard_history WHERE id = ?1",
-                params![id],
-                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
-            )
-            .optional()?;
+    /// 过期时间的存储格式（固定到秒的 UTC 时间，保证按字符串比较即按时间比较）
+    fn expiry_to_sql(time: chrono::DateTime<chrono::Utc>) -> String {
+        time.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+    }
 
-        let Some((content_type, thumbnail_path, content)) = row else {
-            return Ok(None);
-        };
+    /// 删除已过期的敏感记录（有标签的记录视为用户主动保留），返回删除的记录 ID
+    pub fn delete_expired_items(&self, now: chrono::DateTime<chrono::Utc>) -> Result<Vec<i64>> {
+        let conn = self.lock_conn()?;
+        let condition = format!("expires_at IS NOT NULL AND expires_at <= ?1 AND {}", UNTAGGED_SQL);
+        let now = Self::expiry_to_sql(now);
 
-        if content_type != "image" {
-            return Ok(None);
+        let ids: Vec<i64> = conn
+            .prepare(&format!("SELECT id FROM clipboard_history WHERE {}", condition))?
+            .query_map(params![now], |row| row.get(0))?
+            .collect::<Result<Vec<_>>>()?;
+        if ids.is_empty() {
+            return Ok(ids);
         }
 
-        Ok(Self::normalize_image_path(thumbnail_path, content))
+        let image_files = Self::collect_image_files(&conn, &condition, params![now])?;
+        conn.execute(
+            &format!("DELETE FROM clipboard_history WHERE {}", condition),
+            params![now],
+        )?;
+
+        let image_files = Self::unreferenced_image_files(&conn, image_files)?;
+        drop(conn);
+        Self::delete_local_image_files(image_files);
+        Ok(ids)
     }
 
-    /// 更新标签
-    pub fn update_tags(&self, id: i64, tags: &Option<Vec<String>>) -> Result<()> {
-        let conn = self.conn.lock().unwrap();
-        let tags_json = tags
-            .as_ref()
-            .map(|t| serde_json::to_string(t).ok())
-            .flatten();
+    /// 获取单条记录
+    pub fn get_item(&self, id: i64) -> Result<Option<ClipboardItem>> {
+        let conn = self.lock_conn()?;
+        conn.query_row(
+            &format!("SELECT {} FROM clipboard_history h WHERE h.id = ?1", ITEM_COLUMNS_SQL),
+            params![id],
+            Self::map_item_row,
+        )
+        .optional()
+    }
+
+    /// 更新片段正文（同时更新哈希与拼音索引）
+    pub fn update_snippet(&self, id: i64, body: &str, content_hash: &str) -> Result<()> {
+        let (pinyin, pinyin_initials) =
+            Self::compute_pinyin(ClipboardContentType::Snippet, body, Some(body), None);
+        let conn = self.lock_conn()?;
+        conn.execute(
+            "UPDATE clipboard_history
+             SET content = ?1, text_content = ?1, content_hash = ?2, pinyin = ?3, pinyin_initials = ?4
+             WHERE id = ?5 AND content_type = 'snippet'",
+            params![body, content_hash, pinyin, pinyin_initials, id],
+        )?;
+        Ok(())
+    }
+
+    /// 获取所有引用了本地图片文件的记录
+    pub fn get_image_references(&self) -> Result<Vec<ImageReference>> {
+        let conn = self.lock_conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, content_type, content, thumbnail_path FROM clipboard_history
+             WHERE content_type = 'image' OR thumbnail_path IS NOT NULL",
+        )?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)))?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(rows)
+    }
+
+    /// 更新缩略图路径
+    pub fn update_thumbnail_path(&self, id: i64, thumbnail_path: &str) -> Result<()> {
+        let conn = self.lock_conn()?;
         conn.execute(
-            "UPDATE clipboard_history SET tags = ?1 WHERE id = ?2",
-            params![tags_json, id],
+            "UPDATE clipboard_history SET thumbnail_path = ?1 WHERE id = ?2",
+            params![thumbnail_path, id],
         )?;
         Ok(())
     }
 
-    /// 获取所有标签
+    /// 更新记录的标签（整体替换）
+    pub fn update_tags(&self, id: i64, tags: &Option<Vec<String>>) -> Result<()> {
+        let conn = self.lock_conn()?;
+        let tx = conn.unchecked_transaction()?;
+        tx.execute("DELETE FROM item_tags WHERE item_id = ?1", params![id])?;
+        if let Some(tags) = tags {
+            Self::add_item_tags(&tx, id, tags)?;
+        }
+        tx.commit()
+    }
+
+    /// 获取所有标签及使用次数（按使用次数降序）
     pub fn get_all_tags(&self) -> Result<Vec<(String, i64)>> {
-        let conn = self.conn.lock().unwrap();
+        Ok(self
+            .get_tags()?
+            .into_iter()
+            .map(|tag| (tag.name, tag.item_count))
+            .collect())
+    }
 
-        let mut stmt = conn.prepare("SELECT tags FROM clipboard_history WHERE tags IS NOT NULL")?;
+    /// 获取所有标签（含颜色和使用次数，按使用次数降序）
+    pub fn get_tags(&self) -> Result<Vec<Tag>> {
+        let conn = self.lock_conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT t.id, t.name, t.color, COUNT(it.item_id) AS item_count
+             FROM tags t
+             LEFT JOIN item_tags it ON it.tag_id = t.id
+             GROUP BY t.id
+             ORDER BY item_count DESC, t.name ASC",
+        )?;
+        let tags = stmt
+            .query_map([], Self::map_tag_row)?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(tags)
+    }
 
-        let rows: Vec<String> = stmt
-            .query_map([], |row| row.get::<_, String>(0))?
-            .filter_map(|r| r.ok())
-            .collect();
+    /// 按 ID 获取标签
+    pub fn get_tag(&self, id: i64) -> Result<Option<Tag>> {
+        let conn = self.lock_conn()?;
+        conn.query_row(
+            "SELECT t.id, t.name, t.color, (SELECT COUNT(*) FROM item_tags WHERE tag_id = t.id)
+             FROM tags t WHERE t.id = ?1",
+            params![id],
+            Self::map_tag_row,
+        )
+        .optional()
+    }
 
-        let mut tag_counts: std::collections::HashMap<String, i64> =
-            std::collections::HashMap::new();
-        for tags_json in rows {
-            if let Ok(tags) = serde_json::from_str::<Vec<String>>(&tags_json) {
-                for tag in tags {
-                    *tag_counts.entry(tag).or_insert(0) += 1;
-                }
-            }
+    /// 按名称查找标签（不区分大小写）
+    pub fn find_tag_by_name(&self, name: &str) -> Result<Option<Tag>> {
+        let conn = self.lock_conn()?;
+        conn.query_row(
+            "SELECT t.id, t.name, t.color, (SELECT COUNT(*) FROM item_tags WHERE tag_id = t.id)
+             FROM tags t WHERE t.name = ?1",
+            params![name],
+            Self::map_tag_row,
+        )
+        .optional()
+    }
+
+    /// 导入标签颜色：标签不存在时按给定颜色创建，已存在时保留原颜色
+    pub fn import_tag(&self, name: &str, color: &str) -> Result<()> {
+        let conn = self.lock_conn()?;
+        conn.execute(
+            "INSERT OR IGNORE INTO tags (name, color) VALUES (?1, ?2)",
+            params![name, color],
+        )?;
+        Ok(())
+    }
+
+    /// 创建标签，返回新标签 ID
+    pub fn create_tag(&self, name: &str, color: &str) -> Result<i64> {
+        let conn = self.lock_conn()?;
+        conn.execute(
+            "INSERT INTO tags (name, color) VALUES (?1, ?2)",
+            params![name, color],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// 重命名标签（所有记录同步生效）
+    pub fn rename_tag(&self, id: i64, name: &str) -> Result<()> {
+        let conn = self.lock_conn()?;
+        conn.execute("UPDATE tags SET name = ?1 WHERE id = ?2", params![name, id])?;
+        Ok(())
+    }
+
+    /// 修改标签颜色
+    pub fn recolor_tag(&self, id: i64, color: &str) -> Result<()> {
+        let conn = self.lock_conn()?;
+        conn.execute("UPDATE tags SET color = ?1 WHERE id = ?2", params![color, id])?;
+        Ok(())
+    }
+
+    /// 将多个标签合并到目标标签：源标签的记录改挂到目标标签，然后删除源标签
+    pub fn merge_tags(&self, source_ids: &[i64], target_id: i64) -> Result<()> {
+        let conn = self.lock_conn()?;
+        let tx = conn.unchecked_transaction()?;
+        for source_id in source_ids.iter().filter(|id| **id != target_id) {
+            tx.execute(
+                "INSERT OR IGNORE INTO item_tags (item_id, tag_id)
+                 SELECT item_id, ?1 FROM item_tags WHERE tag_id = ?2",
+                params![target_id, source_id],
+            )?;
+            tx.execute("DELETE FROM tags WHERE id = ?1", params![source_id])?;
         }
+        tx.commit()
+    }
+
+    /// 删除标签（从所有记录上移除）
+    pub fn delete_tag(&self, id: i64) -> Result<()> {
+        let conn = self.lock_conn()?;
+        conn.execute("DELETE FROM tags WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+
+    /// 获取所有粘贴按键规则（按匹配顺序）
+    pub fn get_paste_rules(&self) -> Result<Vec<PasteRule>> {
+        let conn = self.lock_conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, match_field, pattern, keystroke, pre_delay_ms, post_delay_ms, enabled, sort_order
+             FROM paste_rules
+             ORDER BY sort_order ASC, id ASC",
+        )?;
+        let rules = stmt
+            .query_map([], Self::map_paste_rule_row)?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(rules)
+    }
+
+    /// 按 ID 获取粘贴按键规则
+    pub fn get_paste_rule(&self, id: i64) -> Result<Option<PasteRule>> {
+        let conn = self.lock_conn()?;
+        conn.query_row(
+            "SELECT id, match_field, pattern, keystroke, pre_delay_ms, post_delay_ms, enabled, sort_order
+             FROM paste_rules WHERE id = ?1",
+            params![id],
+            Self::map_paste_rule_row,
+        )
+        .optional()
+    }
 
-        let mut result: Vec<(String, i64)> = tag_counts.into_iter().collect();
-        result.sort_by(|a, b| b.1.cmp(&a.1));
+    /// 创建粘贴按键规则（排在最后），返回新规则 ID
+    pub fn create_paste_rule(&self, rule: &PasteRuleInput) -> Result<i64> {
+        let conn = self.lock_conn()?;
+        conn.execute(
+            "INSERT INTO paste_rules (match_field, pattern, keystroke, pre_delay_ms, post_delay_ms, enabled, sort_order)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, (SELECT COALESCE(MAX(sort_order), -1) + 1 FROM paste_rules))",
+            params![
+                rule.field.as_str(),
+                rule.pattern,
+                rule.keystroke.as_str(),
+                rule.pre_delay_ms as i64,
+                rule.post_delay_ms as i64,
+                rule.enabled,
+            ],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
 
-        Ok(result)
+    /// 修改粘贴按键规则
+    pub fn update_paste_rule(&self, id: i64, rule: &PasteRuleInput) -> Result<()> {
+        let conn = self.lock_conn()?;
+        conn.execute(
+            "UPDATE paste_rules
+             SET match_field = ?1, pattern = ?2, keystroke = ?3, pre_delay_ms = ?4, post_delay_ms = ?5, enabled = ?6
+             WHERE id = ?7",
+            params![
+                rule.field.as_str(),
+                rule.pattern,
+                rule.keystroke.as_str(),
+                rule.pre_delay_ms as i64,
+                rule.post_delay_ms as i64,
+                rule.enabled,
+                id,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// 删除粘贴按键规则
+    pub fn delete_paste_rule(&self, id: i64) -> Result<()> {
+        let conn = self.lock_conn()?;
+        conn.execute("DELETE FROM paste_rules WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+
+    /// 按给定顺序重排粘贴按键规则（未列出的规则保持原顺序排在后面）
+    pub fn reorder_paste_rules(&self, ids: &[i64]) -> Result<()> {
+        let conn = self.lock_conn()?;
+        let tx = conn.unchecked_transaction()?;
+        tx.execute(
+            "UPDATE paste_rules SET sort_order = sort_order + ?1",
+            params![ids.len() as i64],
+        )?;
+        for (index, id) in ids.iter().enumerate() {
+            tx.execute(
+                "UPDATE paste_rules SET sort_order = ?1 WHERE id = ?2",
+                params![index as i64, id],
+            )?;
+        }
+        tx.commit()
     }
 
     /// 清空历史
     pub fn clear_history(&self, request: &ClearHistoryRequest) -> Result<i64> {
-        let conn = self.conn.lock().unwrap();
-        let mut image_paths_to_delete: HashSet<String> = HashSet::new();
-
-        let rows_affected = if let Some(keep_count) = request.keep_count {
-            let mut stmt = conn.prepare(
-                "SELECT thumbnail_path, content
-                 FROM clipboard_history
-                 WHERE content_type = 'image'
-                   AND id NOT IN (
-                       SELECT id FROM clipboard_history
-                       ORDER BY created_at DESC
-                       LIMIT ?1
-                   )",
-            )?;
-            let rows = stmt.query_map(params![keep_count], |row| {
-                Ok((row.get::<_, Option<String>>(0)?, row.get::<_, String>(1)?))
-            })?;
-            for row in rows {
-                let (thumbnail_path, content) = row?;
-                if let Some(path) = Self::normalize_image_path(thumbnail_path, content) {
-                    image_paths_to_delete.insert(path);
-                }
-            }
+        let conn = self.lock_conn()?;
+
+        let (rows_affected, image_files) = if let Some(keep_count) = request.keep_count {
+            let condition = format!(
+                "{0} AND id NOT IN (
+                    SELECT id FROM clipboard_history
+                    WHERE {0}
+                    ORDER BY created_at DESC
+                    LIMIT ?1
+                )",
+                NOT_SNIPPET_SQL
+            );
+            let image_files = Self::collect_image_files(&conn, &condition, params![keep_count])?;
 
-            conn.execute(
-                "DELETE FROM clipboard_history
-                 WHERE id NOT IN (
-                     SELECT id FROM clipboard_history
-                     ORDER BY created_at DESC
-                     LIMIT ?1
-                 )",
+            let rows_affected = conn.execute(
+                &format!("DELETE FROM clipboard_history WHERE {}", condition),
                 params![keep_count],
-            )?
+            )?;
+            (rows_affected, image_files)
         } else if let Some(keep_days) = request.keep_days {
             let cutoff_date = chrono::Utc::now() - chrono::Duration::days(keep_days);
             let cutoff_date_str = cutoff_date.to_rfc3339();
 
-            let mut stmt = conn.prepare(
-                "SELECT thumbnail_path, content
-                 FROM clipboard_history
-                 WHERE content_type = 'image'
-                   AND created_at < ?1",
-            )?;
-            let rows = stmt.query_map(params![&cutoff_date_str], |row| {
-                Ok((row.get::<_, Option<String>>(0)?, row.get::<_, String>(1)?))
-            })?;
-            for row in rows {
-                let (thumbnail_path, content) = row?;
-                if let Some(path) = Self::normalize_image_path(thumbnail_path, content) {
-                    image_paths_to_delete.insert(path);
-                }
-            }
+            let condition = format!("{} AND created_at < ?1", NOT_SNIPPET_SQL);
+            let image_files = Self::collect_image_files(&conn, &condition, params![&cutoff_date_str])?;
 
-            conn.execute(
-                "DELETE FROM clipboard_history WHERE created_at < ?1",
+            let rows_affected = conn.execute(
+                &format!("DELETE FROM clipboard_history WHERE {}", condition),
                 params![&cutoff_date_str],
-            )?
-        } else {
-            let mut stmt = conn.prepare(
-                "SELECT thumbnail_path, content
-                 FROM clipboard_history
-                 WHERE content_type = 'image'",
             )?;
-            let rows = stmt.query_map([], |row| {
-                Ok((row.get::<_, Option<String>>(0)?, row.get::<_, String>(1)?))
-            })?;
-            for row in rows {
-                let (thumbnail_path, content) = row?;
-                if let Some(path) = Self::normalize_image_path(thumbnail_path, content) {
-                    image_paths_to_delete.insert(path);
-                }
-            }
+            (rows_affected, image_files)
+        } else {
+            let image_files = Self::collect_image_files(&conn, NOT_SNIPPET_SQL, [])?;
 
-            conn.execute("DELETE FROM clipboard_history", [])?
+            let rows_affected = conn.execute(
+                &format!("DELETE FROM clipboard_history WHERE {}", NOT_SNIPPET_SQL),
+                [],
+            )?;
+            (rows_affected, image_files)
         };
 
+        let image_files = Self::unreferenced_image_files(&conn, image_files)?;
         drop(conn);
-        Self::delete_local_image_files(image_paths_to_delete);
+        Self::delete_local_image_files(image_files);
 
         Ok(rows_affected as i64)
     }
 
     /// 启动时自动清理
     pub fn startup_cleanup(&self, max_history_count: i64, auto_cleanup_days: i64) -> Result<i64> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.lock_conn()?;
         let mut total_deleted = 0i64;
-        let mut image_paths_to_delete: HashSet<String> = HashSet::new();
+        let mut image_files: HashSet<String> = HashSet::new();
 
         let count_without_tags: i64 = conn.query_row(
-            "SELECT COUNT(*) FROM clipboard_history WHERE tags IS NULL OR tags = '' OR tags = 'null'",
+            &format!("SELECT COUNT(*) FROM clipboard_history WHERE {}", UNTAGGED_SQL),
             [],
             |row| row.get(0),
         )?;
 
         if count_without_tags > max_history_count {
             let to_delete_count = count_without_tags - max_history_count;
-
-            let mut stmt = conn.prepare(
-                "SELECT thumbnail_path, content
-                 FROM clipboard_history
-                 WHERE content_type = 'image'
-                   AND id IN (
-                       SELECT id FROM clipboard_history
-                       WHERE tags IS NULL OR tags = '' OR tags = 'null'
-                       ORDER BY created_at ASC
-                       LIMIT ?1
-                   )",
-            )?;
-            let rows = stmt.query_map(params![to_delete_count], |row| {
-                Ok((row.get::<_, Option<String>>(0)?, row.get::<_, String>(1)?))
-            })?;
-            for row in rows {
-                let (thumbnail_path, content) = row?;
-                if let Some(path) = Self::normalize_image_path(thumbnail_path, content) {
-                    image_paths_to_delete.insert(path);
-                }
-            }
+            let condition = format!(
+                "id IN (
+                    SELECT id FROM clipboard_history
+                    WHERE {}
+                    ORDER BY created_at ASC
+                    LIMIT ?1
+                )",
+                UNTAGGED_SQL
+            );
+            image_files.extend(Self::collect_image_files(&conn, &condition, params![to_delete_count])?);
 
             let rows_affected = conn.execute(
-                "DELETE FROM clipboard_history
-                 WHERE id IN (
-                     SELECT id FROM clipboard_history
-                     WHERE tags IS NULL OR tags = '' OR tags = 'null'
-                     ORDER BY created_at ASC
-                     LIMIT ?1
-                 )",
+                &format!("DELETE FROM clipboard_history WHERE {}", condition),
                 params![to_delete_count],
             )?;
             total_deleted += rows_affected as i64;
@@ -699,51 +1308,37 @@ impl Database {
         if auto_cleanup_days > 0 {
             let cutoff_date = chrono::Utc::now() - chrono::Duration::days(auto_cleanup_days);
             let cutoff_date_str = cutoff_date.to_rfc3339();
-
-            let mut stmt = conn.prepare(
-                "SELECT thumbnail_path, content
-                 FROM clipboard_history
-                 WHERE content_type = 'image'
-                   AND (tags IS NULL OR tags = '' OR tags = 'null')
-                   AND created_at < ?1",
-            )?;
-            let rows = stmt.query_map(params![&cutoff_date_str], |row| {
-                Ok((row.get::<_, Option<String>>(0)?, row.get::<_, String>(1)?))
-            })?;
-            for row in rows {
-                let (thumbnail_path, content) = row?;
-                if let Some(path) = Self::normalize_image_path(thumbnail_path, content) {
-                    image_paths_to_delete.insert(path);
-                }
-            }
+            let condition = format!("{} AND created_at < ?1", UNTAGGED_SQL);
+            image_files.extend(Self::collect_image_files(&conn, &condition, params![&cutoff_date_str])?);
 
             let rows_affected = conn.execute(
-                "DELETE FROM clipboard_history
-                 WHERE (tags IS NULL OR tags = '' OR tags = 'null')
-                   AND created_at < ?1",
+                &format!("DELETE FROM clipboard_history WHERE {}", condition),
                 params![&cutoff_date_str],
             )?;
             total_deleted += rows_affected as i64;
         }
 
+        let image_files = Self::unreferenced_image_files(&conn, image_files)?;
         drop(conn);
-        Self::delete_local_image_files(image_paths_to_delete);
+        Self::delete_local_image_files(image_files);
 
         Ok(total_deleted)
     }
 
-    /// 获取记录总数
+    /// 获取历史记录总数（不含片段，片段不占用历史记录数量上限）
     pub fn get_count(&self) -> Result<i64> {
-        let conn = self.conn.lock().unwrap();
-        let count: i64 = conn.query_row("SELECT COUNT(*) FROM clipboard_history", [], |row| {
-            row.get(0)
-        })?;
+        let conn = self.lock_conn()?;
+        let count: i64 = conn.query_row(
+            &format!("SELECT COUNT(*) FROM clipboard_history WHERE {}", NOT_SNIPPET_SQL),
+            [],
+            |row| row.get(0),
+        )?;
         Ok(count)
     }
 
     /// 获取设置
     pub fn get_settings(&self) -> Result<AppSettings> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.lock_conn()?;
 
         let mut settings = AppSettings::default();
 
@@ -767,6 +1362,11 @@ impl Database {
                         settings.auto_cleanup_days = v;
                     }
                 }
+                "image_gc_interval_hours" => {
+                    if let Ok(v) = value.parse() {
+                        settings.image_gc_interval_hours = v;
+                    }
+                }
                 "window_position" => settings.window_position = value,
                 "window_pos_x" => {
                     if !value.is_empty() {
@@ -782,6 +1382,20 @@ impl Database {
                         }
                     }
                 }
+                "window_width" => {
+                    if !value.is_empty() {
+                        if let Ok(v) = value.parse() {
+                            settings.window_width = Some(v);
+                        }
+                    }
+                }
+                "window_height" => {
+                    if !value.is_empty() {
+                        if let Ok(v) = value.parse() {
+                            settings.window_height = Some(v);
+                        }
+                    }
+                }
                 "copy_sound" => {
                     if let Ok(v) = value.parse() {
                         settings.copy_sound = v;
@@ -801,6 +1415,22 @@ impl Database {
                 "click_action" => settings.click_action = value,
                 "double_click_action" => settings.double_click_action = value,
                 "paste_shortcut" => settings.paste_shortcut = value,
+                "restore_clipboard_after_paste" => {
+                    if let Ok(v) = value.parse() {
+                        settings.restore_clipboard_after_paste = v;
+                    }
+                }
+                "restore_clipboard_delay_ms" => {
+                    if let Ok(v) = value.parse() {
+                        settings.restore_clipboard_delay_ms = v;
+                    }
+                }
+                "typing_char_delay_ms" => {
+                    if let Ok(v) = value.parse() {
+                        settings.typing_char_delay_ms = v;
+                    }
+                }
+                "typing_newline" => settings.typing_newline = value,
                 "hide_window_after_copy" => {
                     if let Ok(v) = value.parse() {
                         settings.hide_window_after_copy = v;
@@ -839,6 +1469,23 @@ impl Database {
                 }
                 "number_key_shortcut" => settings.number_key_shortcut = value,
                 "pin_shortcut" => settings.pin_shortcut = value,
+                "paste_next_shortcut" => settings.paste_next_shortcut = value,
+                "sensitive_action" => settings.sensitive_action = value,
+                "sensitive_expire_minutes" => {
+                    if let Ok(v) = value.parse() {
+                        settings.sensitive_expire_minutes = v;
+                    }
+                }
+                "sensitive_patterns" => {
+                    if let Ok(v) = serde_json::from_str(&value) {
+                        settings.sensitive_patterns = v;
+                    }
+                }
+                "ignored_apps" => {
+                    if let Ok(v) = serde_json::from_str(&value) {
+                        settings.ignored_apps = v;
+                    }
+                }
                 _ => {}
             }
         }
@@ -848,20 +1495,27 @@ impl Database {
 
     /// 保存设置
     pub fn save_settings(&self, settings: &AppSettings) -> Result<()> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.lock_conn()?;
 
         let settings_to_save = vec![
             ("max_history_count", settings.max_history_count.to_string()),
             ("auto_cleanup_days", settings.auto_cleanup_days.to_string()),
+            ("image_gc_interval_hours", settings.image_gc_interval_hours.to_string()),
             ("window_position", settings.window_position.clone()),
             ("window_pos_x", settings.window_pos_x.map(|v| v.to_string()).unwrap_or_default()),
             ("window_pos_y", settings.window_pos_y.map(|v| v.to_string()).unwrap_or_default()),
+            ("window_width", settings.window_width.map(|v| v.to_string()).unwrap_or_default()),
+            ("window_height", settings.window_height.map(|v| v.to_string()).unwrap_or_default()),
             ("copy_sound", settings.copy_sound.to_string()),
             ("search_position", settings.search_position.clone()),
             ("focus_search_on_activate", settings.focus_search_on_activate.to_string()),
             ("click_action", settings.click_action.clone()),
             ("double_click_action", settings.double_click_action.clone()),
             ("paste_shortcut", settings.paste_shortcut.clone()),
+            ("restore_clipboard_after_paste", settings.restore_clipboard_after_paste.to_string()),
+            ("restore_clipboard_delay_ms", settings.restore_clipboard_delay_ms.to_string()),
+            ("typing_char_delay_ms", settings.typing_char_delay_ms.to_string()),
+            ("typing_newline", settings.typing_newline.clone()),
             ("hide_window_after_copy", settings.hide_window_after_copy.to_string()),
             ("image_ocr", settings.image_ocr.to_string()),
             ("copy_as_plain_text", settings.copy_as_plain_text.to_string()),
@@ -872,6 +1526,17 @@ impl Database {
             ("auto_start", settings.auto_start.to_string()),
             ("number_key_shortcut", settings.number_key_shortcut.clone()),
             ("pin_shortcut", settings.pin_shortcut.clone()),
+            ("paste_next_shortcut", settings.paste_next_shortcut.clone()),
+            ("sensitive_action", settings.sensitive_action.clone()),
+            ("sensitive_expire_minutes", settings.sensitive_expire_minutes.to_string()),
+            (
+                "sensitive_patterns",
+                serde_json::to_string(&settings.sensitive_patterns).unwrap_or_else(|_| "[]".to_string()),
+            ),
+            (
+                "ignored_apps",
+                serde_json::to_string(&settings.ignored_apps).unwrap_or_else(|_| "[]".to_string()),
+            ),
         ];
 
         for (key, value) in settings_to_save {
@@ -883,4 +1548,110 @@ impl Database {
 
         Ok(())
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clipboard::ClipboardManager;
+    use chrono::Utc;
+
+    fn database_with(texts: &[&str]) -> (tempfile::TempDir, Database) {
+        let dir = tempfile::tempdir().unwrap();
+        let database = Database::new(dir.path().to_path_buf()).unwrap();
+        for text in texts {
+            let item = ClipboardItem {
+                id: 0,
+                content_type: ClipboardContentType::Text,
+                content: text.to_string(),
+                created_at: Utc::now(),
+                content_hash: ClipboardManager::content_hash(text, None),
+                text_content: None,
+                metadata: None,
+                file_paths: None,
+                thumbnail_path: None,
+                tags: None,
+                is_sensitive: false,
+                expires_at: None,
+                source_app: None,
+            };
+            database.add_clipboard_item(&item, true, false).unwrap();
+        }
+        (dir, database)
+    }
+
+    fn search(database: &Database, keywords: &[&str]) -> Vec<String> {
+        let request = AdvancedSearchRequest {
+            keywords: keywords.iter().map(|k| k.to_string()).collect(),
+            tags: vec![],
+            types: vec![],
+            limit: None,
+            offset: None,
+            source_apps: vec![],
+        };
+        let mut contents: Vec<String> = database
+            .search_history_advanced(&request)
+            .unwrap()
+            .into_iter()
+            .map(|result| result.item.content)
+            .collect();
+        contents.sort();
+        contents
+    }
+
+    #[test]
+    fn search_matches_long_and_short_keywords() {
+        let (_dir, database) = database_with(&["hello-world: a*b", "50% off", "500 off"]);
+
+        assert_eq!(search(&database, &["o-w"]), vec!["hello-world: a*b"]);
+        assert_eq!(search(&database, &["LO"]), vec!["hello-world: a*b"]);
+        assert_eq!(search(&database, &["0%"]), vec!["50% off"]);
+        assert_eq!(search(&database, &["off", "50"]), vec!["50% off", "500 off"]);
+        assert_eq!(search(&database, &["off", "0%"]), vec!["50% off"]);
+    }
+
+    #[test]
+    fn search_keywords_with_fts_syntax() {
+        let (_dir, database) = database_with(&["hello-world: a*b", "say \"hi\" OR NOT"]);
+
+        assert_eq!(search(&database, &["world: a*"]), vec!["hello-world: a*b"]);
+        assert_eq!(search(&database, &["\"hi\" OR"]), vec!["say \"hi\" OR NOT"]);
+        assert_eq!(search(&database, &["NOT"]), vec!["say \"hi\" OR NOT"]);
+        assert!(search(&database, &["a*c"]).is_empty());
+    }
+
+    #[test]
+    fn search_matches_pinyin_and_initials() {
+        let (_dir, database) = database_with(&["复制粘贴", "中文", "fuzz"]);
+
+        assert_eq!(search(&database, &["fuzhi"]), vec!["复制粘贴"]);
+        assert_eq!(search(&database, &["fz"]), vec!["复制粘贴"]);
+        assert_eq!(search(&database, &["FZZT"]), vec!["复制粘贴"]);
+        assert_eq!(search(&database, &["zhantie"]), vec!["复制粘贴"]);
+        assert_eq!(search(&database, &["fu制"]), vec!["复制粘贴"]);
+        assert_eq!(search(&database, &["复制"]), vec!["复制粘贴"]);
+        assert_eq!(search(&database, &["zw"]), vec!["中文"]);
+        assert_eq!(search(&database, &["fuz"]), vec!["fuzz", "复制粘贴"]);
+        assert!(search(&database, &["fuzhu"]).is_empty());
+    }
+
+    #[test]
+    fn search_results_carry_snippet_and_highlights() {
+        let (_dir, database) = database_with(&["😀 emoji then keyword"]);
+        let request = AdvancedSearchRequest {
+            keywords: vec!["keyword".to_string()],
+            tags: vec![],
+            types: vec![],
+            limit: None,
+            offset: None,
+            source_apps: vec![],
+        };
+        let results = database.search_history_advanced(&request).unwrap();
+        assert_eq!(results.len(), 1);
+        assert!(results[0].score.is_some());
+        assert_eq!(results[0].snippet.as_deref(), Some("😀 emoji then keyword"));
+        assert_eq!(
+            results[0].highlights.iter().map(|r| (r.start, r.end)).collect::<Vec<_>>(),
+            vec![(14, 21)]
+        );
+    }
+}
@@ -0,0 +1,325 @@
+//! 片段模板引擎
+//! 在粘贴前展开片段正文中的占位符：
+//! - `{{date}}` / `{{date:%Y-%m-%d}}`: 当前时间（chrono 格式，默认 `%Y-%m-%d`）
+//! - `{{clipboard}}`: 当前剪贴板文本
+//! - `{{cursor}}`: 粘贴后光标停留的位置（最多一个）
+//! - `{{input:Name}}`: 粘贴时由用户填写的值
+
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+
+const OPEN: &str = "{{";
+const CLOSE: &str = "}}";
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// 模板错误（offset 为占位符在正文中的字符下标）
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TemplateError {
+    /// 不认识的占位符
+    UnknownPlaceholder { placeholder: String, offset: usize },
+    /// `{{` 没有对应的 `}}`
+    Unterminated { offset: usize },
+    /// 日期格式无效
+    InvalidDateFormat { format: String, offset: usize },
+    /// `{{input:}}` 缺少名称
+    EmptyInputName { offset: usize },
+    /// 没有提供 `{{input:Name}}` 的值
+    MissingInput { name: String, offset: usize },
+    /// `{{cursor}}` 出现了多次
+    DuplicateCursor { offset: usize },
+    /// 使用了 `{{clipboard}}` 但剪贴板中没有文本
+    ClipboardUnavailable { offset: usize },
+    /// 读取片段、写入剪贴板等非模板错误
+    Other { message: String },
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownPlaceholder { placeholder, offset } => {
+                write!(f, "未知占位符 {{{{{}}}}}（位置 {}）", placeholder, offset)
+            }
+            Self::Unterminated { offset } => write!(f, "占位符未闭合（位置 {}）", offset),
+            Self::InvalidDateFormat { format, offset } => {
+                write!(f, "无效的日期格式 \"{}\"（位置 {}）", format, offset)
+            }
+            Self::EmptyInputName { offset } => write!(f, "input 占位符缺少名称（位置 {}）", offset),
+            Self::MissingInput { name, offset } => {
+                write!(f, "缺少输入值 \"{}\"（位置 {}）", name, offset)
+            }
+            Self::DuplicateCursor { offset } => {
+                write!(f, "cursor 占位符只能出现一次（位置 {}）", offset)
+            }
+            Self::ClipboardUnavailable { offset } => {
+                write!(f, "剪贴板中没有可用的文本（位置 {}）", offset)
+            }
+            Self::Other { message } => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+impl From<String> for TemplateError {
+    fn from(message: String) -> Self {
+        Self::Other { message }
+    }
+}
+
+/// 解析后的模板片段
+#[derive(Debug, Clone, PartialEq)]
+enum Segment<'a> {
+    Literal(&'a str),
+    Date { format: &'a str, offset: usize },
+    Clipboard { offset: usize },
+    Cursor,
+    Input { name: &'a str, offset: usize },
+}
+
+/// 展开时需要的上下文
+#[derive(Debug, Clone)]
+pub struct TemplateContext {
+    pub now: DateTime<Local>,
+    /// 当前剪贴板文本
+    pub clipboard: Option<String>,
+    /// `{{input:Name}}` 的值
+    pub inputs: HashMap<String, String>,
+}
+
+/// 展开结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expanded {
+    pub text: String,
+    /// `{{cursor}}` 之后的字符数（粘贴后需要向左移动光标的次数）
+    pub cursor_offset: Option<usize>,
+}
+
+/// 日期格式是否有效
+fn is_valid_date_format(format: &str) -> bool {
+    StrftimeItems::new(format).all(|item| !matches!(item, Item::Error))
+}
+
+/// 解析模板
+fn parse(body: &str) -> Result<Vec<Segment<'_>>, TemplateError> {
+    let mut segments = Vec::new();
+    let mut rest = body;
+    let mut consumed_chars = 0;
+    let mut has_cursor = false;
+
+    while let Some(start) = rest.find(OPEN) {
+        if start > 0 {
+            segments.push(Segment::Literal(&rest[..start]));
+        }
+        let offset = consumed_chars + rest[..start].chars().count();
+
+        let after_open = &rest[start + OPEN.len()..];
+        let end = after_open
+            .find(CLOSE)
+            .ok_or(TemplateError::Unterminated { offset })?;
+        let raw = &after_open[..end];
+        let placeholder = raw.trim();
+
+        let (name, argument) = match placeholder.split_once(':') {
+            Some((name, argument)) => (name.trim(), Some(argument)),
+            None => (placeholder, None),
+        };
+
+        let segment = match (name, argument) {
+            ("date", format) => {
+                let format = format.unwrap_or(DEFAULT_DATE_FORMAT);
+                if !is_valid_date_format(format) {
+                    return Err(TemplateError::InvalidDateFormat {
+                        format: format.to_string(),
+                        offset,
+                    });
+                }
+                Segment::Date { format, offset }
+            }
+            ("clipboard", None) => Segment::Clipboard { offset },
+            ("cursor", None) => {
+                if has_cursor {
+                    return Err(TemplateError::DuplicateCursor { offset });
+                }
+                has_cursor = true;
+                Segment::Cursor
+            }
+            ("input", Some(input_name)) => {
+                let input_name = input_name.trim();
+                if input_name.is_empty() {
+                    return Err(TemplateError::EmptyInputName { offset });
+                }
+                Segment::Input {
+                    name: input_name,
+                    offset,
+                }
+            }
+            _ => {
+                return Err(TemplateError::UnknownPlaceholder {
+                    placeholder: placeholder.to_string(),
+                    offset,
+                })
+            }
+        };
+        segments.push(segment);
+
+        let placeholder_len = OPEN.len() + end + CLOSE.len();
+        consumed_chars = offset + rest[start..start + placeholder_len].chars().count();
+        rest = &rest[start + placeholder_len..];
+    }
+
+    if !rest.is_empty() {
+        segments.push(Segment::Literal(rest));
+    }
+    Ok(segments)
+}
+
+/// 校验模板并返回需要用户填写的输入项（按首次出现顺序去重）
+pub fn input_names(body: &str) -> Result<Vec<String>, TemplateError> {
+    let mut names: Vec<String> = Vec::new();
+    for segment in parse(body)? {
+        if let Segment::Input { name, .. } = segment {
+            if !names.iter().any(|n| n == name) {
+                names.push(name.to_string());
+            }
+        }
+    }
+    Ok(names)
+}
+
+/// 模板是否需要读取当前剪贴板
+pub fn uses_clipboard(body: &str) -> Result<bool, TemplateError> {
+    Ok(parse(body)?
+        .iter()
+        .any(|segment| matches!(segment, Segment::Clipboard { .. })))
+}
+
+/// 展开模板
+pub fn expand(body: &str, context: &TemplateContext) -> Result<Expanded, TemplateError> {
+    let mut text = String::with_capacity(body.len());
+    let mut cursor_at: Option<usize> = None;
+
+    for segment in parse(body)? {
+        match segment {
+            Segment::Literal(literal) => text.push_str(literal),
+            Segment::Date { format, .. } => text.push_str(&context.now.format(format).to_string()),
+            Segment::Clipboard { offset } => {
+                let clipboard = context
+                    .clipboard
+                    .as_deref()
+                    .ok_or(TemplateError::ClipboardUnavailable { offset })?;
+                text.push_str(clipboard);
+            }
+            Segment::Cursor => cursor_at = Some(text.chars().count()),
+            Segment::Input { name, offset } => {
+                let value = context.inputs.get(name).ok_or_else(|| TemplateError::MissingInput {
+                    name: name.to_string(),
+                    offset,
+                })?;
+                text.push_str(value);
+            }
+        }
+    }
+
+    let cursor_offset = cursor_at.map(|at| text.chars().count() - at);
+    Ok(Expanded { text, cursor_offset })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn context() -> TemplateContext {
+        TemplateContext {
+            now: Local.with_ymd_and_hms(2024, 3, 5, 9, 7, 0).unwrap(),
+            clipboard: Some("剪贴板".to_string()),
+            inputs: HashMap::from([("Name".to_string(), "张三".to_string())]),
+        }
+    }
+
+    fn error(body: &str) -> TemplateError {
+        expand(body, &context()).unwrap_err()
+    }
+
+    #[test]
+    fn expands_placeholders() {
+        let expanded = expand("{{date}} {{ date:%H:%M }} {{clipboard}} {{input: Name }}", &context()).unwrap();
+        assert_eq!(expanded.text, "2024-03-05 09:07 剪贴板 张三");
+        assert_eq!(expanded.cursor_offset, None);
+        assert_eq!(expand("没有占位符", &context()).unwrap().text, "没有占位符");
+    }
+
+    #[test]
+    fn cursor_offset_counts_characters_after_cursor() {
+        let expanded = expand("前{{cursor}}后缀😀", &context()).unwrap();
+        assert_eq!(expanded.text, "前后缀😀");
+        assert_eq!(expanded.cursor_offset, Some(3));
+        assert_eq!(expand("结尾{{cursor}}", &context()).unwrap().cursor_offset, Some(0));
+    }
+
+    #[test]
+    fn errors_report_character_offsets() {
+        // 占位符前的多字节字符按字符计数
+        assert_eq!(
+            error("你好 {{nope}}"),
+            TemplateError::UnknownPlaceholder {
+                placeholder: "nope".to_string(),
+                offset: 3,
+            }
+        );
+        assert_eq!(error("😀 {{date"), TemplateError::Unterminated { offset: 2 });
+        assert_eq!(
+            error("日期 {{date:%Q}}"),
+            TemplateError::InvalidDateFormat {
+                format: "%Q".to_string(),
+                offset: 3,
+            }
+        );
+        assert_eq!(error("{{input: }}"), TemplateError::EmptyInputName { offset: 0 });
+        assert_eq!(error("{{cursor}}é{{cursor}}"), TemplateError::DuplicateCursor { offset: 11 });
+        assert_eq!(
+            error("名字：{{input:Age}}"),
+            TemplateError::MissingInput {
+                name: "Age".to_string(),
+                offset: 3,
+            }
+        );
+
+        let no_clipboard = TemplateContext {
+            clipboard: None,
+            ..context()
+        };
+        assert_eq!(
+            expand("粘贴 {{clipboard}}", &no_clipboard).unwrap_err(),
+            TemplateError::ClipboardUnavailable { offset: 3 }
+        );
+        assert_eq!(
+            TemplateError::from("写入剪贴板失败".to_string()),
+            TemplateError::Other {
+                message: "写入剪贴板失败".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn errors_serialize_with_kind() {
+        let json = serde_json::to_value(TemplateError::Unterminated { offset: 4 }).unwrap();
+        assert_eq!(json, serde_json::json!({ "kind": "unterminated", "offset": 4 }));
+        assert_eq!(TemplateError::Unterminated { offset: 4 }.to_string(), "占位符未闭合（位置 4）");
+    }
+
+    #[test]
+    fn input_names_are_deduplicated_in_order() {
+        assert_eq!(
+            input_names("{{input:B}} {{input:A}} {{ input: B }}").unwrap(),
+            vec!["B".to_string(), "A".to_string()]
+        );
+        assert!(input_names("{{unknown}}").is_err());
+        assert!(uses_clipboard("x {{clipboard}}").unwrap());
+        assert!(!uses_clipboard("{{date}}").unwrap());
+    }
+}
@@ -79,8 +79,8 @@ fn create_tray_menu(
     Ok(menu)
 }
 
-/// 显示设置窗口
-fn show_settings_window(app: &tauri::AppHandle) {
+/// 显示设置窗口（窗口已关闭时重新创建）
+pub fn show_settings_window(app: &tauri::AppHandle) {
     if let Some(window) = app.get_webview_window("main") {
         // 如果窗口已存在，显示并聚焦
         let _ = window.show();
@@ -0,0 +1,264 @@
+//! 模拟键盘输入文本（"输入粘贴"）
+//! 远程桌面、虚拟机控制台和部分网页表单会屏蔽剪贴板粘贴，此时逐字输入文本代替粘贴按键。
+//! 输入过程中可随时通过取消标记中止（由全局 Escape 快捷键设置）。
+
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+/// 字符间隔的上限（毫秒）
+pub const MAX_CHAR_DELAY_MS: u64 = 1000;
+
+/// 换行使用的按键
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NewlineMode {
+    Enter,
+    /// 聊天工具等 Enter 会直接发送的程序中使用 Shift+Enter 换行
+    ShiftEnter,
+}
+
+impl NewlineMode {
+    /// 解析设置中的值（未知值按 Enter 处理）
+    pub fn from_setting(value: &str) -> Self {
+        match value {
+            "shift_enter" => Self::ShiftEnter,
+            _ => Self::Enter,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Enter => "enter",
+            Self::ShiftEnter => "shift_enter",
+        }
+    }
+}
+
+/// 输入参数
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TypingOptions {
+    /// 每个字符之间的间隔，为 0 时整段输入
+    pub char_delay: Duration,
+    pub newline: NewlineMode,
+}
+
+impl TypingOptions {
+    /// 由设置值构造（间隔超过上限时按上限处理）
+    pub fn from_settings(char_delay_ms: u64, newline: &str) -> Self {
+        Self {
+            char_delay: Duration::from_millis(char_delay_ms.min(MAX_CHAR_DELAY_MS)),
+            newline: NewlineMode::from_setting(newline),
+        }
+    }
+}
+
+/// 文本拆分后的输入步骤
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step<'a> {
+    /// 不含换行的一段文本
+    Text(&'a str),
+    /// 一次换行（\r\n、\n、\r 都算一次）
+    Newline,
+}
+
+/// 按换行拆分文本
+pub fn steps(text: &str) -> Vec<Step<'_>> {
+    let mut steps = Vec::new();
+    let mut rest = text;
+    while let Some(index) = rest.find(['\r', '\n']) {
+        if index > 0 {
+            steps.push(Step::Text(&rest[..index]));
+        }
+        steps.push(Step::Newline);
+        let skip = if rest[index..].starts_with("\r\n") { 2 } else { 1 };
+        rest = &rest[index + skip..];
+    }
+    if !rest.is_empty() {
+        steps.push(Step::Text(rest));
+    }
+    steps
+}
+
+/// 实际发送按键的后端
+pub trait TypingBackend {
+    /// 输入一段不含换行的文本
+    fn text(&mut self, text: &str) -> Result<(), String>;
+    /// 按一次换行键
+    fn newline(&mut self, mode: NewlineMode) -> Result<(), String>;
+}
+
+/// 输入结果
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TypingOutcome {
+    /// 已输入的字符数（换行算一个字符）
+    pub typed_chars: usize,
+    /// 是否在输入完成前被取消
+    pub cancelled: bool,
+}
+
+/// 输入文本，每输入一个字符（间隔为 0 时每段文本）前检查取消标记
+pub fn type_text<B: TypingBackend>(
+    backend: &mut B,
+    text: &str,
+    options: TypingOptions,
+    cancel: &AtomicBool,
+) -> Result<TypingOutcome, String> {
+    let mut outcome = TypingOutcome::default();
+    let mut first = true;
+
+    for step in steps(text) {
+        match step {
+            Step::Newline => {
+                if !pause(options.char_delay, &mut first, cancel) {
+                    return Ok(TypingOutcome { cancelled: true, ..outcome });
+                }
+                backend.newline(options.newline)?;
+                outcome.typed_chars += 1;
+            }
+            Step::Text(segment) if options.char_delay.is_zero() => {
+                if cancel.load(Ordering::SeqCst) {
+                    return Ok(TypingOutcome { cancelled: true, ..outcome });
+                }
+                backend.text(segment)?;
+                outcome.typed_chars += segment.chars().count();
+            }
+            Step::Text(segment) => {
+                for (index, ch) in segment.char_indices() {
+                    if !pause(options.char_delay, &mut first, cancel) {
+                        return Ok(TypingOutcome { cancelled: true, ..outcome });
+                    }
+                    backend.text(&segment[index..index + ch.len_utf8()])?;
+                    outcome.typed_chars += 1;
+                }
+            }
+        }
+    }
+
+    Ok(outcome)
+}
+
+// 两次输入之间等待字符间隔（第一个字符前不等待），返回 false 表示已取消
+fn pause(delay: Duration, first: &mut bool, cancel: &AtomicBool) -> bool {
+    if !std::mem::take(first) && !delay.is_zero() {
+        std::thread::sleep(delay);
+    }
+    !cancel.load(Ordering::SeqCst)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 记录按键的后端，可在输入若干次后设置取消标记
+    struct FakeBackend<'a> {
+        keys: Vec<String>,
+        cancel_after: Option<(usize, &'a AtomicBool)>,
+    }
+
+    impl<'a> FakeBackend<'a> {
+        fn new() -> Self {
+            Self { keys: Vec::new(), cancel_after: None }
+        }
+
+        fn cancelling(after: usize, cancel: &'a AtomicBool) -> Self {
+            Self { keys: Vec::new(), cancel_after: Some((after, cancel)) }
+        }
+
+        fn record(&mut self, key: String) {
+            self.keys.push(key);
+            if let Some((after, cancel)) = self.cancel_after {
+                if self.keys.len() == after {
+                    cancel.store(true, Ordering::SeqCst);
+                }
+            }
+        }
+    }
+
+    impl TypingBackend for FakeBackend<'_> {
+        fn text(&mut self, text: &str) -> Result<(), String> {
+            self.record(text.to_string());
+            Ok(())
+        }
+
+        fn newline(&mut self, mode: NewlineMode) -> Result<(), String> {
+            self.record(format!("<{}>", mode.as_str()));
+            Ok(())
+        }
+    }
+
+    fn options(char_delay_ms: u64, newline: NewlineMode) -> TypingOptions {
+        TypingOptions {
+            char_delay: Duration::from_millis(char_delay_ms),
+            newline,
+        }
+    }
+
+    #[test]
+    fn steps_normalize_newlines() {
+        assert_eq!(
+            steps("a\r\nb\rc\nd"),
+            vec![
+                Step::Text("a"),
+                Step::Newline,
+                Step::Text("b"),
+                Step::Newline,
+                Step::Text("c"),
+                Step::Newline,
+                Step::Text("d"),
+            ]
+        );
+        assert_eq!(steps("\n\r\n\r"), vec![Step::Newline; 3]);
+        assert_eq!(steps("\r\r\n"), vec![Step::Newline; 2]);
+        assert!(steps("").is_empty());
+    }
+
+    #[test]
+    fn zero_delay_types_whole_segments() {
+        let mut backend = FakeBackend::new();
+        let cancel = AtomicBool::new(false);
+        let outcome = type_text(&mut backend, "你好 ab\r\ncd", options(0, NewlineMode::Enter), &cancel).unwrap();
+
+        assert_eq!(backend.keys, vec!["你好 ab", "<enter>", "cd"]);
+        assert_eq!(outcome, TypingOutcome { typed_chars: 8, cancelled: false });
+    }
+
+    #[test]
+    fn delayed_typing_sends_each_char_with_shift_enter() {
+        let mut backend = FakeBackend::new();
+        let cancel = AtomicBool::new(false);
+        let outcome = type_text(&mut backend, "a😀\nb", options(1, NewlineMode::ShiftEnter), &cancel).unwrap();
+
+        assert_eq!(backend.keys, vec!["a", "😀", "<shift_enter>", "b"]);
+        assert_eq!(outcome, TypingOutcome { typed_chars: 4, cancelled: false });
+    }
+
+    #[test]
+    fn cancellation_stops_midway() {
+        let cancel = AtomicBool::new(false);
+        let mut backend = FakeBackend::cancelling(3, &cancel);
+        let outcome = type_text(&mut backend, "abc\ndef", options(1, NewlineMode::Enter), &cancel).unwrap();
+        assert_eq!(backend.keys, vec!["a", "b", "c"]);
+        assert_eq!(outcome, TypingOutcome { typed_chars: 3, cancelled: true });
+
+        let cancel = AtomicBool::new(false);
+        let mut backend = FakeBackend::cancelling(2, &cancel);
+        let outcome = type_text(&mut backend, "ab\ncd\nef", options(0, NewlineMode::Enter), &cancel).unwrap();
+        assert_eq!(backend.keys, vec!["ab", "<enter>"]);
+        assert_eq!(outcome, TypingOutcome { typed_chars: 3, cancelled: true });
+
+        let cancel = AtomicBool::new(true);
+        let mut backend = FakeBackend::new();
+        let outcome = type_text(&mut backend, "abc", options(0, NewlineMode::Enter), &cancel).unwrap();
+        assert!(backend.keys.is_empty());
+        assert_eq!(outcome, TypingOutcome { typed_chars: 0, cancelled: true });
+    }
+
+    #[test]
+    fn options_from_settings_clamp_delay() {
+        let options = TypingOptions::from_settings(MAX_CHAR_DELAY_MS + 1, "shift_enter");
+        assert_eq!(options.char_delay, Duration::from_millis(MAX_CHAR_DELAY_MS));
+        assert_eq!(options.newline, NewlineMode::ShiftEnter);
+        assert_eq!(TypingOptions::from_settings(5, "other").newline, NewlineMode::Enter);
+    }
+}
@@ -0,0 +1,258 @@
+//! 窗口定位的几何计算
+//! 所有坐标和尺寸都是物理像素（与 Tauri 的 PhysicalPosition/PhysicalSize 一致），
+//! 逻辑尺寸只在换算窗口大小时出现。这里只做纯计算，不依赖 Tauri，便于直接验证多显示器/混合 DPI 布局。
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// 矩形区域（物理像素）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl Rect {
+    pub fn new(x: i32, y: i32, width: i32, height: i32) -> Self {
+        Self { x, y, width, height }
+    }
+
+    pub fn right(&self) -> i32 {
+        self.x + self.width
+    }
+
+    pub fn bottom(&self) -> i32 {
+        self.y + self.height
+    }
+
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        x >= self.x && x < self.right() && y >= self.y && y < self.bottom()
+    }
+
+    /// 点到矩形的距离平方（点在矩形内时为 0）
+    fn distance_squared(&self, x: i32, y: i32) -> i64 {
+        let dx = (self.x - x).max(0).max(x - (self.right() - 1)) as i64;
+        let dy = (self.y - y).max(0).max(y - (self.bottom() - 1)) as i64;
+        dx * dx + dy * dy
+    }
+}
+
+/// 显示器
+#[derive(Debug, Clone, PartialEq)]
+pub struct Monitor {
+    /// 显示器名称（部分平台没有）
+    pub name: Option<String>,
+    /// 显示器区域
+    pub bounds: Rect,
+    /// 工作区（去掉任务栏、Dock 等）
+    pub work_area: Rect,
+    /// 缩放比例（物理像素 / 逻辑像素）
+    pub scale_factor: f64,
+}
+
+impl Monitor {
+    /// 记住窗口位置时使用的显示器标识：优先使用名称，没有名称时使用显示器区域
+    pub fn key(&self) -> String {
+        match &self.name {
+            Some(name) if !name.is_empty() => name.clone(),
+            _ => format!(
+                "{},{} {}x{}",
+                self.bounds.x, self.bounds.y, self.bounds.width, self.bounds.height
+            ),
+        }
+    }
+
+    /// 逻辑尺寸换算为该显示器上的物理尺寸
+    pub fn to_physical(&self, size: (f64, f64)) -> (i32, i32) {
+        (
+            (size.0 * self.scale_factor).round() as i32,
+            (size.1 * self.scale_factor).round() as i32,
+        )
+    }
+}
+
+/// 每个显示器上记住的窗口位置（key 为 `Monitor::key`）
+pub type MonitorPositions = HashMap<String, SavedPosition>;
+
+/// 记住的窗口位置（物理像素，屏幕坐标）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SavedPosition {
+    pub x: i32,
+    pub y: i32,
+}
+
+/// 找到包含该点的显示器；点不在任何显示器内时取最近的显示器
+pub fn monitor_at(monitors: &[Monitor], x: i32, y: i32) -> Option<&Monitor> {
+    monitors
+        .iter()
+        .find(|m| m.bounds.contains(x, y))
+        .or_else(|| monitors.iter().min_by_key(|m| m.bounds.distance_squared(x, y)))
+}
+
+/// 窗口当前所在的显示器（以窗口中心点判断）
+pub fn monitor_of_window(monitors: &[Monitor], position: (i32, i32), size: (i32, i32)) -> Option<&Monitor> {
+    monitor_at(monitors, position.0 + size.0 / 2, position.1 + size.1 / 2)
+}
+
+/// 将物理尺寸从一个缩放比例换算到另一个（窗口移到不同 DPI 的显示器后会按逻辑尺寸重新缩放）
+pub fn rescale_size(size: (i32, i32), from_scale: f64, to_scale: f64) -> (i32, i32) {
+    if from_scale <= 0.0 || to_scale <= 0.0 {
+        return size;
+    }
+    let ratio = to_scale / from_scale;
+    (
+        (size.0 as f64 * ratio).round() as i32,
+        (size.1 as f64 * ratio).round() as i32,
+    )
+}
+
+/// 将窗口尺寸限制在显示器工作区内，且不小于最小尺寸（工作区更小时以工作区为准）
+pub fn fit_size(monitor: &Monitor, size: (i32, i32), min_size: (i32, i32)) -> (i32, i32) {
+    let area = monitor.work_area;
+    (
+        size.0.max(min_size.0).min(area.width),
+        size.1.max(min_size.1).min(area.height),
+    )
+}
+
+/// 将窗口限制在显示器工作区内（窗口比工作区大时对齐左上角）
+pub fn clamp_to_work_area(monitor: &Monitor, position: (i32, i32), size: (i32, i32)) -> (i32, i32) {
+    let area = monitor.work_area;
+    let clamp_axis = |pos: i32, len: i32, start: i32, end: i32| {
+        if len >= end - start {
+            start
+        } else {
+            pos.clamp(start, end - len)
+        }
+    };
+    (
+        clamp_axis(position.0, size.0, area.x, area.right()),
+        clamp_axis(position.1, size.1, area.y, area.bottom()),
+    )
+}
+
+/// 在显示器工作区内居中
+pub fn center_in(monitor: &Monitor, size: (i32, i32)) -> (i32, i32) {
+    let area = monitor.work_area;
+    let position = (
+        area.x + (area.width - size.0) / 2,
+        area.y + (area.height - size.1) / 2,
+    );
+    clamp_to_work_area(monitor, position, size)
+}
+
+/// 在光标处显示：窗口左上角对齐光标，超出工作区时向内收
+pub fn position_at_cursor(monitor: &Monitor, cursor: (i32, i32), size: (i32, i32)) -> (i32, i32) {
+    clamp_to_work_area(monitor, cursor, size)
+}
+
+/// "记住位置"模式下的目标位置
+///
+/// 目标显示器上记住过位置时使用该位置，否则使用上一次的位置（仅当它位于目标显示器上），
+/// 都没有时居中。结果总是完整落在工作区内。
+pub fn remembered_position(
+    monitor: &Monitor,
+    saved: &MonitorPositions,
+    last_position: Option<(i32, i32)>,
+    size: (i32, i32),
+) -> (i32, i32) {
+    let position = saved
+        .get(&monitor.key())
+        .map(|p| (p.x, p.y))
+        .or_else(|| last_position.filter(|&(x, y)| monitor.bounds.contains(x, y)));
+    match position {
+        Some(position) => clamp_to_work_area(monitor, position, size),
+        None => center_in(monitor, size),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn monitor(name: &str, bounds: Rect, taskbar: i32, scale_factor: f64) -> Monitor {
+        Monitor {
+            name: Some(name.to_string()),
+            bounds,
+            work_area: Rect::new(bounds.x, bounds.y, bounds.width, bounds.height - taskbar),
+            scale_factor,
+        }
+    }
+
+    /// 1.0x 主显示器（1920x1080）左侧放一台 2.0x 的 4K 显示器，顶边对齐
+    fn mixed_dpi() -> Vec<Monitor> {
+        vec![
+            monitor("primary", Rect::new(0, 0, 1920, 1080), 40, 1.0),
+            monitor("left-4k", Rect::new(-3840, 0, 3840, 2160), 80, 2.0),
+        ]
+    }
+
+    #[test]
+    fn cursor_on_negative_offset_monitor() {
+        let monitors = mixed_dpi();
+        let target = monitor_at(&monitors, -100, 500).unwrap();
+        assert_eq!(target.name.as_deref(), Some("left-4k"));
+
+        // 800x600 逻辑尺寸在 2.0x 显示器上是 1600x1200 物理像素，靠右边缘时向内收
+        let size = fit_size(target, target.to_physical((800.0, 600.0)), (720, 600));
+        assert_eq!(size, (1600, 1200));
+        assert_eq!(position_at_cursor(target, (-100, 500), size), (-1600, 500));
+
+        // 从 1.0x 显示器移过去，物理尺寸按比例放大
+        assert_eq!(rescale_size((800, 600), 1.0, 2.0), (1600, 1200));
+        assert_eq!(center_in(target, size), (-2720, 440));
+    }
+
+    #[test]
+    fn cursor_on_monitor_boundary() {
+        let monitors = mixed_dpi();
+        // 右、下边界是开区间：x = 0 属于主显示器，x = -1 属于左侧显示器
+        assert_eq!(monitor_at(&monitors, 0, 0).unwrap().name.as_deref(), Some("primary"));
+        assert_eq!(monitor_at(&monitors, -1, 0).unwrap().name.as_deref(), Some("left-4k"));
+        // 主显示器下方的空白区域（只有 4K 显示器有这么高）取最近的显示器
+        assert_eq!(monitor_at(&monitors, 1000, 1500).unwrap().name.as_deref(), Some("primary"));
+        assert_eq!(monitor_at(&monitors, 10, 1500).unwrap().name.as_deref(), Some("left-4k"));
+
+        // 光标在主显示器最右下角的像素上，窗口完整落在工作区内
+        let primary = &monitors[0];
+        assert_eq!(position_at_cursor(primary, (1919, 1079), (800, 600)), (1120, 440));
+    }
+
+    #[test]
+    fn saved_size_larger_than_monitor_is_clamped() {
+        let monitors = mixed_dpi();
+        let primary = &monitors[0];
+        let min_size = primary.to_physical((360.0, 300.0));
+
+        // 在 4K 显示器上记住的 2400x1400 逻辑尺寸放不进 1.0x 主显示器的工作区
+        let size = fit_size(primary, primary.to_physical((2400.0, 1400.0)), min_size);
+        assert_eq!(size, (1920, 1040));
+        assert_eq!(center_in(primary, size), (0, 0));
+
+        // 同一尺寸在 2.0x 显示器上也超出工作区（3840x2080）
+        let left = &monitors[1];
+        assert_eq!(fit_size(left, left.to_physical((2400.0, 1400.0)), min_size), (3840, 2080));
+
+        // 工作区比最小尺寸还小时以工作区为准
+        let tiny = monitor("tiny", Rect::new(0, 0, 320, 240), 0, 1.0);
+        assert_eq!(fit_size(&tiny, (100, 100), min_size), (320, 240));
+    }
+
+    #[test]
+    fn remembered_position_per_monitor() {
+        let monitors = mixed_dpi();
+        let left = &monitors[1];
+        let mut saved = MonitorPositions::new();
+        saved.insert(left.key(), SavedPosition { x: -3000, y: 100 });
+
+        assert_eq!(remembered_position(left, &saved, None, (1600, 1200)), (-3000, 100));
+        // 主显示器上没有记住的位置，上次位置又不在主显示器上时居中
+        let primary = &monitors[0];
+        assert_eq!(
+            remembered_position(primary, &saved, Some((-3000, 100)), (800, 600)),
+            (560, 220)
+        );
+    }
+}
@@ -1,19 +1,25 @@
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 use tauri::Emitter;
 use tauri::Manager;
 
 use crate::models::AppSettings;
+use crate::paste_target::PasteTarget;
 use crate::platform;
 use crate::storage::Database;
+use crate::window_geometry::{self, Monitor, Rect, SavedPosition};
 
 /// 窗口配置常量
 const WINDOW_WIDTH: f64 = 800.0;
 const WINDOW_HEIGHT: f64 = 600.0;
+const MIN_WINDOW_WIDTH: f64 = 360.0;
+const MIN_WINDOW_HEIGHT: f64 = 300.0;
+// 拖动调整窗口大小时会连续触发 Resized，停止调整后再保存
+const RESIZE_SAVE_DELAY_MS: u64 = 500;
 // 保留一个很短的缓冲，避免瞬时焦点抖动导致误隐藏，同时让失焦隐藏更跟手。
 const BLUR_DELAY_MS: u64 = 20;
 const FOCUS_DELAY_MS: u64 = 50;
-const WINDOW_MARGIN: i32 = 100;
 
 /// 将 Tauri 错误转换为 String 的宏
 macro_rules! map_err {
@@ -35,43 +41,16 @@ impl PinMode {
     }
 }
 
-/// 计算窗口在屏幕内的有效位置
-/// 确保窗口至少有一部分在屏幕内（考虑边距）
-fn clamp_window_position(
-    x: i32,
-    y: i32,
-    win_width: i32,
-    win_height: i32,
-    screen_width: i32,
-    screen_height: i32,
-    margin: i32,
-) -> (i32, i32) {
-    let mut pos_x = x;
-    let mut pos_y = y;
-
-    // 边界检查：确保窗口至少有一部分在屏幕内
-    if pos_x + win_width < margin {
-        pos_x = margin - win_width;
-    }
-    if pos_x > screen_width - margin {
-        pos_x = screen_width - margin;
-    }
-    if pos_y + win_height < margin {
-        pos_y = margin - win_height;
-    }
-    if pos_y > screen_height - margin {
-        pos_y = screen_height - margin;
-    }
-
-    (pos_x, pos_y)
-}
-
 pub struct WindowManager {
     settings: Arc<Mutex<AppSettings>>,
     database: Arc<Database>,
     pin_mode: Arc<Mutex<PinMode>>,
     /// 标志位：是否有待执行的隐藏操作（用于取消点击窗口内部时的意外隐藏）
     pending_hide: Arc<Mutex<bool>>,
+    /// 窗口尺寸变化计数（用于合并连续的 Resized 事件）
+    resize_generation: Arc<AtomicU64>,
+    /// 粘贴目标：显示剪贴板窗口之前的前台窗口（粘贴前切换回该窗口）
+    paste_target: PasteTarget,
 }
 
 /// 状态引用集合（用于事件处理回调）
@@ -81,6 +60,7 @@ struct StateRefs {
     database: Arc<Database>,
     pin_mode: Arc<Mutex<PinMode>>,
     pending_hide: Arc<Mutex<bool>>,
+    resize_generation: Arc<AtomicU64>,
 }
 
 impl WindowManager {
@@ -90,6 +70,8 @@ impl WindowManager {
             database,
             pin_mode: Arc::new(Mutex::new(PinMode::Standard)),
             pending_hide: Arc::new(Mutex::new(false)),
+            resize_generation: Arc::new(AtomicU64::new(0)),
+            paste_target: PasteTarget::new(Arc::new(platform::SystemForegroundWindow)),
         }
     }
 
@@ -100,6 +82,7 @@ impl WindowManager {
             database: self.database.clone(),
             pin_mode: self.pin_mode.clone(),
             pending_hide: self.pending_hide.clone(),
+            resize_generation: self.resize_generation.clone(),
         }
     }
 
@@ -136,6 +119,24 @@ impl WindowManager {
         Ok(new_pinned)
     }
 
+    /// 显示剪贴板窗口之前的前台窗口（无法获取时为 None）
+    pub async fn previous_window(&self) -> Option<u64> {
+        self.paste_target.window().await
+    }
+
+    /// 记录当前前台窗口（在剪贴板窗口显示之前调用；不经过剪贴板窗口直接粘贴时也用它记录粘贴目标）
+    pub async fn remember_foreground_window(&self) {
+        self.paste_target.remember_foreground().await;
+    }
+
+    /// 不经过剪贴板窗口直接粘贴前记录粘贴目标（剪贴板窗口显示时保留打开前的窗口）
+    pub async fn remember_paste_target(&self, app: &tauri::AppHandle) {
+        let visible = app
+            .get_webview_window("clipboard")
+            .is_some_and(|window| window.is_visible().unwrap_or(false));
+        self.paste_target.remember_if_hidden(visible).await;
+    }
+
     pub async fn toggle_clipboard_window(&self, app: &tauri::AppHandle) -> Result<bool, String> {
         let label = "clipboard";
 
@@ -148,12 +149,14 @@ impl WindowManager {
                 map_err!(window.hide())?;
                 Ok(false)
             } else {
+                self.remember_foreground_window().await;
                 self.position_window(&window).await?;
                 map_err!(window.show())?;
                 map_err!(window.set_focus())?;
                 Ok(true)
             }
         } else {
+            self.remember_foreground_window().await;
             self.create_clipboard_window(app).await
         }
     }
@@ -172,10 +175,14 @@ impl WindowManager {
 
     pub async fn show_clipboard_window(&self, app: &tauri::AppHandle) -> Result<(), String> {
         if let Some(window) = app.get_webview_window("clipboard") {
+            if !map_err!(window.is_visible())? {
+                self.remember_foreground_window().await;
+            }
             self.position_window(&window).await?;
             map_err!(window.show())?;
             map_err!(window.set_focus())?;
         } else {
+            self.remember_foreground_window().await;
             self.create_clipboard_window(app).await?;
         }
         Ok(())
@@ -186,72 +193,106 @@ impl WindowManager {
         self.position_window(window).await
     }
 
+    /// 将窗口尺寸恢复为默认值
+    pub async fn reset_window_size(&self, app: &tauri::AppHandle) -> Result<(), String> {
+        let mut settings = self.settings.lock().await;
+        settings.window_width = None;
+        settings.window_height = None;
+        map_err!(self.database.save_settings(&settings))?;
+        drop(settings);
+
+        if let Some(window) = app.get_webview_window("clipboard") {
+            map_err!(window.set_size(tauri::Size::Logical(tauri::LogicalSize::new(
+                WINDOW_WIDTH,
+                WINDOW_HEIGHT
+            ))))?;
+            if map_err!(window.is_visible())? {
+                self.position_window(&window).await?;
+            }
+        }
+        Ok(())
+    }
+
     /// 根据设置定位窗口
+    ///
+    /// 目标显示器为光标所在的显示器（取不到光标位置时为上次位置所在的显示器，再退回主显示器），
+    /// 窗口尺寸按目标显示器的缩放比例换算，并恢复记住的窗口尺寸（不超过工作区），
+    /// 位置总是完整落在该显示器的工作区内。
     async fn position_window(&self, window: &tauri::WebviewWindow) -> Result<(), String> {
         let settings = self.settings.lock().await;
+        let mode = settings.window_position.clone();
+        let last_position = settings.window_pos_x.zip(settings.window_pos_y);
+        let saved_size = settings.window_width.zip(settings.window_height);
+        drop(settings);
 
-        match settings.window_position.as_str() {
-            "remember" => {
-                if let (Some(x), Some(y)) = (settings.window_pos_x, settings.window_pos_y) {
-                    let size = map_err!(window.outer_size())?;
-                    let screen = map_err!(window.primary_monitor())?;
-
-                    if let Some(screen) = screen {
-                        let (pos_x, pos_y) = clamp_window_position(
-                            x, y,
-                            size.width as i32, size.height as i32,
-                            screen.size().width as i32, screen.size().height as i32,
-                            WINDOW_MARGIN,
-                        );
-
-                        map_err!(window.set_position(tauri::Position::Physical(
-                            tauri::PhysicalPosition::new(pos_x, pos_y)
-                        )))?;
-                    } else {
-                        map_err!(window.center())?;
-                    }
-                } else {
-                    map_err!(window.center())?;
-                }
-            }
-            "center" => {
-                map_err!(window.center())?;
-            }
-            "cursor" => {
-                if let Ok((x, y)) = Self::get_cursor_position() {
-                    let size = map_err!(window.inner_size())?;
-                    let screen = map_err!(window.primary_monitor())?;
-
-                    if let Some(screen) = screen {
-                        let screen_width = screen.size().width as f64;
-                        let screen_height = screen.size().height as f64;
-                        let win_width = size.width as f64;
-                        let win_height = size.height as f64;
-
-                        let mut pos_x = x;
-                        let mut pos_y = y;
-
-                        if pos_x + win_width > screen_width {
-                            pos_x = screen_width - win_width;
-                        }
-                        if pos_y + win_height > screen_height {
-                            pos_y = screen_height - win_height;
-                        }
-
-                        map_err!(window.set_position(tauri::Position::Physical(
-                            tauri::PhysicalPosition::new(pos_x as i32, pos_y as i32)
-                        )))?;
-                    }
-                } else {
-                    map_err!(window.center())?;
-                }
-            }
-            _ => {
-                map_err!(window.center())?;
-            }
+        let monitors = Self::available_monitors(window)?;
+        let cursor = Self::get_cursor_position().map(|(x, y)| (x.round() as i32, y.round() as i32));
+        if let (Err(e), "cursor") = (&cursor, mode.as_str()) {
+            eprintln!("获取光标位置失败，窗口居中显示: {}", e);
         }
 
-        Ok(())
+        let anchor = cursor.ok().or(last_position);
+        let monitor = match anchor.and_then(|(x, y)| window_geometry::monitor_at(&monitors, x, y)) {
+            Some(monitor) => monitor.clone(),
+            None => match map_err!(window.primary_monitor())? {
+                Some(primary) => Self::to_geometry_monitor(&primary),
+                None => return map_err!(window.center()),
+            },
+        };
+
+        let current_size = map_err!(window.outer_size())?;
+        let current_size = (current_size.width as i32, current_size.height as i32);
+        let scale = monitor.scale_factor;
+        let size = match saved_size {
+            Some((width, height)) => monitor.to_physical((width as f64, height as f64)),
+            // 窗口移到缩放比例不同的显示器后会按逻辑尺寸重新缩放
+            None => window_geometry::rescale_size(current_size, map_err!(window.scale_factor())?, scale),
+        };
+        let min_size = monitor.to_physical((MIN_WINDOW_WIDTH, MIN_WINDOW_HEIGHT));
+        let size = window_geometry::fit_size(&monitor, size, min_size);
+        if saved_size.is_some() && size != current_size {
+            map_err!(window.set_size(tauri::Size::Physical(tauri::PhysicalSize::new(
+                size.0 as u32,
+                size.1 as u32
+            ))))?;
+        }
+
+        let (x, y) = match (mode.as_str(), cursor) {
+            ("remember", _) => {
+                let saved = self.database.get_window_positions().unwrap_or_default();
+                window_geometry::remembered_position(&monitor, &saved, last_position, size)
+            }
+            ("cursor", Ok(cursor)) => window_geometry::position_at_cursor(&monitor, cursor, size),
+            _ => window_geometry::center_in(&monitor, size),
+        };
+
+        map_err!(window.set_position(tauri::Position::Physical(
+            tauri::PhysicalPosition::new(x, y)
+        )))
+    }
+
+    /// 可用的显示器
+    fn available_monitors(window: &tauri::WebviewWindow) -> Result<Vec<Monitor>, String> {
+        let monitors = map_err!(window.available_monitors())?;
+        Ok(monitors.iter().map(Self::to_geometry_monitor).collect())
+    }
+
+    /// 转换为几何计算使用的显示器结构（物理像素）
+    fn to_geometry_monitor(monitor: &tauri::Monitor) -> Monitor {
+        let position = monitor.position();
+        let size = monitor.size();
+        let work_area = monitor.work_area();
+        Monitor {
+            name: monitor.name().cloned(),
+            bounds: Rect::new(position.x, position.y, size.width as i32, size.height as i32),
+            work_area: Rect::new(
+                work_area.position.x,
+                work_area.position.y,
+                work_area.size.width as i32,
+                work_area.size.height as i32,
+            ),
+            scale_factor: monitor.scale_factor(),
+        }
     }
 
     /// 保存窗口位置到设置
@@ -277,13 +318,21 @@ impl WindowManager {
 
     /// 构建剪贴板窗口
     async fn build_clipboard_window(&self, app: &tauri::AppHandle) -> Result<tauri::WebviewWindow, String> {
+        let (width, height) = {
+            let settings = self.settings.lock().await;
+            (
+                settings.window_width.map_or(WINDOW_WIDTH, f64::from),
+                settings.window_height.map_or(WINDOW_HEIGHT, f64::from),
+            )
+        };
         let window = tauri::webview::WebviewWindowBuilder::new(
             app,
             "clipboard",
             tauri::WebviewUrl::App("/clipboard".into())
         )
         .title("剪贴板历史")
-        .inner_size(WINDOW_WIDTH, WINDOW_HEIGHT)
+        .inner_size(width, height)
+        .min_inner_size(MIN_WINDOW_WIDTH, MIN_WINDOW_HEIGHT)
         .decorations(false)
         .resizable(true)
         .skip_taskbar(true)
@@ -313,6 +362,9 @@ impl WindowManager {
                 tauri::WindowEvent::Moved(_) => {
                     Self::handle_moved_event(state_refs.clone());
                 }
+                tauri::WindowEvent::Resized(size) => {
+                    Self::handle_resized_event(&app_handle, state_refs.clone(), *size);
+                }
                 _ => {}
             }
         });
@@ -359,6 +411,28 @@ impl WindowManager {
         });
     }
 
+    /// 处理窗口尺寸变化事件：停止调整一段时间后保存窗口尺寸
+    fn handle_resized_event(app: &tauri::AppHandle, state: StateRefs, size: tauri::PhysicalSize<u32>) {
+        // 最小化或隐藏时会报告 0 尺寸
+        if size.width == 0 || size.height == 0 {
+            return;
+        }
+
+        let app = app.clone();
+        let generation = state.resize_generation.fetch_add(1, Ordering::SeqCst) + 1;
+        tauri::async_runtime::spawn(async move {
+            tokio::time::sleep(tokio::time::Duration::from_millis(RESIZE_SAVE_DELAY_MS)).await;
+            if state.resize_generation.load(Ordering::SeqCst) != generation {
+                return;
+            }
+
+            let Some(window) = app.get_webview_window("clipboard") else { return };
+            if let Err(e) = Self::save_window_size_internal(&state, &window).await {
+                eprintln!("保存窗口尺寸失败: {}", e);
+            }
+        });
+    }
+
     async fn resolve_blur_hide_window(
         app: &tauri::AppHandle,
         state: &StateRefs,
@@ -393,7 +467,31 @@ impl WindowManager {
         let _ = app.emit("clipboard-window-blur", ());
     }
 
+    /// 保存窗口尺寸（逻辑像素，换到不同缩放比例的显示器时保持视觉大小）
+    async fn save_window_size_internal(state: &StateRefs, window: &tauri::WebviewWindow) -> Result<(), String> {
+        let scale = map_err!(window.scale_factor())?;
+        let size = map_err!(window.inner_size())?.to_logical::<f64>(scale);
+        let width = size.width.round();
+        let height = size.height.round();
+        // 默认尺寸不单独记录，之后修改默认尺寸时仍能生效
+        let (width, height) = if width == WINDOW_WIDTH && height == WINDOW_HEIGHT {
+            (None, None)
+        } else {
+            (Some(width as u32), Some(height as u32))
+        };
+
+        let mut settings = state.settings.lock().await;
+        if settings.window_width == width && settings.window_height == height {
+            return Ok(());
+        }
+        settings.window_width = width;
+        settings.window_height = height;
+        map_err!(state.database.save_settings(&settings))
+    }
+
     /// 保存窗口位置（内部实现，使用 StateRefs）
+    ///
+    /// 位置按窗口所在的显示器分别记住，同时更新 window_pos_x/y 作为上次位置。
     async fn save_window_position_internal(state: &StateRefs, window: &tauri::WebviewWindow) -> Result<(), String> {
         let settings = state.settings.lock().await;
 
@@ -403,6 +501,20 @@ impl WindowManager {
         drop(settings);
 
         let position = map_err!(window.outer_position())?;
+        let size = map_err!(window.outer_size())?;
+        let monitors = Self::available_monitors(window)?;
+        if let Some(monitor) = window_geometry::monitor_of_window(
+            &monitors,
+            (position.x, position.y),
+            (size.width as i32, size.height as i32),
+        ) {
+            let mut positions = state.database.get_window_positions().unwrap_or_default();
+            positions.insert(monitor.key(), SavedPosition { x: position.x, y: position.y });
+            if let Err(e) = state.database.save_window_positions(&positions) {
+                eprintln!("保存窗口位置失败: {}", e);
+            }
+        }
+
         let mut settings = state.settings.lock().await;
         settings.window_pos_x = Some(position.x);
         settings.window_pos_y = Some(position.y);
@@ -1,17 +1,13 @@
 import { invoke } from '@tauri-apps/api/core';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
 import { ref, onMounted, onUnmounted } from 'vue';
 import {
-  startListening,
-  stopListening,
-  onClipboardChange,
   writeText,
   writeHTML,
-  writeImage,
   writeFiles,
-  type ReadClipboard,
 } from 'tauri-plugin-clipboard-x-api';
-import type { ClipboardItem, ClipboardContentType, ClipboardMetadata, GetHistoryRequest, SearchRequest, ClearHistoryRequest } from '@/types';
-import { decodeHtmlEntities, stripHtmlAndDecode } from '@/utils/htmlUtils';
+import type { ClipboardItem, GetHistoryRequest, SearchRequest, ClearHistoryRequest } from '@/types';
+import { stripHtmlAndDecode } from '@/utils/htmlUtils';
 
 const history = ref<ClipboardItem[]>([]);
 const isListening = ref(false);
@@ -30,33 +26,31 @@ export function useClipboard() {
     }
   };
 
-  const startClipboardListening = async (): Promise<void> => {
-    try {
-      await startListening();
-      isListening.value = true;
-      console.log('Clipboard listening started');
-    } catch (error) {
-      console.error('Failed to start clipboard listening:', error);
-    }
-  };
-
-  const stopClipboardListening = async (): Promise<void> => {
-    try {
-      await stopListening();
-      isListening.value = false;
-      console.log('Clipboard listening stopped');
-    } catch (error) {
-      console.error('Failed to stop clipboard listening:', error);
-    }
-  };
-
   const setupClipboardListener = (): (() => void) => {
-    let unlisten: (() => void) | null = null;
+    let unlisten: UnlistenFn | null = null;
+    let unlistenUnlocked: UnlistenFn | null = null;
+    let unlistenExpired: UnlistenFn | null = null;
+    let unlistenChanged: UnlistenFn | null = null;
 
+    // 剪贴板由 Rust 后端监听并写入历史，这里只响应新增事件刷新列表
     const initListener = async () => {
-      unlisten = await onClipboardChange(async (result) => {
-        await handleClipboardChange(result);
+      unlisten = await listen<ClipboardItem>('clipboard-item-added', async () => {
+        await handleClipboardItemAdded();
       });
+      // 加密数据库解锁后重新加载历史
+      unlistenUnlocked = await listen('database-unlocked', async () => {
+        await loadHistory();
+      });
+      // 过期的敏感记录被后端删除后，从列表中移除
+      unlistenExpired = await listen<number[]>('clipboard-items-expired', (event) => {
+        const expired = new Set(event.payload);
+        history.value = history.value.filter(item => !expired.has(item.id));
+      });
+      // 外部程序通过 IPC 删除记录或修改标签后重新加载历史
+      unlistenChanged = await listen('clipboard-history-changed', async () => {
+        await loadHistory();
+      });
+      isListening.value = true;
     };
 
     initListener();
@@ -65,74 +59,23 @@ export function useClipboard() {
       if (unlisten) {
         unlisten();
       }
+      if (unlistenUnlocked) {
+        unlistenUnlocked();
+      }
+      if (unlistenExpired) {
+        unlistenExpired();
+      }
+      if (unlistenChanged) {
+        unlistenChanged();
+      }
+      isListening.value = false;
     };
   };
 
-  const handleClipboardChange = async (result: ReadClipboard): Promise<void> => {
+  const handleClipboardItemAdded = async (): Promise<void> => {
     try {
-      // 检查是否是应用内复制（如果是，跳过智能激活的时间记录）
-      const wasInternalCopy = isInternalCopy.value;
-
-      // 优先级: files > image > html > rtf > text
-      if (result.files) {
-        // 文件类型
-        const paths = result.files.value;
-        const contentType: ClipboardContentType = paths.length === 1
-          ? (await isDirectory(paths[0]) ? 'folder' : 'file')
-          : 'files';
-
-        const metadata: ClipboardMetadata = paths.length === 1
-          ? { file_name: getFileName(paths[0]), file_size: result.files.count }
-          : { item_count: paths.length };
-
-        await invoke('add_clipboard_item_extended', {
-          contentType,
-          content: paths.join('\n'),
-          filePaths: paths,
-          metadata,
-          isInternalCopy: wasInternalCopy,
-        });
-      } else if (result.image) {
-        // 图片类型
-        const metadata: ClipboardMetadata = {
-          width: result.image.width,
-          height: result.image.height,
-          format: 'png',
-        };
-
-        await invoke('add_clipboard_item_extended', {
-          contentType: 'image',
-          content: result.image.value,
-          thumbnailPath: result.image.value,
-          metadata,
-          isInternalCopy: wasInternalCopy,
-        });
-      } else if (result.html) {
-        // HTML 类型：存储原始 HTML 内容
-        // 参考 EcoPaste 处理方式：
-        // - content: 原始 HTML
-        // - text: 剪贴板提供的纯文本（用于纯文本粘贴）
-        const htmlContent = result.html.value;
-        // 对纯文本进行 HTML 实体解码（如 &#160; -> 空格）
-        const plainText = decodeHtmlEntities(result.text?.value || '');
-
-        // 存储 HTML 内容，纯文本版本通过内存缓存
-        await invoke('add_clipboard_item', {
-          text: plainText,  // 解码后的纯文本用于预览和纯文本粘贴
-          html: htmlContent,  // 原始 HTML
-          isInternalCopy: wasInternalCopy,
-        });
-      } else if (result.text) {
-        // 纯文本类型
-        await invoke('add_clipboard_item', {
-          text: result.text.value,
-          html: null,
-          isInternalCopy: wasInternalCopy,
-        });
-      }
-
       // 只有在不是应用内复制的情况下，才更新 lastCopyTime（用于智能激活）
-      if (!wasInternalCopy) {
+      if (!isInternalCopy.value) {
         lastCopyTime.value = Date.now();
       }
 
@@ -145,22 +88,6 @@ export function useClipboard() {
     }
   };
 
-  // 辅助函数：检查路径是否为目录
-  const isDirectory = async (path: string): Promise<boolean> => {
-    try {
-      // 简单判断：如果路径没有扩展名，可能是目录
-      const lastPart = path.split(/[/\\]/).pop() || '';
-      return !lastPart.includes('.');
-    } catch {
-      return false;
-    }
-  };
-
-  // 辅助函数：获取文件名
-  const getFileName = (path: string): string => {
-    return path.split(/[/\\]/).pop() || path;
-  };
-
   const searchHistory = async (query: string, limit = 100): Promise<void> => {
     try {
       const request: SearchRequest = { query, limit };
@@ -204,7 +131,8 @@ export function useClipboard() {
 
     for (let attempt = 1; attempt <= maxAttempts; attempt += 1) {
       try {
-        await writeImage(imagePath);
+        // 图片存储可能已加密，由后端解密后写入剪贴板
+        await invoke('write_image_to_clipboard', { path: imagePath });
         return;
       } catch (error) {
         if (attempt === maxAttempts) {
@@ -218,7 +146,7 @@ export function useClipboard() {
   };
 
   const restoreToClipboard = async (item: ClipboardItem, options?: { copyAsPlainText?: boolean }): Promise<void> => {
-    // 标记为应用内复制（这样 handleClipboardChange 就不会更新 lastCopyTime）
+    // 标记为应用内复制（这样 handleClipboardItemAdded 就不会更新 lastCopyTime）
     isInternalCopy.value = true;
 
     // 辅助函数：获取纯文本内容（优先使用 text_content，否则从 content 提取）
@@ -234,6 +162,9 @@ export function useClipboard() {
     };
 
     try {
+      // 通知后端：接下来的剪贴板变化来自应用内复制
+      await invoke('mark_internal_copy');
+
       // 如果需要复制为纯文本，直接使用存储的纯文本内容
       if (options?.copyAsPlainText && (item.content_type === 'html' || item.content_type === 'rtf')) {
         await writeText(getPlainText());
@@ -250,9 +181,9 @@ export function useClipboard() {
           break;
         }
         case 'image':
-          // 图片类型：使用缩略图路径或内容路径
-          if (item.thumbnail_path) {
-            await restoreImageToClipboard(item.thumbnail_path);
+          // 图片类型：content 为原图路径（thumbnail_path 只是列表用的缩略图）
+          if (item.content) {
+            await restoreImageToClipboard(item.content);
           }
           break;
         case 'file':
@@ -276,6 +207,27 @@ export function useClipboard() {
       }, 500);
     }
   };
+  // 由后端写入剪贴板并粘贴，粘贴后恢复用户原来的剪贴板内容
+  const pasteWithRestore = async (
+    item: ClipboardItem,
+    options: { pasteShortcut?: string; copyAsPlainText?: boolean },
+  ): Promise<void> => {
+    isInternalCopy.value = true;
+    try {
+      await invoke('paste_item', {
+        item,
+        pasteShortcut: options.pasteShortcut ?? 'ctrl_v',
+        copyAsPlainText: options.copyAsPlainText ?? false,
+      });
+    } catch (error) {
+      console.error('Failed to paste item:', error);
+    } finally {
+      setTimeout(() => {
+        isInternalCopy.value = false;
+      }, 500);
+    }
+  };
+
 
   const formatRelativeTime = (dateString: string): string => {
     const date = new Date(dateString);
@@ -319,13 +271,11 @@ export function useClipboard() {
   // 初始化函数 - 由组件在 onMounted 中调用
   const init = (): (() => void) => {
     loadHistory();
-    startClipboardListening();
     const cleanupListener = setupClipboardListener();
 
     // 返回清理函数，供组件在 onUnmounted 中调用
     return () => {
       cleanupListener();
-      stopClipboardListening();
     };
   };
 
@@ -335,13 +285,12 @@ export function useClipboard() {
     lastCopyTime,
     init,
     loadHistory,
-    startClipboardListening,
-    stopClipboardListening,
     searchHistory,
     deleteItem,
     clearHistory,
     clearAllItems,
     restoreToClipboard,
+    pasteWithRestore,
     formatRelativeTime,
     getContentPreview,
     getTypeIcon,
@@ -1,4 +1,4 @@
-import type { ClipboardItem } from "@/types";
+import type { ClipboardItem, SnippetError } from "@/types";
 import { decodeHtmlEntities } from "@/utils/htmlUtils";
 import { invoke } from "@tauri-apps/api/core";
 import { writeText } from "tauri-plugin-clipboard-x-api";
@@ -33,6 +33,7 @@ export interface UseClipboardListOptions {
     copy_as_plain_text?: boolean;
     hide_window_after_copy?: boolean;
     paste_shortcut?: string;
+    restore_clipboard_after_paste?: boolean;
     confirm_delete?: boolean;
     click_action?: string;
     double_click_action?: string;
@@ -45,6 +46,10 @@ export interface UseClipboardListOptions {
     item: ClipboardItem,
     options?: { copyAsPlainText?: boolean },
   ) => Promise<void>;
+  pasteWithRestore: (
+    item: ClipboardItem,
+    options: { pasteShortcut?: string; copyAsPlainText?: boolean },
+  ) => Promise<void>;
   resetPanelState?: () => void;
 }
 
@@ -56,6 +61,7 @@ export function useClipboardList(options: UseClipboardListOptions) {
     deleteItem,
     loadHistory,
     restoreToClipboard,
+    pasteWithRestore,
     resetPanelState,
   } = options;
 
@@ -100,6 +106,103 @@ export function useClipboardList(options: UseClipboardListOptions) {
     }
   };
 
+  // 片段展开错误的提示文字
+  const describeSnippetError = (error: unknown): string => {
+    if (typeof error !== "object" || error === null || !("kind" in error)) {
+      return String(error);
+    }
+    const e = error as SnippetError;
+    switch (e.kind) {
+      case "unknown_placeholder":
+        return `未知占位符 {{${e.placeholder}}}（位置 ${e.offset}）`;
+      case "unterminated":
+        return `占位符未闭合（位置 ${e.offset}）`;
+      case "invalid_date_format":
+        return `无效的日期格式 "${e.format}"（位置 ${e.offset}）`;
+      case "empty_input_name":
+        return `input 占位符缺少名称（位置 ${e.offset}）`;
+      case "missing_input":
+        return `缺少输入值 "${e.name}"（位置 ${e.offset}）`;
+      case "duplicate_cursor":
+        return `cursor 占位符只能出现一次（位置 ${e.offset}）`;
+      case "clipboard_unavailable":
+        return `剪贴板中没有可用的文本（位置 ${e.offset}）`;
+      case "other":
+        return e.message;
+    }
+  };
+
+  // 片段需要填写的输入项（{{input:Name}}），用户取消时返回 null
+  const promptSnippetInputs = async (
+    item: ClipboardItem,
+  ): Promise<Record<string, string> | null> => {
+    const names = await invoke<string[]>("get_snippet_inputs", { id: item.id });
+    const inputs: Record<string, string> = {};
+    for (const name of names) {
+      const value = prompt(`请输入「${name}」`);
+      if (value === null) {
+        return null;
+      }
+      inputs[name] = value;
+    }
+    return inputs;
+  };
+
+  // 片段：后端展开占位符后写入剪贴板（不记录到历史），粘贴时光标停在 {{cursor}} 处
+  const executeSnippetAction = async (
+    item: ClipboardItem,
+    action: ClipboardAction,
+    hideWindow: boolean | undefined,
+  ) => {
+    try {
+      const inputs = await promptSnippetInputs(item);
+      if (inputs === null) {
+        return;
+      }
+
+      if (!isPinned.value && (action === "paste" || hideWindow)) {
+        await invoke("hide_clipboard_window");
+        resetPanelState?.();
+      }
+
+      if (action === "paste") {
+        await invoke("paste_snippet", {
+          id: item.id,
+          inputs,
+          pasteShortcut: settings.value.paste_shortcut,
+        });
+      } else {
+        await invoke("copy_snippet", { id: item.id, inputs });
+      }
+    } catch (error) {
+      console.error("Failed to expand snippet:", error);
+      alert(`片段展开失败：${describeSnippetError(error)}`);
+    }
+  };
+
+  // 模拟输入粘贴：后端逐字输入记录的文本（用于屏蔽粘贴的程序），输入过程中按 Esc 取消
+  const typeItem = async (item: ClipboardItem) => {
+    try {
+      let inputs: Record<string, string> | null = null;
+      if (item.content_type === "snippet") {
+        inputs = await promptSnippetInputs(item);
+        if (inputs === null) {
+          return;
+        }
+      }
+
+      if (!isPinned.value) {
+        await invoke("hide_clipboard_window");
+        resetPanelState?.();
+      }
+
+      await invoke("simulate_typing", { id: item.id, inputs });
+    } catch (error) {
+      console.error("Failed to simulate typing:", error);
+      alert(`模拟输入失败：${error}`);
+    }
+  };
+
   // 统一的剪贴板操作入口
   const executeClipboardAction = async (
     item: ClipboardItem,
@@ -114,6 +217,24 @@ export function useClipboardList(options: UseClipboardListOptions) {
       hideWindow = settings.value.hide_window_after_copy,
     } = opts;
 
+    if (item.content_type === "snippet") {
+      await executeSnippetAction(item, action, hideWindow);
+      return;
+    }
+
+    // 粘贴后恢复剪贴板：写入、粘贴和恢复都由后端完成
+    if (action === "paste" && settings.value.restore_clipboard_after_paste) {
+      if (!isPinned.value) {
+        await invoke("hide_clipboard_window");
+        resetPanelState?.();
+      }
+      await pasteWithRestore(item, {
+        pasteShortcut: settings.value.paste_shortcut,
+        copyAsPlainText,
+      });
+      return;
+    }
+
     // 1. 恢复到剪贴板
     await restoreToClipboard(item, { copyAsPlainText });
 
@@ -220,7 +341,9 @@ export function useClipboardList(options: UseClipboardListOptions) {
 
   const handleSaveAsNew = async (content: string, type: string) => {
     try {
-      if (type === "html") {
+      if (type === "snippet") {
+        await invoke("create_snippet", { body: content, tags: null });
+      } else if (type === "html") {
         await invoke("add_clipboard_item", {
           text: content.replace(/<[^>]*>/g, ""),
           html: content,
@@ -315,21 +438,36 @@ export function useClipboardList(options: UseClipboardListOptions) {
         });
         break;
       }
+      case "typePaste":
+        await typeItem(item);
+        break;
+      case "saveAsSnippet":
+        try {
+          await invoke("create_snippet", {
+            body: item.text_content || item.content,
+            tags: item.tags ?? null,
+          });
+          await loadHistory();
+        } catch (error) {
+          console.error("Failed to save as snippet:", error);
+          alert(`保存片段失败：${error}`);
+        }
+        break;
       case "delete":
         await handleDelete(item);
         break;
       case "openFile":
         if (item.file_paths && item.file_paths.length > 0) {
           await invoke("open_file", { path: item.file_paths[0] });
-        } else if (item.thumbnail_path) {
-          await invoke("open_file", { path: item.thumbnail_path });
+        } else if (item.content_type === "image") {
+          await invoke("open_file", { path: item.content });
         }
         break;
       case "showInFolder":
         if (item.file_paths && item.file_paths.length > 0) {
           await invoke("show_in_folder", { path: item.file_paths[0] });
-        } else if (item.thumbnail_path) {
-          await invoke("show_in_folder", { path: item.thumbnail_path });
+        } else if (item.content_type === "image") {
+          await invoke("show_in_folder", { path: item.content });
         }
         break;
       case "copyFilePath":
@@ -14,6 +14,7 @@ const CONTENT_TYPE_MAP: Record<ClipboardContentType, ContentTypeInfo> = {
   file: { label: '文件', icon: '📎', color: '#595959' },
   folder: { label: '文件夹', icon: '📁', color: '#faad14' },
   files: { label: '多文件', icon: '📦', color: '#13c2c2' },
+  snippet: { label: '片段', icon: '🧩', color: '#eb2f96' },
 };
 
 export function useContentType() {
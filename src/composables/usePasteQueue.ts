@@ -1,53 +1,63 @@
 import { ref, computed } from 'vue';
-import type { ClipboardItem } from '@/types';
-
-export type SeparatorType = 'newline' | 'none' | 'custom';
+import { invoke } from '@tauri-apps/api/core';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
+import type { ClipboardItem, PasteQueueState, QueueSeparator } from '@/types';
 
+// 队列由 Rust 后端维护（窗口关闭后依然保留），这里只是 paste-queue-changed 事件的镜像
 const queue = ref<ClipboardItem[]>([]);
-const separator = ref<SeparatorType>('newline');
-const customSeparator = ref('');
-const autoClear = ref(false);
+const separator = ref<QueueSeparator>('none');
+let unlistenQueueChanged: UnlistenFn | null = null;
+
+const applyState = (state: PasteQueueState) => {
+  queue.value = state.items;
+  separator.value = state.separator;
+};
 
 export function usePasteQueue() {
-  const addToQueue = (item: ClipboardItem) => {
-    // Check if item already exists in queue
-    const exists = queue.value.some(q => q.id === item.id);
-    if (!exists) {
-      queue.value.push(item);
+  const init = async () => {
+    if (!unlistenQueueChanged) {
+      unlistenQueueChanged = await listen<PasteQueueState>('paste-queue-changed', (event) => {
+        applyState(event.payload);
+      });
+    }
+    try {
+      applyState(await invoke<PasteQueueState>('get_paste_queue'));
+    } catch (error) {
+      console.error('Failed to load paste queue:', error);
     }
   };
 
-  const removeFromQueue = (id: number) => {
-    const index = queue.value.findIndex(item => item.id === id);
-    if (index > -1) {
-      queue.value.splice(index, 1);
+  const run = async (command: string, args?: Record<string, unknown>) => {
+    try {
+      applyState(await invoke<PasteQueueState>(command, args));
+    } catch (error) {
+      console.error(`Failed to ${command}:`, error);
     }
   };
 
+  const addToQueue = (item: ClipboardItem) => run('push_paste_queue', { ids: [item.id] });
+
+  const removeFromQueue = (id: number) => run('remove_from_paste_queue', { id });
+
   const moveItem = (fromIndex: number, toIndex: number) => {
     if (toIndex < 0 || toIndex >= queue.value.length) return;
-    const item = queue.value.splice(fromIndex, 1)[0];
-    queue.value.splice(toIndex, 0, item);
+    const ids = queue.value.map(item => item.id);
+    const [id] = ids.splice(fromIndex, 1);
+    ids.splice(toIndex, 0, id);
+    return run('reorder_paste_queue', { ids });
   };
 
-  const clearQueue = () => {
-    queue.value = [];
-  };
+  const clearQueue = () => run('clear_paste_queue');
 
-  const getMergedContent = () => {
-    const sep = separator.value === 'custom' 
-      ? customSeparator.value 
-      : separator.value === 'newline' 
-        ? '\n' 
-        : '';
-    
-    return queue.value.map(item => {
-      // Strip HTML for text content
-      if (item.content_type === 'html') {
-        return item.content.replace(/<[^>]*>/g, '');
-      }
-      return item.content;
-    }).join(sep);
+  const setSeparator = (value: QueueSeparator) => run('set_paste_queue_separator', { separator: value });
+
+  const pasteNext = async (): Promise<ClipboardItem | null> => {
+    try {
+      return await invoke<ClipboardItem | null>('paste_next_in_queue');
+    } catch (error) {
+      console.error('Failed to paste next queue item:', error);
+      return null;
+    }
   };
 
   const queueCount = computed(() => queue.value.length);
@@ -59,14 +69,14 @@ export function usePasteQueue() {
   return {
     queue,
     separator,
-    customSeparator,
-    autoClear,
     queueCount,
+    init,
     addToQueue,
     removeFromQueue,
     moveItem,
     clearQueue,
-    getMergedContent,
+    setSeparator,
+    pasteNext,
     isInQueue,
   };
 }
@@ -1,4 +1,4 @@
-import type { ClipboardItem } from "@/types";
+import type { ClipboardItem, SearchResult } from "@/types";
 import { invoke } from "@tauri-apps/api/core";
 import { computed, nextTick, ref, type Ref } from "vue";
 import {
@@ -56,6 +56,7 @@ export function useSearch(options: SearchOptions): SearchState {
       keywords: string[];
       tags: string[];
       types: string[];
+      source_apps: string[];
       limit: number;
       offset: number;
     };
@@ -74,9 +75,11 @@ export function useSearch(options: SearchOptions): SearchState {
             file: "file",
             folder: "folder",
             files: "files",
+            snippet: "snippet",
           };
           return typeMap[t] || "text";
         }),
+        source_apps: parsed.apps,
         limit: ITEMS_PER_PAGE,
         offset: searchOffset.value,
       };
@@ -95,12 +98,13 @@ export function useSearch(options: SearchOptions): SearchState {
       isSearching.value = true;
     }
     try {
-      const results = await invoke<ClipboardItem[]>(
+      const searchResults = await invoke<SearchResult[]>(
         "search_clipboard_advanced",
         {
           request: searchRequest,
         },
       );
+      const results = searchResults.map((result) => result.item);
 
       if (isLoadMore) {
         // 加载更多：追加数据
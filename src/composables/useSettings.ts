@@ -7,11 +7,14 @@ const settings = ref<AppSettings>({
   // 历史记录设置
   max_history_count: 5000,
   auto_cleanup_days: 30,
+  image_gc_interval_hours: 24,
 
   // 窗口设置
   window_position: 'remember',
   window_pos_x: undefined,
   window_pos_y: undefined,
+  window_width: undefined,
+  window_height: undefined,
 
   // 音效设置
   copy_sound: false,
@@ -25,6 +28,10 @@ const settings = ref<AppSettings>({
   click_action: 'copy', // 'copy' | 'paste' | 'none'
   double_click_action: 'paste', // 'copy' | 'paste' | 'none'
   paste_shortcut: 'ctrl_v',
+  restore_clipboard_after_paste: false,
+  restore_clipboard_delay_ms: 300,
+  typing_char_delay_ms: 10,
+  typing_newline: 'enter',
   hide_window_after_copy: false,
   image_ocr: false,
   copy_as_plain_text: false,
@@ -41,6 +48,13 @@ const settings = ref<AppSettings>({
 
   // 钉住模式设置
   pin_shortcut: 'Ctrl+Shift+P',
+
+  // 粘贴队列设置
+  paste_next_shortcut: 'Alt+Shift+V',
+  sensitive_action: 'expire',
+  sensitive_expire_minutes: 5,
+  sensitive_patterns: [],
+  ignored_apps: ['keepassxc', 'keepass', '1password', 'bitwarden', 'enpass'],
 });
 
 // 全局监听器标记
@@ -11,6 +11,8 @@ export interface ParsedQuery {
   tags: string[];
   /** 类型过滤器 */
   types: ClipboardContentType[];
+  /** 来源应用过滤器（app:进程名） */
+  apps: string[];
   /** 原始查询字符串 */
   raw: string;
   /** 是否为有效查询 */
@@ -33,6 +35,8 @@ const TYPE_ALIASES: Record<string, ClipboardContentType[]> = {
   'file': ['file', 'files', 'folder'],
   '文件夹': ['folder'],
   'folder': ['folder'],
+  '片段': ['snippet'],
+  'snippet': ['snippet'],
 };
 
 function isTypeToken(value: string): boolean {
@@ -47,6 +51,7 @@ function isTypeToken(value: string): boolean {
  * - @类型 - 按类型搜索（如@文本、@html、@图片、@文件）
  * - 关键词 @标签 - 组合搜索
  * - 关键词 @类型 - 组合搜索
+ * - app:进程名 - 按来源应用搜索（如 app:firefox）
  * 
  * @param query 原始查询字符串
  * @returns 解析后的查询对象
@@ -60,6 +65,7 @@ export function parseSearchQuery(query: string): ParsedQuery {
       keywords: [],
       tags: [],
       types: [],
+      apps: [],
       raw: '',
       isValid: false,
     };
@@ -68,6 +74,7 @@ export function parseSearchQuery(query: string): ParsedQuery {
   const keywords: string[] = [];
   const tags: string[] = [];
   const types: ClipboardContentType[] = [];
+  const apps: string[] = [];
 
   // 使用正则匹配 @语法
   // @xxx 匹配标签或类型
@@ -103,6 +110,12 @@ export function parseSearchQuery(query: string): ParsedQuery {
     remainingText = remainingText.slice(0, index) + remainingText.slice(index + length);
   }
 
+  // 提取 app:进程名
+  remainingText = remainingText.replace(/(^|\s)app:(\S+)/gi, (_match, prefix: string, app: string) => {
+    apps.push(app);
+    return prefix;
+  });
+
   // 清理并分割关键词
   const cleanedKeywords = remainingText
     .trim()
@@ -114,13 +127,15 @@ export function parseSearchQuery(query: string): ParsedQuery {
   // 去重
   const uniqueTags = [...new Set(tags)];
   const uniqueTypes = [...new Set(types)];
+  const uniqueApps = [...new Set(apps)];
 
   return {
     keywords,
     tags: uniqueTags,
     types: uniqueTypes,
+    apps: uniqueApps,
     raw: trimmed,
-    isValid: keywords.length > 0 || uniqueTags.length > 0 || uniqueTypes.length > 0,
+    isValid: keywords.length > 0 || uniqueTags.length > 0 || uniqueTypes.length > 0 || uniqueApps.length > 0,
   };
 }
 
@@ -170,6 +185,15 @@ export function matchItemWithQuery(
     }
   }
 
+  // 来源应用过滤（不区分大小写，忽略 .exe 后缀）
+  if (query.apps.length > 0) {
+    const normalize = (name: string) => name.toLowerCase().replace(/\.exe$/, '');
+    const source = item.source_app ? normalize(item.source_app.process_name) : null;
+    if (!source || !query.apps.some(app => normalize(app) === source)) {
+      return false;
+    }
+  }
+
   // 关键词过滤
   if (query.keywords.length > 0) {
     // 获取用于搜索的文本内容
@@ -8,7 +8,8 @@ export type ClipboardContentType =
   | 'image'
   | 'file'
   | 'folder'
-  | 'files';
+  | 'files'
+  | 'snippet';
 
 /**
  * 剪贴板元数据
@@ -33,11 +34,38 @@ export interface ClipboardMetadata {
  * 标签定义
  */
 export interface Tag {
-  id: string;
+  id: number;
   name: string;
+  /** 颜色 (#RRGGBB) */
   color: string;
+  /** 使用该标签的记录数 */
+  item_count: number;
+}
+
+/** 粘贴按键组合（typing 表示不发送按键，模拟键盘逐字输入剪贴板中的文本） */
+export type PasteKeystroke = 'ctrl_v' | 'shift_insert' | 'ctrl_shift_v' | 'typing';
+
+/**
+ * 粘贴按键规则（按目标程序选择粘贴按键，按顺序第一条命中的规则生效）
+ */
+export interface PasteRule {
+  id: number;
+  /** 匹配字段：进程名或窗口类名 */
+  field: 'process' | 'window_class';
+  /** 正则表达式（不区分大小写） */
+  pattern: string;
+  keystroke: PasteKeystroke;
+  /** 粘贴前等待（毫秒，最多 2000） */
+  pre_delay_ms: number;
+  /** 粘贴后等待（毫秒，最多 2000） */
+  post_delay_ms: number;
+  enabled: boolean;
+  sort_order: number;
 }
 
+/** 新建/修改粘贴按键规则时提交的内容 */
+export type PasteRuleInput = Omit<PasteRule, 'id' | 'sort_order'>;
+
 /**
  * 剪贴板历史记录项
  */
@@ -54,8 +82,67 @@ export interface ClipboardItem {
   file_paths?: string[];
   thumbnail_path?: string;
   tags?: string[];
+  /** 命中敏感内容规则（限时保存） */
+  is_sensitive?: boolean;
+  /** 过期时间，到期后自动删除 */
+  expires_at?: string;
+  /** 来源应用（复制时的前台窗口） */
+  source_app?: SourceApp;
+}
+
+/**
+ * 剪贴板内容的来源应用
+ */
+export interface SourceApp {
+  /** 进程名，如 firefox、Code.exe */
+  process_name: string;
+  window_title?: string;
+}
+
+/**
+ * 命中敏感内容后的处理方式（off 表示不检测）
+ */
+export type SensitiveAction = 'drop' | 'expire' | 'mask';
+
+/**
+ * 敏感内容命中（start/end 为 UTF-8 字节下标）
+ */
+export interface Finding {
+  rule: string;
+  start: number;
+  end: number;
+}
+
+/**
+ * 敏感内容处理报告（sensitive-content-detected 事件内容）
+ */
+export interface SensitiveReport {
+  rules: string[];
+  action: SensitiveAction;
+  expires_at?: string;
 }
 
+/**
+ * 记录剪贴板内容的结果
+ */
+export interface CaptureOutcome {
+  item: ClipboardItem | null;
+  sensitive: SensitiveReport | null;
+}
+
+/**
+ * 片段展开错误（offset 为占位符在片段正文中的字符下标）
+ */
+export type SnippetError =
+  | { kind: 'unknown_placeholder'; placeholder: string; offset: number }
+  | { kind: 'unterminated'; offset: number }
+  | { kind: 'invalid_date_format'; format: string; offset: number }
+  | { kind: 'empty_input_name'; offset: number }
+  | { kind: 'missing_input'; name: string; offset: number }
+  | { kind: 'duplicate_cursor'; offset: number }
+  | { kind: 'clipboard_unavailable'; offset: number }
+  | { kind: 'other'; message: string };
+
 /**
  * 创建剪贴板项请求
  */
@@ -72,6 +159,27 @@ export interface SearchRequest {
   limit?: number;
 }
 
+/**
+ * 匹配高亮区间（UTF-16 偏移，相对于 snippet）
+ */
+export interface MatchRange {
+  start: number;
+  end: number;
+}
+
+/**
+ * 高级搜索结果
+ */
+export interface SearchResult {
+  item: ClipboardItem;
+  /** bm25 相关度分数（越小越相关） */
+  score?: number;
+  /** 匹配片段 */
+  snippet?: string;
+  /** 片段中的高亮区间 */
+  highlights: MatchRange[];
+}
+
 /**
  * 获取历史记录请求
  */
@@ -96,6 +204,88 @@ export interface ClearHistoryRequest {
   keep_days?: number;
 }
 
+/**
+ * 图片存储垃圾回收报告
+ */
+export interface StorageGcReport {
+  files_scanned: number;
+  files_deleted: number;
+  bytes_reclaimed: number;
+  rows_repaired: number;
+  /** 原图文件已丢失的记录 ID */
+  broken_item_ids: number[];
+}
+
+/**
+ * 归档导出请求（条件之间是 AND 关系，均为空时导出全部记录）
+ */
+export interface ExportRequest {
+  /** 带有任一标签的记录 */
+  tags?: string[];
+  types?: ClipboardContentType[];
+  /** 创建时间范围 [from, to)，RFC 3339 */
+  from?: string;
+  to?: string;
+  /** 是否导出敏感记录（默认不导出） */
+  include_sensitive?: boolean;
+}
+
+/**
+ * 归档导出结果
+ */
+export interface ExportReport {
+  items: number;
+  images: number;
+  /** 原图已丢失而跳过的图片记录数 */
+  missing_images: number;
+}
+
+/**
+ * 导入结果
+ */
+export interface ImportReport {
+  imported: number;
+  images: number;
+  skipped: number;
+}
+
+/** 可导入的其他剪贴板管理器数据 */
+export type ImportSource = 'ditto' | 'copyq' | 'maccy' | 'snippets';
+
+/**
+ * 从其他剪贴板管理器导入的结果
+ */
+export interface ExternalImportReport {
+  source: ImportSource;
+  imported: number;
+  /** 分组、空内容 */
+  skipped: number;
+  /** 格式都不支持的条目 */
+  unsupported: number;
+  /** 不支持的格式说明（去重） */
+  unsupported_reasons: string[];
+  /** 数据损坏或写入失败 */
+  failed: number;
+  images: number;
+}
+
+/** 静态加密密钥来源 */
+export type KeySource = 'passphrase' | 'key_file';
+
+/** 用户提供的密钥材料（key_file 不指定路径时使用应用数据目录下的 clipboard.key） */
+export type KeyMaterial =
+  | { source: 'passphrase'; passphrase: string }
+  | { source: 'key_file'; path: string | null };
+
+/** 静态加密状态 */
+export interface EncryptionStatus {
+  enabled: boolean;
+  key_source: KeySource | null;
+  key_file: string | null;
+  /** 数据库等待解锁（口令模式启动后） */
+  locked: boolean;
+}
+
 /**
  * 应用设置
  */
@@ -103,6 +293,8 @@ export interface AppSettings {
   // 历史记录设置
   max_history_count: number;
   auto_cleanup_days: number;
+  /** 图片存储垃圾回收周期（小时，0 表示不定期回收） */
+  image_gc_interval_hours: number;
 
   // 窗口设置
   window_position: 'remember' | 'center' | 'cursor';
@@ -110,6 +302,10 @@ export interface AppSettings {
   window_pos_x?: number;
   /** 窗口位置 Y 坐标 (remember 模式使用) */
   window_pos_y?: number;
+  /** 窗口宽度（逻辑像素，未设置时使用默认尺寸） */
+  window_width?: number;
+  /** 窗口高度（逻辑像素，未设置时使用默认尺寸） */
+  window_height?: number;
 
   // 音效设置
   copy_sound: boolean;
@@ -122,7 +318,15 @@ export interface AppSettings {
   // 内容设置
   click_action: 'copy' | 'paste' | 'none';
   double_click_action: 'copy' | 'paste' | 'none';
-  paste_shortcut: 'ctrl_v' | 'shift_insert';
+  paste_shortcut: PasteKeystroke;
+  /** 粘贴后恢复原剪贴板内容 */
+  restore_clipboard_after_paste: boolean;
+  /** 粘贴后恢复剪贴板前的等待时间 (毫秒) */
+  restore_clipboard_delay_ms: number;
+  /** 模拟输入时每个字符之间的间隔 (毫秒，0 表示整段输入) */
+  typing_char_delay_ms: number;
+  /** 模拟输入时换行使用的按键 */
+  typing_newline: 'enter' | 'shift_enter';
   hide_window_after_copy: boolean;
   image_ocr: boolean;
   copy_as_plain_text: boolean;
@@ -141,6 +345,36 @@ export interface AppSettings {
   // 钉住模式设置
   /** 切换钉住模式的快捷键 */
   pin_shortcut: string;
+
+  // 粘贴队列设置
+  /** 粘贴队列下一项的快捷键（为空表示不启用） */
+  paste_next_shortcut: string;
+
+  // 敏感内容设置
+  /** 命中敏感内容后的处理方式 */
+  sensitive_action: SensitiveAction | 'off';
+  /** 限时保存的分钟数（sensitive_action 为 expire 时使用） */
+  sensitive_expire_minutes: number;
+  /** 自定义敏感内容正则（每条一个） */
+  sensitive_patterns: string[];
+
+  // 来源应用设置
+  /** 不记录这些应用复制的内容（进程名，不区分大小写） */
+  ignored_apps: string[];
+}
+
+/**
+ * 粘贴队列项之间输入的分隔符
+ */
+export type QueueSeparator = 'none' | 'newline' | 'tab';
+
+/**
+ * 粘贴队列状态（paste-queue-changed 事件内容）
+ */
+export interface PasteQueueState {
+  /** 按粘贴顺序排列的记录 */
+  items: ClipboardItem[];
+  separator: QueueSeparator;
 }
 
 /**
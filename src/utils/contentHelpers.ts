@@ -1,3 +1,4 @@
+import { convertFileSrc } from '@tauri-apps/api/core';
 import type { ClipboardContentType } from '@/types';
 
 const TYPE_LABEL_MAP: Record<ClipboardContentType, string> = {
@@ -8,6 +9,7 @@ const TYPE_LABEL_MAP: Record<ClipboardContentType, string> = {
   file: '文件',
   folder: '文件夹',
   files: '多文件',
+  snippet: '片段',
 };
 
 /**
@@ -69,6 +71,7 @@ export function getTypeIcon(type: ClipboardContentType): string {
     case 'file': return '📎';
     case 'folder': return '📁';
     case 'files': return '📦';
+    case 'snippet': return '🧩';
     default: return '📋';
   }
 }
@@ -86,3 +89,13 @@ export function isDirectory(path: string): boolean {
     return false;
   }
 }
+
+/**
+ * 图片存储中的图片地址（原图/缩略图）
+ * 图片存储可能已加密，经由后端 clipimg 协议解密后加载，不能直接使用 asset 协议
+ * @param path 图片存储中的文件路径
+ * @returns 可用于 img src 的地址
+ */
+export function storedImageSrc(path: string): string {
+  return convertFileSrc(path, 'clipimg');
+}